@@ -0,0 +1,215 @@
+use std::{
+    error::Error,
+    fmt::Write as _,
+    sync::{Arc, RwLock, Weak},
+    time::{Duration, Instant},
+};
+
+use tb::engine::{
+    css,
+    dom::{Append as _, Dom, Node, NodeId, NodeType},
+    stylesheet::Origin,
+};
+
+const SECTIONS: usize = 500;
+const ITEMS_PER_SECTION: usize = 20;
+const ROUNDS: u32 = 10;
+
+/// the queries that are selected in every round, from the cheapest to the most expensive
+const QUERIES: [&str; 6] = [
+    "#s250",
+    ".item",
+    "section > h2",
+    "main section .text",
+    "h2 ~ ul li",
+    "li + li > p",
+];
+
+/// builds a page with `SECTIONS * ITEMS_PER_SECTION` list items, with some text and a link in each
+fn sample_html() -> String {
+    let mut html = String::from("<body><main>");
+    for section in 0..SECTIONS {
+        write!(
+            html,
+            r#"<section id="s{section}" class="section c{}">"#,
+            section % 10
+        )
+        .unwrap();
+        write!(html, "<h2>Section {section}</h2><ul>").unwrap();
+        for item in 0..ITEMS_PER_SECTION {
+            write!(
+                html,
+                r#"<li class="item"><p class="text">item <a href="/{item}">{item}</a></p></li>"#
+            )
+            .unwrap();
+        }
+        html.push_str("</ul></section>");
+    }
+    html.push_str("</main></body>");
+    html
+}
+
+fn sample_css() -> String {
+    let mut css = String::from(
+        ".section { border: 1tb solid; padding: 0 1tb; } \
+         h2 { color: red; } \
+         .item::before { content: '* '; } \
+         li + li > p { color: blue; } \
+         main .text a { color: green; }\n",
+    );
+    for i in 0..10 {
+        writeln!(css, ".c{i} .text {{ padding-left: {i}tb; }}").unwrap();
+    }
+    css
+}
+
+/// the node the dom was made of before the arena, reduced to the tree:
+/// every node is shared behind a lock, and points to its parent weakly
+struct SharedNode {
+    node_type: NodeType,
+    parent: Option<Weak<RwLock<SharedNode>>>,
+    children: Vec<Arc<RwLock<SharedNode>>>,
+}
+
+/// copies the subtree of `id` into shared nodes, the way the dom used to be built
+fn build_shared(
+    dom: &Dom,
+    id: NodeId,
+    parent: Option<&Arc<RwLock<SharedNode>>>,
+) -> Arc<RwLock<SharedNode>> {
+    let node = Arc::new(RwLock::new(SharedNode {
+        node_type: dom[id].node_type.clone(),
+        parent: parent.map(Arc::downgrade),
+        children: vec![],
+    }));
+    for child in dom.children(id) {
+        let child = build_shared(dom, child, Some(&node));
+        node.write().unwrap().children.push(child);
+    }
+    node
+}
+
+/// copies the subtree of `id` into a new arena dom
+fn build_arena(dom: &Dom, id: NodeId) -> Dom {
+    let mut copy = Dom::new("html");
+    let mut stack = vec![(id, copy.root)];
+    while let Some((original, parent)) = stack.pop() {
+        for child in dom.children(original) {
+            let node = Node::new(dom[child].node_type.clone());
+            let copied = copy.node_mut(parent).append_node(node);
+            stack.push((child, copied));
+        }
+    }
+    copy
+}
+
+fn has_class(node_type: &NodeType, class: &str) -> bool {
+    matches!(node_type, NodeType::Element(element) if element.classes().contains(class))
+}
+
+fn has_tag(node_type: &NodeType, tag: &str) -> bool {
+    matches!(node_type, NodeType::Element(element) if element.tag == tag)
+}
+
+/// counts the nodes with `class` inside a `tag`, like `tag .class`, walking up through the weak parents
+fn select_shared(node: &Arc<RwLock<SharedNode>>, tag: &str, class: &str) -> usize {
+    let guard = node.read().unwrap();
+    let mut count = 0;
+    if has_class(&guard.node_type, class) {
+        let mut parent = guard.parent.as_ref().and_then(Weak::upgrade);
+        while let Some(ancestor) = parent {
+            let ancestor = ancestor.read().unwrap();
+            if has_tag(&ancestor.node_type, tag) {
+                count += 1;
+                break;
+            }
+            parent = ancestor.parent.as_ref().and_then(Weak::upgrade);
+        }
+    }
+    for child in &guard.children {
+        count += select_shared(child, tag, class);
+    }
+    count
+}
+
+/// `select_shared` for the arena
+fn select_arena(dom: &Dom, tag: &str, class: &str) -> usize {
+    dom.pre_order(dom.root)
+        .filter(|&id| has_class(&dom[id].node_type, class))
+        .filter(|&id| dom.ancestors(id).any(|a| has_tag(&dom[a].node_type, tag)))
+        .count()
+}
+
+/// runs `f` `ROUNDS` times and returns how long the fastest run took, which is the least noisy
+fn time<T>(mut f: impl FnMut() -> Result<T, Box<dyn Error>>) -> Result<Duration, Box<dyn Error>> {
+    let mut fastest = Duration::MAX;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        f()?;
+        fastest = fastest.min(start.elapsed());
+    }
+    Ok(fastest)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let html = sample_html();
+    let sheet = css::parse_from_str(&sample_css(), Origin::Author)?;
+    println!(
+        "{} sections of {} items, {} bytes of html",
+        SECTIONS,
+        ITEMS_PER_SECTION,
+        html.len()
+    );
+
+    let parse = time(|| Ok(tb::engine::html::parse_from_str(&html)?))?;
+
+    let mut dom: Dom = tb::engine::html::parse_from_str(&html)?;
+    let mut selected = 0;
+    let select = time(|| {
+        selected = 0;
+        for query in QUERIES {
+            selected += dom.query_select(query)?.len();
+        }
+        Ok(())
+    })?;
+
+    dom.stylesheets.push(sheet);
+    let restyle = time(|| Ok(dom.refresh_styles()?))?;
+    // the part of it that doesn't walk the tree much: the cascade of every node
+    let cascade = time(|| Ok(dom.compute_styles()?))?;
+
+    println!("parse:   {parse:?}");
+    println!(
+        "select:  {select:?} ({selected} nodes for {} queries)",
+        QUERIES.len()
+    );
+    println!("restyle: {restyle:?}");
+    println!(
+        "  matching: {:?}, cascade: {cascade:?}",
+        restyle.saturating_sub(cascade)
+    );
+
+    // the same tree, built and walked the way the `Arc<RwLock<Node>>` dom did it
+    let build = time(|| Ok(build_arena(&dom, dom.root)))?;
+    let build_baseline = time(|| Ok(build_shared(&dom, dom.root, None)))?;
+    let shared = build_shared(&dom, dom.root, None);
+    let mut found = (0, 0);
+    let walk = time(|| {
+        found.0 = select_arena(&dom, "main", "text");
+        Ok(())
+    })?;
+    let walk_baseline = time(|| {
+        found.1 = select_shared(&shared, "main", "text");
+        Ok(())
+    })?;
+    assert_eq!(found.0, found.1);
+
+    println!("arena vs Arc<RwLock<Node>>:");
+    println!("  build: {build:?} vs {build_baseline:?}");
+    println!(
+        "  walk:  {walk:?} vs {walk_baseline:?} ({} nodes in `main .text`)",
+        found.0
+    );
+
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use std::{
+    error::Error,
+    fmt::Write as _,
+    time::{Duration, Instant},
+};
+
+use tb::engine::{css, dom::Dom, layout::LayoutManager, stylesheet::Origin};
+
+const SECTIONS: usize = 200;
+const ITEMS_PER_SECTION: usize = 10;
+/// the terminal sizes the page is resized to, in turn
+const SIZES: [(u16, u16); 4] = [(80, 24), (120, 40), (100, 30), (60, 20)];
+const ROUNDS: usize = 5;
+
+/// builds a page with `SECTIONS * ITEMS_PER_SECTION` list items, and an aside in each section
+fn sample_html() -> String {
+    let mut html = String::from("<body><main>");
+    for section in 0..SECTIONS {
+        write!(html, r#"<section class="section c{}">"#, section % 10).unwrap();
+        write!(
+            html,
+            r#"<h2>Section {section}</h2><aside class="note">note {section}</aside><ul>"#
+        )
+        .unwrap();
+        for item in 0..ITEMS_PER_SECTION {
+            write!(
+                html,
+                r#"<li class="item"><p class="text">item {item}</p></li>"#
+            )
+            .unwrap();
+        }
+        html.push_str("</ul></section>");
+    }
+    html.push_str("</main></body>");
+    html
+}
+
+/// only the asides and the headings depend on the viewport
+fn sample_css() -> String {
+    let mut css = String::from(
+        "main { display: block; } \
+         .section { border: 1tb solid; padding: 0 1tb; } \
+         .note { width: 30vw; border: 1tb dashed; } \
+         h2 { width: calc(50vw - 4tb); } \
+         .item::before { content: '* '; } \
+         .text { width: 40tb; }\n",
+    );
+    for i in 0..10 {
+        writeln!(css, ".c{i} .text {{ padding-left: {i}tb; }}").unwrap();
+    }
+    css
+}
+
+fn space(size: (u16, u16)) -> taffy::Size<taffy::AvailableSpace> {
+    taffy::Size {
+        width: taffy::AvailableSpace::Definite(size.0.into()),
+        height: taffy::AvailableSpace::Definite(size.1.into()),
+    }
+}
+
+fn new_dom() -> Result<Dom, Box<dyn Error>> {
+    let mut dom = tb::engine::html::parse_from_str(&sample_html())?;
+    dom.stylesheets
+        .push(css::parse_from_str(&sample_css(), Origin::Author)?);
+    dom.refresh_styles()?;
+    Ok(dom)
+}
+
+/// what a resize used to cost: every style recomputed, and every box built and laid out again
+fn full(dom: &mut Dom) -> Result<Duration, Box<dyn Error>> {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for size in SIZES {
+            dom.viewport = size;
+            dom.compute_styles()?;
+            let mut layout = LayoutManager::new();
+            layout.build(dom)?;
+            layout.compute(space(size))?;
+        }
+    }
+    Ok(start.elapsed())
+}
+
+/// only the styles that depend on the viewport are recomputed, and only their boxes are rebuilt
+fn incremental(dom: &mut Dom) -> Result<Duration, Box<dyn Error>> {
+    let mut layout = LayoutManager::new();
+    layout.build(dom)?;
+    layout.compute(space(dom.viewport))?;
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for size in SIZES {
+            dom.set_viewport(size)?;
+            layout.update(dom)?;
+            layout.compute(space(size))?;
+        }
+    }
+    Ok(start.elapsed())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let resizes = ROUNDS * SIZES.len();
+    println!(
+        "{} sections of {} items, {resizes} resizes",
+        SECTIONS, ITEMS_PER_SECTION
+    );
+
+    let full = full(&mut new_dom()?)?;
+    let incremental = incremental(&mut new_dom()?)?;
+
+    println!(
+        "full restyle + rebuild:   {full:?} ({:?} per resize)",
+        full / resizes as u32
+    );
+    println!(
+        "dirty bits + kept boxes:  {incremental:?} ({:?} per resize)",
+        incremental / resizes as u32
+    );
+    println!(
+        "speedup: {:.1}x",
+        full.as_secs_f64() / incremental.as_secs_f64()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,141 @@
+use std::{error::Error, fmt::Write as _, time::Instant};
+
+use tb::engine::{
+    css,
+    dom::{AppliedStyle, Dom},
+    stylesheet::{self, Origin},
+};
+
+const SECTIONS: usize = 200;
+const ITEMS_PER_SECTION: usize = 10;
+const RULES: usize = 3000;
+
+/// builds a page with `SECTIONS * ITEMS_PER_SECTION` list items, each with a paragraph inside
+fn sample_html() -> String {
+    let mut html = String::from("<body><main>");
+    for section in 0..SECTIONS {
+        write!(
+            html,
+            r#"<section id="s{section}" class="section c{}">"#,
+            section % 50
+        )
+        .unwrap();
+        write!(html, "<h2>Section {section}</h2><ul>").unwrap();
+        for item in 0..ITEMS_PER_SECTION {
+            write!(
+                html,
+                r#"<li class="item c{}"><p class="text">item {item}</p></li>"#,
+                (section + item) % 50
+            )
+            .unwrap();
+        }
+        html.push_str("</ul></section>");
+    }
+    html.push_str("</main></body>");
+    html
+}
+
+/// builds a stylesheet with `RULES` rules, mixing every selector kind
+fn sample_css() -> String {
+    let mut css = String::new();
+    for i in 0..RULES {
+        let selector = match i % 8 {
+            0 => format!(".c{}", i % 97),
+            1 => format!("#s{}", i % (SECTIONS * 2)),
+            2 => format!("#s{} li", i % (SECTIONS * 2)),
+            3 => format!(".c{} > p.text", i % 97),
+            4 => format!("section.c{} .item", i % 97),
+            5 => format!("h2 ~ ul .c{}", i % 97),
+            6 => String::from("ul > li"),
+            _ => format!("main .c{} p", i % 97),
+        };
+        writeln!(css, "{selector} {{ width: {}px; }}", i % 100).unwrap();
+    }
+    css
+}
+
+fn count_applied_styles(dom: &tb::engine::dom::Dom) -> usize {
+    dom.pre_order(dom.root)
+        .map(|node| dom[node].applied_styles.len())
+        .sum()
+}
+
+fn count_nodes(dom: &tb::engine::dom::Dom) -> usize {
+    dom.pre_order(dom.root).count()
+}
+
+/// the old way of doing `refresh_styles`: runs `select` over the whole tree for every rule.
+/// the sample has no `style` attributes, so there are no inline styles to apply
+fn refresh_styles_naive(dom: &mut Dom) -> Result<(), Box<dyn Error>> {
+    let nodes = dom.pre_order(dom.root).collect::<Vec<_>>();
+    for &node in &nodes {
+        dom[node].applied_styles.clear();
+        dom[node].pseudo_applied_styles.clear();
+    }
+
+    let mut applied = vec![];
+    let layer_orders = stylesheet::layer_orders(&dom.stylesheets);
+    let rules = dom
+        .stylesheets
+        .iter()
+        .zip(&layer_orders)
+        .flat_map(|(sheet, layers)| {
+            sheet
+                .rules
+                .iter()
+                .map(move |rule| (sheet.origin, layers[rule.layer], rule))
+        });
+    for (source_order, (origin, layer_order, rule)) in rules.enumerate() {
+        if !rule.media_matches(&dom.device, dom.viewport) {
+            continue;
+        }
+        for node in dom.select(&rule.selector)? {
+            for (k, v) in &rule.props {
+                let style = AppliedStyle {
+                    key: k.clone(),
+                    value: v.clone(),
+                    origin,
+                    rule_specificity: rule.selector.specificity(),
+                    source_order,
+                    layer_order,
+                };
+                applied.push((node, rule.selector.pseudo_element, style));
+            }
+        }
+    }
+    for (node, pseudo, style) in applied {
+        dom[node].applied_styles_mut(pseudo).push(style);
+    }
+
+    Ok(dom.compute_styles()?)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let html = sample_html();
+    let css = sample_css();
+
+    let mut dom = tb::engine::html::parse_from_str(&html)?;
+    dom.stylesheets
+        .push(css::parse_from_str(&css, Origin::Author)?);
+
+    println!("{} nodes, {} rules", count_nodes(&dom), RULES);
+
+    let start = Instant::now();
+    refresh_styles_naive(&mut dom)?;
+    let naive = start.elapsed();
+    let naive_count = count_applied_styles(&dom);
+
+    let start = Instant::now();
+    dom.refresh_styles()?;
+    let hashed = start.elapsed();
+    let hashed_count = count_applied_styles(&dom);
+
+    println!("naive (select per rule): {naive:?}, {naive_count} applied styles");
+    println!("rule hash + bloom:       {hashed:?}, {hashed_count} applied styles");
+    println!(
+        "speedup: {:.1}x",
+        naive.as_secs_f64() / hashed.as_secs_f64()
+    );
+
+    Ok(())
+}
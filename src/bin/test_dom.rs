@@ -1,24 +1,21 @@
-use std::{collections::HashMap, error::Error};
+use std::error::Error;
 
-use tb::engine::dom::{self, Append as _, PrettyPrintTree as _};
+use tb::engine::dom::{Append as _, Dom, PrettyPrintTree as _};
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let html = dom::Node::new(dom::NodeType::Element(dom::ElementData {
-        tag: "html".into(),
-        attrs: HashMap::new(),
-    }))
-    .into_shared();
+    let mut dom = Dom::new("html");
+    let html = dom.node_mut(dom.root).append_element("html", None);
 
-    let head = html.append_element("head", None)?;
-    let title = head.append_element("title", None)?;
-    title.append_text("MY WWBSITE")?;
+    let head = dom.node_mut(html).append_element("head", None);
+    let title = dom.node_mut(head).append_element("title", None);
+    dom.node_mut(title).append_text("MY WWBSITE");
 
-    let body = html.append_element("body", None)?;
+    let body = dom.node_mut(html).append_element("body", None);
 
-    body.append_text("lorem ipsum")?;
-    body.append_comment("lorem ipsum commentum")?;
+    dom.node_mut(body).append_text("lorem ipsum");
+    dom.node_mut(body).append_comment("lorem ipsum commentum");
 
-    let _ = html.pretty_print_tree(0);
+    dom.node(html).pretty_print_tree(0);
 
     Ok(())
 }
@@ -11,7 +11,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     dbg!(dom.query_select("body>div>p")?.len());
     dbg!(dom.query_select("h1 ~ div")?.len());
     dbg!(dom.query_select("h1 + div")?.len());
-    dom.root.pretty_print_tree(0)?;
+    dom.node(dom.root).pretty_print_tree(0);
 
     Ok(())
 }
@@ -1,14 +1,69 @@
+use std::{env, fs};
+
 use color_eyre::eyre::Result;
 use ratatui::{
-    crossterm::event,
-    layout::{Constraint, Flex, Layout, Rect},
-    widgets::{self, Block, Clear, Paragraph, Wrap},
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
     DefaultTerminal, Frame,
 };
+use taffy::Point;
+use tb::engine::{
+    css,
+    dom::Dom,
+    html,
+    layout::LayoutManager,
+    render::{self, Glyphs, Viewport},
+    scroll::{self, ScrollFocus},
+    stylesheet::Origin,
+};
+
+/// the page that's shown when no file is given, with a sidebar and a code block that scroll on their own
+const SAMPLE_HTML: &str = r#"
+<div id="page">
+    <nav id="sidebar">
+        <p>Contents</p><p>Intro</p><p>Usage</p><p>Keys</p><p>Scrolling</p><p>Layout</p><p>Tables</p><p>Borders</p>
+    </nav>
+    <main>
+        <h1>tb</h1>
+        <p>Tab and Shift+Tab move the focus between the boxes that scroll, the arrows scroll it.</p>
+        <pre id="code">fn main() {
+    let mut dom = html::parse_from_str(input)?;
+    dom.refresh_styles()?;
+    let mut layout = LayoutManager::new();
+    layout.build(&mut dom)?;
+    layout.compute(size)?;
+    render(&dom, &layout, viewport, Glyphs::Unicode, buffer)?;
+}</pre>
+        <p>With the focus on the page, or once a box is scrolled to its end, the page scrolls instead.</p>
+        <p>One</p><p>Two</p><p>Three</p><p>Four</p><p>Five</p><p>Six</p><p>Seven</p><p>Eight</p>
+    </main>
+</div>
+"#;
+
+const SAMPLE_CSS: &str = r#"
+#page { display: flex; }
+#sidebar { width: 16tb; flex-shrink: 0; height: 6tb; overflow: auto; border: 1tb solid; }
+main { flex-grow: 1; flex-basis: 0; min-width: 0; padding: 0 1tb; }
+#code { display: block; white-space: pre; height: 5tb; overflow: auto; border: 1tb solid; }
+"#;
 
 fn main() -> Result<()> {
     color_eyre::install()?;
-    let mut app = App { counter: 0 };
+    // a page to show can be given instead of the sample
+    let (html_input, css_input) = match env::args().nth(1) {
+        Some(path) => (fs::read_to_string(path)?, String::new()),
+        None => (SAMPLE_HTML.to_string(), SAMPLE_CSS.to_string()),
+    };
+    let mut dom = html::parse_from_str(&html_input)?;
+    dom.stylesheets
+        .push(css::parse_from_str(&css_input, Origin::Author)?);
+    dom.refresh_styles()?;
+
+    let mut app = App {
+        dom,
+        layout: LayoutManager::new(),
+        focus: ScrollFocus::default(),
+        scroll: Point::ZERO,
+    };
     let terminal = ratatui::init();
     let result = app.run(terminal);
     ratatui::restore();
@@ -16,42 +71,109 @@ fn main() -> Result<()> {
 }
 
 struct App {
-    counter: u64,
+    dom: Dom,
+    layout: LayoutManager,
+    /// the scroll container that the keys scroll, or the page
+    focus: ScrollFocus,
+    /// how far the page is scrolled
+    scroll: Point<f32>,
 }
 
 impl App {
     fn run(&mut self, mut terminal: DefaultTerminal) -> Result<()> {
         loop {
-            terminal.draw(|f| self.render(f))?;
-            if let event::Event::Key(key_event) = event::read()? {
-                match key_event.code {
-                    event::KeyCode::Char('q') => break Ok(()),
-                    event::KeyCode::Char(' ') => self.counter += 1,
-                    event::KeyCode::Backspace => self.counter -= 1,
-                    event::KeyCode::Esc => break Ok(()),
-                    _ => {}
+            let size = terminal.size()?;
+            self.lay_out((size.width, size.height))?;
+            let mut drawn = Ok(());
+            terminal.draw(|frame| drawn = self.render(frame))?;
+            drawn?;
+
+            // a resize is picked up by the next layout
+            let Event::Key(key_event) = event::read()? else {
+                continue;
+            };
+            if key_event.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key_event.code {
+                KeyCode::Char('q') | KeyCode::Esc => break Ok(()),
+                KeyCode::Tab => self.focus.next(&self.layout)?,
+                KeyCode::BackTab => self.focus.previous(&self.layout)?,
+                code => {
+                    if let Some(delta) = scroll_delta(code, size.height) {
+                        self.scroll_by(delta)?;
+                    }
                 }
             }
         }
     }
 
-    fn render(&self, frame: &mut Frame) {
-        let area = center(
-            frame.area(),
-            Constraint::Percentage(20),
-            Constraint::Length(3), // top and bottom border + content
-        );
-        let popup =
-            Paragraph::new(format!("{}", self.counter)).block(Block::bordered().title("Popup"));
-        frame.render_widget(Clear, area);
-        frame.render_widget(popup, area);
+    /// lays the page out again for a terminal of `size`, which only redoes what changed
+    fn lay_out(&mut self, size: (u16, u16)) -> Result<()> {
+        if self.dom.viewport != size {
+            self.dom.set_viewport(size)?;
+        }
+        self.dom.update_styles()?;
+        self.layout.update(&mut self.dom)?;
+        self.layout.compute(taffy::Size {
+            width: taffy::AvailableSpace::Definite(size.0.into()),
+            height: taffy::AvailableSpace::Definite(size.1.into()),
+        })?;
+        self.scroll = self.clamp_page_scroll(self.scroll)?;
+        Ok(())
+    }
+
+    /// scrolls the focused container, and the page with what it couldn't scroll
+    fn scroll_by(&mut self, delta: Point<f32>) -> Result<()> {
+        let rest = self.focus.scroll(&mut self.dom, &self.layout, delta)?;
+        let wanted = Point {
+            x: self.scroll.x + rest.x,
+            y: self.scroll.y + rest.y,
+        };
+        self.scroll = self.clamp_page_scroll(wanted)?;
+        Ok(())
+    }
+
+    /// the page can be scrolled up to the end of its content
+    fn clamp_page_scroll(&self, offset: Point<f32>) -> Result<Point<f32>> {
+        let Some(root) = self.layout.root else {
+            return Ok(Point::ZERO);
+        };
+        let max = scroll::max_offset(self.layout.tree.layout(root)?);
+        Ok(Point {
+            x: offset.x.round().clamp(0.0, max.x),
+            y: offset.y.round().clamp(0.0, max.y),
+        })
+    }
+
+    fn render(&self, frame: &mut Frame) -> tb::engine::Result<()> {
+        let viewport = Viewport {
+            area: frame.area(),
+            scroll: self.scroll,
+        };
+        render::render(
+            &self.dom,
+            &self.layout,
+            viewport,
+            Glyphs::Unicode,
+            frame.buffer_mut(),
+        )
     }
 }
 
-fn center(area: Rect, horizontal: Constraint, vertical: Constraint) -> Rect {
-    let [area] = Layout::horizontal([horizontal])
-        .flex(Flex::Center)
-        .areas(area);
-    let [area] = Layout::vertical([vertical]).flex(Flex::Center).areas(area);
-    area
+/// how far a key scrolls, in cells: a line or a column with the arrows, a screen with the page keys
+fn scroll_delta(code: KeyCode, height: u16) -> Option<Point<f32>> {
+    let page = f32::from(height.saturating_sub(1).max(1));
+    let (x, y) = match code {
+        KeyCode::Up | KeyCode::Char('k') => (0.0, -1.0),
+        KeyCode::Down | KeyCode::Char('j') => (0.0, 1.0),
+        KeyCode::Left | KeyCode::Char('h') => (-1.0, 0.0),
+        KeyCode::Right | KeyCode::Char('l') => (1.0, 0.0),
+        KeyCode::PageUp => (0.0, -page),
+        KeyCode::PageDown | KeyCode::Char(' ') => (0.0, page),
+        KeyCode::Home => (0.0, f32::MIN),
+        KeyCode::End => (0.0, f32::MAX),
+        _ => return None,
+    };
+    Some(Point { x, y })
 }
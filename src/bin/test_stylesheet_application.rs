@@ -1,8 +1,9 @@
 use std::error::Error;
 
 use tb::engine::{
-    dom::{shared_node, AskStyle, Parent},
-    stylesheet::{Origin, Value},
+    dom::{AskStyle, Parent},
+    layout::LayoutManager,
+    stylesheet::Origin,
 };
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -64,19 +65,25 @@ fn main() -> Result<(), Box<dyn Error>> {
     dom.apply_stylesheet(sheet_agent)?;
     dom.apply_stylesheet(sheet_author)?;
 
-    let node = dom.query_select(".half-as-wide>.half-as-wide")?[0].clone();
-    let width = node.ask_style("width")?.unwrap();
+    let node = dom.node(dom.query_select(".half-as-wide>.half-as-wide")?[0]);
+    let width = node.ask_style("width").unwrap();
     dbg!(&width);
 
-    let Value::Dimension(dim) = width.value.value else {
-        unreachable!()
-    };
+    let parent = node.parent().unwrap();
 
-    let parent = node.parent()?;
+    dbg!(parent.ask_style("width"));
+    let (node, parent) = (node.id, parent.id);
 
-    dbg!(parent.ask_style("width"))?;
+    // percentages are resolved against the containing block during layout
+    let mut layout = LayoutManager::new();
+    layout.build(&mut dom)?;
+    layout.compute(taffy::Size {
+        width: taffy::AvailableSpace::Definite(120.0),
+        height: taffy::AvailableSpace::Definite(60.0),
+    })?;
 
-    dbg!(dim.as_tb(&parent, "width", (120, 60)))?;
+    dbg!(layout.get(parent)?.size);
+    dbg!(layout.get(node)?.size);
 
     // dbg!(dom.root);
 
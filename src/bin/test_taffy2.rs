@@ -1,15 +1,18 @@
 use std::fs;
 
-use tb::engine::{self, dom::Select};
+use tb::engine;
 
-fn main() -> Result<(), taffy::TaffyError> {
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     let input = fs::read_to_string("samples/helloweb/index.html").unwrap();
-    let dom = tb::engine::html::parse_from_str(&input).unwrap();
+    let mut dom = tb::engine::html::parse_from_str(&input)?;
+    dom.refresh_styles()?;
 
     let mut layout = engine::layout::LayoutManager::new();
-    layout.build(dom.root.clone()).unwrap();
-    let title = dom.query_select("#title").unwrap()[0].clone();
-    let l = layout.get(title).unwrap();
-    dbg!(l);
+    layout.build(&mut dom)?;
+    layout.compute(taffy::Size {
+        width: taffy::AvailableSpace::Definite(80.0),
+        height: taffy::AvailableSpace::Definite(24.0),
+    })?;
+    print!("{}", layout.dump_text(&dom)?);
     Ok(())
 }
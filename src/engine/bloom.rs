@@ -0,0 +1,138 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use super::dom::ElementData;
+
+const KEY_BITS: u32 = 12;
+const KEY_MASK: u32 = (1 << KEY_BITS) - 1;
+const ARRAY_SIZE: usize = 1 << KEY_BITS;
+
+/// A counting bloom filter containing the ids, classes and tags of the ancestors
+/// of the node that is currently being styled.
+///
+/// It is used to quickly reject rules with descendant or child combinators
+/// that could never match, without walking up the tree.
+/// False positives are possible, false negatives are not.
+#[derive(Debug, Clone)]
+pub struct AncestorBloom {
+    counters: Box<[u8; ARRAY_SIZE]>,
+}
+
+impl AncestorBloom {
+    pub fn new() -> Self {
+        Self {
+            counters: Box::new([0; ARRAY_SIZE]),
+        }
+    }
+
+    pub fn insert_hash(&mut self, hash: u32) {
+        for index in indexes(hash) {
+            let counter = &mut self.counters[index];
+            // a saturated counter must never be decremented again, so it just sticks
+            *counter = counter.saturating_add(1);
+        }
+    }
+
+    pub fn remove_hash(&mut self, hash: u32) {
+        for index in indexes(hash) {
+            let counter = &mut self.counters[index];
+            if *counter != u8::MAX {
+                *counter = counter.saturating_sub(1);
+            }
+        }
+    }
+
+    pub fn might_contain_hash(&self, hash: u32) -> bool {
+        indexes(hash).iter().all(|index| self.counters[*index] != 0)
+    }
+
+    /// adds the id, classes and tag of `element` to the filter
+    pub fn push_element(&mut self, element: &ElementData) {
+        for hash in element_hashes(element) {
+            self.insert_hash(hash);
+        }
+    }
+
+    /// removes what `push_element` added.
+    /// must be called with the same element, in reverse order of pushing
+    pub fn pop_element(&mut self, element: &ElementData) {
+        for hash in element_hashes(element) {
+            self.remove_hash(hash);
+        }
+    }
+}
+
+impl Default for AncestorBloom {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn indexes(hash: u32) -> [usize; 2] {
+    [
+        (hash & KEY_MASK) as usize,
+        ((hash >> KEY_BITS) & KEY_MASK) as usize,
+    ]
+}
+
+fn hash_with_kind(kind: u8, value: &str) -> u32 {
+    let mut hasher = DefaultHasher::new();
+    kind.hash(&mut hasher);
+    value.hash(&mut hasher);
+    hasher.finish() as u32
+}
+
+pub fn hash_id(id: &str) -> u32 {
+    hash_with_kind(b'#', id)
+}
+
+pub fn hash_class(class: &str) -> u32 {
+    hash_with_kind(b'.', class)
+}
+
+pub fn hash_tag(tag: &str) -> u32 {
+    hash_with_kind(b't', tag)
+}
+
+fn element_hashes(element: &ElementData) -> Vec<u32> {
+    let mut hashes = vec![hash_tag(&element.tag)];
+    if let Some(id) = element.id() {
+        hashes.push(hash_id(id));
+    }
+    hashes.extend(element.classes().into_iter().map(hash_class));
+    hashes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn push_and_pop() {
+        let div = ElementData::new(
+            "div",
+            Some(HashMap::from([
+                ("id".into(), "main".into()),
+                ("class".into(), "a b".into()),
+            ])),
+        );
+
+        let mut bloom = AncestorBloom::new();
+        assert!(!bloom.might_contain_hash(hash_tag("div")));
+
+        bloom.push_element(&div);
+        assert!(bloom.might_contain_hash(hash_tag("div")));
+        assert!(bloom.might_contain_hash(hash_id("main")));
+        assert!(bloom.might_contain_hash(hash_class("a")));
+        assert!(bloom.might_contain_hash(hash_class("b")));
+
+        bloom.push_element(&div);
+        bloom.pop_element(&div);
+        assert!(bloom.might_contain_hash(hash_class("b")));
+
+        bloom.pop_element(&div);
+        assert!(!bloom.might_contain_hash(hash_tag("div")));
+        assert!(!bloom.might_contain_hash(hash_id("main")));
+    }
+}
@@ -0,0 +1,278 @@
+use std::sync::Arc;
+
+use super::stylesheet::{Dimension, DimensionError, FontMetrics, Unit};
+
+/// The expression inside `calc()`, `min()`, `max()` or `clamp()`.
+///
+/// Units are only resolved when the expression is evaluated,
+/// so that `calc(50vw - 10px)` can mix viewport and absolute lengths.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MathExpr {
+    Value(Dimension),
+    Add(Box<MathExpr>, Box<MathExpr>),
+    Sub(Box<MathExpr>, Box<MathExpr>),
+    Mul(Box<MathExpr>, Box<MathExpr>),
+    Div(Box<MathExpr>, Box<MathExpr>),
+    Min(Vec<MathExpr>),
+    Max(Vec<MathExpr>),
+    /// `clamp(min, value, max)`
+    Clamp(Box<MathExpr>, Box<MathExpr>, Box<MathExpr>),
+}
+
+/// the result of evaluating (part of) an expression.
+/// lengths are already in tb units, and not rounded yet
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Evaluated {
+    Number(f64),
+    Length(f64),
+}
+
+impl MathExpr {
+    /// evaluates an expression that must resolve to a length, eg. `width: calc(100% - 2rem)`.
+    /// the result is only rounded at the end, like the other `as_tb` methods.
+    ///
+    /// percentages are relative to `basis`, and fail with `DimensionError::Relative` without one
+    pub fn as_tb(
+        &self,
+        basis: Option<f64>,
+        viewport: (u16, u16),
+        font: FontMetrics,
+    ) -> Result<Dimension, DimensionError> {
+        Ok(Dimension {
+            value: self.tb_value(basis, viewport, font)?.round(),
+            unit: Unit::Tb,
+        })
+    }
+
+    /// like `as_tb`, but not rounded
+    pub(super) fn tb_value(
+        &self,
+        basis: Option<f64>,
+        viewport: (u16, u16),
+        font: FontMetrics,
+    ) -> Result<f64, DimensionError> {
+        match self.eval(basis, viewport, font)? {
+            Evaluated::Length(value) => Ok(value),
+            Evaluated::Number(_) => Err(DimensionError::InvalidMath),
+        }
+    }
+
+    /// whether it depends on the viewport, through viewport units
+    pub fn is_viewport_relative(&self) -> bool {
+        self.any_unit(&|unit| matches!(unit, Unit::Vw | Unit::Vh))
+    }
+
+    /// whether it has percentages, which are only known in layout, see `CalcLength`
+    pub fn has_percentages(&self) -> bool {
+        self.any_unit(&|unit| unit == Unit::Percent)
+    }
+
+    fn any_unit(&self, f: &dyn Fn(Unit) -> bool) -> bool {
+        match self {
+            MathExpr::Value(dimension) => f(dimension.unit),
+            MathExpr::Add(lhs, rhs)
+            | MathExpr::Sub(lhs, rhs)
+            | MathExpr::Mul(lhs, rhs)
+            | MathExpr::Div(lhs, rhs) => lhs.any_unit(f) || rhs.any_unit(f),
+            MathExpr::Min(args) | MathExpr::Max(args) => args.iter().any(|arg| arg.any_unit(f)),
+            MathExpr::Clamp(min, value, max) => {
+                min.any_unit(f) || value.any_unit(f) || max.any_unit(f)
+            }
+        }
+    }
+
+    /// the same expression with its lengths in tb, except for percentages
+    fn resolve_units(
+        &self,
+        viewport: (u16, u16),
+        font: FontMetrics,
+    ) -> Result<MathExpr, DimensionError> {
+        let resolve = |expr: &MathExpr| expr.resolve_units(viewport, font).map(Box::new);
+        let resolve_all = |args: &[MathExpr]| {
+            args.iter()
+                .map(|arg| arg.resolve_units(viewport, font))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(match self {
+            MathExpr::Value(dimension) => match dimension.unit {
+                Unit::Unitless | Unit::Percent => MathExpr::Value(*dimension),
+                _ => MathExpr::Value(Dimension {
+                    value: dimension.tb_value(None, viewport, font)?,
+                    unit: Unit::Tb,
+                }),
+            },
+            MathExpr::Add(lhs, rhs) => MathExpr::Add(resolve(lhs)?, resolve(rhs)?),
+            MathExpr::Sub(lhs, rhs) => MathExpr::Sub(resolve(lhs)?, resolve(rhs)?),
+            MathExpr::Mul(lhs, rhs) => MathExpr::Mul(resolve(lhs)?, resolve(rhs)?),
+            MathExpr::Div(lhs, rhs) => MathExpr::Div(resolve(lhs)?, resolve(rhs)?),
+            MathExpr::Min(args) => MathExpr::Min(resolve_all(args)?),
+            MathExpr::Max(args) => MathExpr::Max(resolve_all(args)?),
+            MathExpr::Clamp(min, value, max) => {
+                MathExpr::Clamp(resolve(min)?, resolve(value)?, resolve(max)?)
+            }
+        })
+    }
+
+    /// evaluates an expression that must resolve to a plain number, eg. `flex-grow: calc(1 + 1)`
+    pub fn as_number(&self) -> Result<f64, DimensionError> {
+        match self.eval(None, (0, 0), FontMetrics::default())? {
+            Evaluated::Number(value) => Ok(value),
+            Evaluated::Length(_) => Err(DimensionError::InvalidMath),
+        }
+    }
+
+    fn eval(
+        &self,
+        basis: Option<f64>,
+        viewport: (u16, u16),
+        font: FontMetrics,
+    ) -> Result<Evaluated, DimensionError> {
+        use Evaluated::{Length, Number};
+
+        let eval = |expr: &MathExpr| expr.eval(basis, viewport, font);
+
+        Ok(match self {
+            MathExpr::Value(dimension) => match dimension.unit {
+                Unit::Unitless => Number(dimension.value),
+                _ => Length(dimension.tb_value(basis, viewport, font)?),
+            },
+            MathExpr::Add(lhs, rhs) => same_kind(eval(lhs)?, eval(rhs)?, |a, b| a + b)?,
+            MathExpr::Sub(lhs, rhs) => same_kind(eval(lhs)?, eval(rhs)?, |a, b| a - b)?,
+            MathExpr::Mul(lhs, rhs) => match (eval(lhs)?, eval(rhs)?) {
+                (Number(a), Number(b)) => Number(a * b),
+                (Length(a), Number(b)) | (Number(b), Length(a)) => Length(a * b),
+                (Length(_), Length(_)) => return Err(DimensionError::InvalidMath),
+            },
+            MathExpr::Div(lhs, rhs) => match (eval(lhs)?, eval(rhs)?) {
+                (_, Number(0.0)) => return Err(DimensionError::InvalidMath),
+                (Number(a), Number(b)) => Number(a / b),
+                (Length(a), Number(b)) => Length(a / b),
+                (_, Length(_)) => return Err(DimensionError::InvalidMath),
+            },
+            MathExpr::Min(args) => fold(args, eval, f64::min)?,
+            MathExpr::Max(args) => fold(args, eval, f64::max)?,
+            MathExpr::Clamp(min, value, max) => {
+                let value = same_kind(eval(value)?, eval(max)?, f64::min)?;
+                same_kind(eval(min)?, value, f64::max)?
+            }
+        })
+    }
+}
+
+/// A length given by a math function with percentages, like `calc(100% - 2tb)`.
+///
+/// taffy can't evaluate math functions, so it's kept as an expression until layout,
+/// where it's resolved against the size of the containing block, see `LayoutManager::compute`.
+/// its other units are already in tb, since they depend on the element's font and the viewport
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalcLength(Arc<MathExpr>);
+
+impl CalcLength {
+    /// fails like `MathExpr::as_tb` would, for any basis
+    pub fn new(
+        expr: &MathExpr,
+        viewport: (u16, u16),
+        font: FontMetrics,
+    ) -> Result<Self, DimensionError> {
+        let expr = expr.resolve_units(viewport, font)?;
+        // whether it's a valid length doesn't depend on what the percentages are relative to
+        expr.tb_value(Some(0.0), viewport, font)?;
+        Ok(Self(Arc::new(expr)))
+    }
+
+    /// the length in tb, rounded, with percentages relative to `basis`
+    pub fn resolve(&self, basis: f32) -> f32 {
+        let value = self
+            .0
+            .tb_value(Some(basis.into()), (0, 0), FontMetrics::default());
+        value.map_or(0.0, |value| value.round() as f32)
+    }
+}
+
+/// combines two values that must both be numbers or both be lengths
+fn same_kind(
+    lhs: Evaluated,
+    rhs: Evaluated,
+    f: impl Fn(f64, f64) -> f64,
+) -> Result<Evaluated, DimensionError> {
+    match (lhs, rhs) {
+        (Evaluated::Number(a), Evaluated::Number(b)) => Ok(Evaluated::Number(f(a, b))),
+        (Evaluated::Length(a), Evaluated::Length(b)) => Ok(Evaluated::Length(f(a, b))),
+        _ => Err(DimensionError::InvalidMath),
+    }
+}
+
+fn fold(
+    args: &[MathExpr],
+    eval: impl Fn(&MathExpr) -> Result<Evaluated, DimensionError>,
+    f: fn(f64, f64) -> f64,
+) -> Result<Evaluated, DimensionError> {
+    let (first, rest) = args.split_first().ok_or(DimensionError::InvalidMath)?;
+    rest.iter()
+        .try_fold(eval(first)?, |acc, arg| same_kind(acc, eval(arg)?, f))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{css, stylesheet::Value};
+
+    fn eval(raw: &str, viewport: (u16, u16)) -> Result<f64, DimensionError> {
+        match css::parse_value_str(raw) {
+            Some(Value::Math(expr)) => expr
+                .as_tb(None, viewport, FontMetrics::default())
+                .map(|d| d.value),
+            other => panic!("{raw} didn't parse as a math function: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn evaluation() {
+        assert_eq!(eval("calc(50vw - 10px)", (80, 24)).unwrap(), 39.0);
+        assert_eq!(eval("calc(1tb + 2tb * 3)", (80, 24)).unwrap(), 7.0);
+        assert_eq!(eval("calc((1tb + 2tb) * 3)", (80, 24)).unwrap(), 9.0);
+        assert_eq!(eval("calc(10tb / 4)", (80, 24)).unwrap(), 3.0);
+        assert_eq!(eval("CALC(2 * 5tb)", (80, 24)).unwrap(), 10.0);
+        assert_eq!(eval("min(10tb, 50vw, 1in)", (80, 24)).unwrap(), 10.0);
+        assert_eq!(eval("max(10tb, calc(50vw + 1tb))", (80, 24)).unwrap(), 41.0);
+        assert_eq!(eval("clamp(5tb, 50vw, 20tb)", (80, 24)).unwrap(), 20.0);
+        assert_eq!(eval("clamp(5tb, 50vw, 20tb)", (4, 24)).unwrap(), 5.0);
+        assert_eq!(eval("clamp(5tb, 50vw, 20tb)", (20, 24)).unwrap(), 10.0);
+
+        for invalid in [
+            "calc(1tb * 2tb)",
+            "calc(1tb + 2)",
+            "calc(1tb / 0)",
+            "calc(2 / 1tb)",
+            "calc(1 + 2)",
+            "min(1tb, 2)",
+        ] {
+            assert!(
+                matches!(eval(invalid, (80, 24)), Err(DimensionError::InvalidMath)),
+                "{invalid} should be invalid"
+            );
+        }
+        assert!(matches!(
+            eval("calc(100% - 1tb)", (80, 24)),
+            Err(DimensionError::Relative)
+        ));
+    }
+
+    #[test]
+    fn calc_lengths() {
+        let calc = |raw: &str| match css::parse_value_str(raw) {
+            Some(Value::Math(expr)) => CalcLength::new(&expr, (80, 24), FontMetrics::default()),
+            other => panic!("{raw} didn't parse as a math function: {other:?}"),
+        };
+
+        // the viewport and font units are resolved right away, the percentages later
+        let width = calc("calc(100% - 2rem - 10vw)").unwrap();
+        assert_eq!(width.resolve(40.0), 28.0);
+        assert_eq!(width.resolve(20.0), 8.0);
+        assert_eq!(calc("clamp(5tb, 50%, 20tb)").unwrap().resolve(100.0), 20.0);
+        assert!(matches!(
+            calc("calc(100% * 1tb)"),
+            Err(DimensionError::InvalidMath)
+        ));
+    }
+}
@@ -0,0 +1,1743 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::Arc,
+};
+
+use super::{
+    calc::CalcLength,
+    computed_style::{
+        BorderCollapse, BorderStyle, CaptionSide, ComputedStyle, Content, ContentItem, Display,
+        Edges, FontStyle, Length, LengthAuto, Overflow, Position, Quotes, ScrollbarWidth,
+        TextAlign, TextDecoration, TextTransform, Visibility, WhiteSpace,
+    },
+    counters::CounterStyle,
+    css,
+    custom_properties::{self, CustomProperties, CustomResolver},
+    dom::{is_inherited, AppliedStyle},
+    shorthands,
+    stylesheet::{Color, FontMetrics, Origin, Unit, Value, PX_TO_TB, STYLE_ATTRIBUTE_LAYER},
+};
+
+/// whether `key: raw` is a declaration we understand, for `@supports`.
+///
+/// the property has to be one that ends up in `ComputedStyle`,
+/// and the value has to be valid for it.
+pub fn supports_declaration(key: &str, raw: &str) -> bool {
+    if key.starts_with("--") {
+        return true;
+    }
+    // invalid values are dropped when parsing, see `is_valid_value`
+    let declarations = css::parse_declaration_str(key, raw);
+    let cascade = Cascade::validator();
+    !declarations.is_empty()
+        && declarations
+            .iter()
+            .all(|(key, declared)| match &declared.value {
+                // values with `var()` are only checked once they're substituted
+                value @ Value::Raw(_) => {
+                    shorthands::is_shorthand(key) || cascade.accepts(key, value).is_some()
+                }
+                value => value.is_css_wide_keyword() || cascade.accepts(key, value) == Some(true),
+            })
+}
+
+/// whether `value` is valid for `key`, so that the declaration isn't dropped when parsing.
+/// it's converted the same way as when it's computed, see `Cascade::accepts`.
+/// properties that don't end up in `ComputedStyle` take any value
+pub fn is_valid_value(key: &str, value: &Value) -> bool {
+    value.is_css_wide_keyword() || Cascade::validator().accepts(key, value) != Some(false)
+}
+
+/// picks the declaration of `key` that wins the cascade, if there is any
+pub fn cascaded_value<'a>(styles: &'a [AppliedStyle], key: &str) -> Option<&'a AppliedStyle> {
+    cascaded_value_where(styles, key, |_| true)
+}
+
+/// like `cascaded_value`, but only looks at the styles for which `filter` returns true
+fn cascaded_value_where<'a>(
+    styles: &'a [AppliedStyle],
+    key: &str,
+    filter: impl Fn(&AppliedStyle) -> bool,
+) -> Option<&'a AppliedStyle> {
+    styles
+        .iter()
+        .filter(|s| s.key.as_str() == key && filter(s))
+        .max_by_key(|s| {
+            (
+                s.origin.value(s.value.important),
+                layer_precedence(s),
+                s.rule_specificity,
+                s.source_order,
+            )
+        })
+}
+
+/// later layers win for normal declarations, and earlier ones for `!important` declarations.
+/// inline styles win over every layer either way
+fn layer_precedence(style: &AppliedStyle) -> usize {
+    match style.layer_order {
+        STYLE_ATTRIBUTE_LAYER => usize::MAX,
+        order if style.value.important => STYLE_ATTRIBUTE_LAYER - 1 - order,
+        order => order,
+    }
+}
+
+/// the declarations that are left after a `revert` or `revert-layer`
+#[derive(Debug, Clone, Copy)]
+enum Rollback {
+    /// only the declarations from lower origins
+    Origin(Origin),
+    /// only the declarations that rank lower than this, by origin and importance first, then by layer
+    Layer((u8, usize)),
+}
+
+/// the result of the cascade for a single property, after the css-wide keywords are handled
+enum Cascaded<'a> {
+    /// owned when the value had to be parsed after substituting `var()`
+    Value(Cow<'a, Value>),
+    Inherit,
+    Initial,
+}
+
+/// computes the style of a single element, given its applied styles and its parent's computed style
+pub struct Cascade<'a> {
+    pub styles: &'a [AppliedStyle],
+    pub parent: Option<&'a ComputedStyle>,
+    pub viewport: (u16, u16),
+    /// the computed custom properties of this node, used to substitute `var()`
+    custom_properties: Arc<CustomProperties>,
+    /// the computed font size of this node, used to resolve `em` and the like
+    font: FontMetrics,
+}
+
+impl<'a> Cascade<'a> {
+    pub fn new(
+        styles: &'a [AppliedStyle],
+        parent: Option<&'a ComputedStyle>,
+        viewport: (u16, u16),
+        root_font_size: f32,
+    ) -> Self {
+        let parent_font_size =
+            parent.map_or(FontMetrics::DEFAULT_FONT_SIZE as f32, |p| p.font_size);
+        let mut cascade = Self {
+            styles,
+            parent,
+            viewport,
+            custom_properties: Arc::default(),
+            font: FontMetrics::new(parent_font_size, root_font_size),
+        };
+        cascade.custom_properties = cascade.compute_custom_properties();
+        // every other length can be relative to it
+        cascade.font.em = cascade.compute_font_size().into();
+        cascade
+    }
+
+    /// a cascade without any declaration, to check values with `accepts`
+    pub fn validator() -> Cascade<'static> {
+        Cascade::new(&[], None, (80, 24), FontMetrics::DEFAULT_FONT_SIZE as f32)
+    }
+
+    /// `em` and `%` in `font-size` are relative to the parent's font size,
+    /// which is what `font` has at this point
+    fn compute_font_size(&self) -> f32 {
+        self.resolve(
+            "font-size",
+            |p| p.font_size,
+            FontMetrics::DEFAULT_FONT_SIZE as f32,
+            |v| self.font_size(v),
+        )
+    }
+
+    fn font_size(&self, v: &Value) -> Option<f32> {
+        let px = match v {
+            Value::Keyword(k) => match k.to_ascii_lowercase().as_str() {
+                "xx-small" => 9.0,
+                "x-small" => 10.0,
+                "small" => 13.0,
+                "medium" => 16.0,
+                "large" => 18.0,
+                "x-large" => 24.0,
+                "xx-large" => 32.0,
+                "xxx-large" => 48.0,
+                "larger" => self.font.em * 1.2,
+                "smaller" => self.font.em / 1.2,
+                _ => return None,
+            },
+            Value::Dimension(d) => match d.unit {
+                Unit::Percent => self.font.em * d.value / 100.0,
+                Unit::Unitless if d.value == 0.0 => 0.0,
+                _ => d.tb_value(None, self.viewport, self.font).ok()? * PX_TO_TB,
+            },
+            Value::Math(expr) => {
+                let tb = expr.tb_value(None, self.viewport, self.font);
+                // math functions are clamped to the allowed range instead of being invalid
+                tb.ok()?.max(0.0) * PX_TO_TB
+            }
+            _ => return None,
+        };
+        (px >= 0.0).then_some(px as f32)
+    }
+
+    /// custom properties are always inherited, and their `var()`s are substituted
+    /// before any other property is computed
+    fn compute_custom_properties(&self) -> Arc<CustomProperties> {
+        let inherited = self
+            .parent
+            .map(|p| Arc::clone(&p.custom_properties))
+            .unwrap_or_default();
+
+        let names: HashSet<&str> = self
+            .styles
+            .iter()
+            .map(|s| s.key.as_str())
+            .filter(|key| key.starts_with("--"))
+            .collect();
+        if names.is_empty() {
+            return inherited;
+        }
+
+        let mut declared = HashMap::new();
+        for name in names {
+            match self.declared(name) {
+                Some(Cascaded::Value(Cow::Borrowed(Value::Raw(raw)))) => {
+                    declared.insert(name, Some(raw.as_str()));
+                }
+                Some(Cascaded::Initial) => {
+                    declared.insert(name, None);
+                }
+                // `inherit` and `unset` just keep the inherited value
+                _ => {}
+            }
+        }
+
+        Arc::new(CustomResolver::new(declared, &inherited).resolve_all())
+    }
+}
+
+impl Cascade<'_> {
+    pub fn compute(&self) -> ComputedStyle {
+        let initial = ComputedStyle::default();
+        let parent = self.parent.unwrap_or(&initial);
+
+        let mut style = ComputedStyle {
+            display: self.resolve("display", |p| p.display, initial.display, keyword),
+            font_size: self.font.em as f32,
+            position: self.resolve("position", |p| p.position, initial.position, keyword),
+            z_index: self.resolve("z-index", |p| p.z_index, initial.z_index, z_index),
+            visibility: self.resolve("visibility", |p| p.visibility, initial.visibility, keyword),
+            // `overflow` is the x value then the y value, or one value for both
+            overflow_x: self.resolve_with_fallback(
+                "overflow-x",
+                "overflow",
+                |p| p.overflow_x,
+                initial.overflow_x,
+                |v| overflow(v).map(|(x, _)| x),
+            ),
+            overflow_y: self.resolve_with_fallback(
+                "overflow-y",
+                "overflow",
+                |p| p.overflow_y,
+                initial.overflow_y,
+                |v| overflow(v).map(|(_, y)| y),
+            ),
+            scrollbar_width: self.resolve(
+                "scrollbar-width",
+                |p| p.scrollbar_width,
+                initial.scrollbar_width,
+                keyword,
+            ),
+            color: self.resolve(
+                "color",
+                |p| p.color,
+                initial.color,
+                |v| Some(color_or_current(v)?.or(parent.color)),
+            ),
+            width: self.length("width", initial.width.clone()),
+            height: self.length("height", initial.height.clone()),
+            min_width: self.length("min-width", initial.min_width.clone()),
+            min_height: self.length("min-height", initial.min_height.clone()),
+            max_width: self.length("max-width", initial.max_width.clone()),
+            max_height: self.length("max-height", initial.max_height.clone()),
+            inset: Edges {
+                top: self.length_with_fallback("top", "inset", initial.inset.top.clone()),
+                right: self.length_with_fallback("right", "inset", initial.inset.right.clone()),
+                bottom: self.length_with_fallback("bottom", "inset", initial.inset.bottom.clone()),
+                left: self.length_with_fallback("left", "inset", initial.inset.left.clone()),
+            },
+            margin: Edges {
+                top: self.length_with_fallback("margin-top", "margin", initial.margin.top.clone()),
+                right: self.length_with_fallback(
+                    "margin-right",
+                    "margin",
+                    initial.margin.right.clone(),
+                ),
+                bottom: self.length_with_fallback(
+                    "margin-bottom",
+                    "margin",
+                    initial.margin.bottom.clone(),
+                ),
+                left: self.length_with_fallback(
+                    "margin-left",
+                    "margin",
+                    initial.margin.left.clone(),
+                ),
+            },
+            padding: Edges {
+                top: self.non_negative("padding-top", "padding"),
+                right: self.non_negative("padding-right", "padding"),
+                bottom: self.non_negative("padding-bottom", "padding"),
+                left: self.non_negative("padding-left", "padding"),
+            },
+            border_width: Edges::all(0.0),
+            border_style: Edges::all(BorderStyle::None),
+            border_color: Edges::all(None),
+            border_radius: self.resolve(
+                "border-radius",
+                |p| p.border_radius.clone(),
+                initial.border_radius.clone(),
+                |v| self.border_radius(v),
+            ),
+
+            flex_direction: self.resolve(
+                "flex-direction",
+                |p| p.flex_direction,
+                initial.flex_direction,
+                flex_direction,
+            ),
+            flex_wrap: self.resolve("flex-wrap", |p| p.flex_wrap, initial.flex_wrap, flex_wrap),
+            flex_grow: self.resolve("flex-grow", |p| p.flex_grow, initial.flex_grow, number),
+            flex_shrink: self.resolve(
+                "flex-shrink",
+                |p| p.flex_shrink,
+                initial.flex_shrink,
+                number,
+            ),
+            flex_basis: self.length("flex-basis", initial.flex_basis.clone()),
+            justify_content: self.resolve(
+                "justify-content",
+                |p| p.justify_content,
+                initial.justify_content,
+                justify_content,
+            ),
+            align_items: self.resolve(
+                "align-items",
+                |p| p.align_items,
+                initial.align_items,
+                align_items,
+            ),
+            row_gap: self.non_negative("row-gap", "gap"),
+            column_gap: self.non_negative("column-gap", "gap"),
+
+            border_collapse: self.resolve(
+                "border-collapse",
+                |p| p.border_collapse,
+                initial.border_collapse,
+                keyword,
+            ),
+            border_spacing: self.resolve(
+                "border-spacing",
+                |p| p.border_spacing,
+                initial.border_spacing,
+                |v| self.border_spacing(v),
+            ),
+            caption_side: self.resolve(
+                "caption-side",
+                |p| p.caption_side,
+                initial.caption_side,
+                keyword,
+            ),
+
+            font_weight: self.resolve(
+                "font-weight",
+                |p| p.font_weight,
+                initial.font_weight,
+                |v| font_weight(v, parent.font_weight),
+            ),
+            font_style: self.resolve("font-style", |p| p.font_style, initial.font_style, keyword),
+            text_decoration: self.resolve_with_fallback(
+                "text-decoration-line",
+                "text-decoration",
+                |p| p.text_decoration,
+                initial.text_decoration,
+                text_decoration_line,
+            ),
+            text_align: self.resolve("text-align", |p| p.text_align, initial.text_align, keyword),
+            text_transform: self.resolve(
+                "text-transform",
+                |p| p.text_transform,
+                initial.text_transform,
+                keyword,
+            ),
+            white_space: self.resolve(
+                "white-space",
+                |p| p.white_space,
+                initial.white_space,
+                keyword,
+            ),
+
+            list_style_type: self.resolve_with_fallback(
+                "list-style-type",
+                "list-style",
+                |p| p.list_style_type,
+                initial.list_style_type,
+                keyword,
+            ),
+
+            content: self.resolve("content", |p| p.content.clone(), initial.content, content),
+            quotes: self.resolve("quotes", |p| p.quotes.clone(), initial.quotes, quotes),
+            counter_reset: self.resolve(
+                "counter-reset",
+                |p| p.counter_reset.clone(),
+                initial.counter_reset,
+                |v| counter_list(v, 0),
+            ),
+            counter_increment: self.resolve(
+                "counter-increment",
+                |p| p.counter_increment.clone(),
+                initial.counter_increment,
+                |v| counter_list(v, 1),
+            ),
+            counter_set: self.resolve(
+                "counter-set",
+                |p| p.counter_set.clone(),
+                initial.counter_set,
+                |v| counter_list(v, 0),
+            ),
+
+            background_color: None,
+            custom_properties: Arc::clone(&self.custom_properties),
+        };
+
+        for side in ["top", "right", "bottom", "left"] {
+            let (width, border_style, color) = self.border_side(side);
+            *side_mut(&mut style.border_width, side) = width;
+            *side_mut(&mut style.border_style, side) = border_style;
+            *side_mut(&mut style.border_color, side) = color;
+        }
+
+        // a box that scrolls on one axis can't let its content spill out on the other
+        let (x, y) = (style.overflow_x, style.overflow_y);
+        if x.is_scroll_container() != y.is_scroll_container() {
+            let scrolling = |overflow| match overflow {
+                Overflow::Visible => Overflow::Auto,
+                Overflow::Clip => Overflow::Hidden,
+                overflow => overflow,
+            };
+            style.overflow_x = scrolling(x);
+            style.overflow_y = scrolling(y);
+        }
+
+        // `currentcolor` needs the computed `color` first
+        let current_color = style.color;
+        style.background_color = self.resolve(
+            "background-color",
+            |p| p.background_color,
+            initial.background_color,
+            |v| Some(color_or_current(v)?.or(current_color)),
+        );
+
+        // decorations propagate to every descendant
+        if let Some(parent) = self.parent {
+            style.text_decoration = style.text_decoration.union(parent.text_decoration);
+        }
+
+        style
+    }
+
+    /// whether `value` is valid for `key`, converted the same way as in `compute`.
+    /// returns `None` if `key` isn't a property that's computed
+    pub fn accepts(&self, key: &str, value: &Value) -> Option<bool> {
+        let valid = match key {
+            "display" => keyword::<Display>(value).is_some(),
+            "position" => keyword::<Position>(value).is_some(),
+            "visibility" => keyword::<Visibility>(value).is_some(),
+            "scrollbar-width" => keyword::<ScrollbarWidth>(value).is_some(),
+            "z-index" => z_index(value).is_some(),
+            "overflow" => overflow(value).is_some(),
+            "overflow-x" | "overflow-y" => keyword::<Overflow>(value).is_some(),
+            "color" | "background-color" => color_or_current(value).is_some(),
+            "width" | "height" | "min-width" | "min-height" | "max-width" | "max-height"
+            | "top" | "right" | "bottom" | "left" | "margin-top" | "margin-right"
+            | "margin-bottom" | "margin-left" | "flex-basis" => {
+                self.to_length_auto(key, value).is_some()
+            }
+            "padding-top" | "padding-right" | "padding-bottom" | "padding-left" | "row-gap"
+            | "column-gap" => self.non_negative_length(value).is_some(),
+            "border-radius" => self.border_radius(value).is_some(),
+            "flex-direction" => flex_direction(value).is_some(),
+            "flex-wrap" => flex_wrap(value).is_some(),
+            "flex-grow" | "flex-shrink" => number(value).is_some(),
+            "justify-content" => justify_content(value).is_some(),
+            "align-items" => align_items(value).is_some(),
+            "border-collapse" => keyword::<BorderCollapse>(value).is_some(),
+            "border-spacing" => self.border_spacing(value).is_some(),
+            "caption-side" => keyword::<CaptionSide>(value).is_some(),
+            "font-size" => self.font_size(value).is_some(),
+            "font-weight" => font_weight(value, 400).is_some(),
+            "font-style" => keyword::<FontStyle>(value).is_some(),
+            "text-decoration" | "text-decoration-line" => text_decoration_line(value).is_some(),
+            "text-align" => keyword::<TextAlign>(value).is_some(),
+            "text-transform" => keyword::<TextTransform>(value).is_some(),
+            "white-space" => keyword::<WhiteSpace>(value).is_some(),
+            "list-style" | "list-style-type" => keyword::<CounterStyle>(value).is_some(),
+            "content" => content(value).is_some(),
+            "quotes" => quotes(value).is_some(),
+            "counter-reset" | "counter-increment" | "counter-set" => {
+                counter_list(value, 0).is_some()
+            }
+            key => {
+                let (side, part) = key.strip_prefix("border-")?.split_once('-')?;
+                if !["top", "right", "bottom", "left"].contains(&side) {
+                    return None;
+                }
+                match part {
+                    "width" => self.border_width(value).is_some(),
+                    "style" => keyword::<BorderStyle>(value).is_some(),
+                    "color" => color_or_current(value).is_some(),
+                    _ => return None,
+                }
+            }
+        };
+        Some(valid)
+    }
+
+    /// finds the declaration of `key`, rolling back to lower origins on `revert`
+    /// and to lower layers on `revert-layer`.
+    /// returns `None` if there's no declaration at all.
+    fn declared(&self, key: &str) -> Option<Cascaded<'_>> {
+        let inherited = is_inherited(key);
+        let mut below: Option<Rollback> = None;
+        loop {
+            let winner = cascaded_value_where(self.styles, key, |s| match below {
+                None => true,
+                Some(Rollback::Origin(origin)) => s.origin < origin,
+                Some(Rollback::Layer(rank)) => {
+                    (s.origin.value(s.value.important), layer_precedence(s)) < rank
+                }
+            });
+
+            let Some(winner) = winner else {
+                // reverting past every origin is the same as `unset`
+                return below.map(|_| Self::unset(inherited));
+            };
+
+            return Some(match &winner.value.value {
+                Value::Keyword(k) if k.eq_ignore_ascii_case("inherit") => Cascaded::Inherit,
+                Value::Keyword(k) if k.eq_ignore_ascii_case("initial") => Cascaded::Initial,
+                Value::Keyword(k) if k.eq_ignore_ascii_case("unset") => Self::unset(inherited),
+                Value::Keyword(k) if k.eq_ignore_ascii_case("revert") => {
+                    below = Some(Rollback::Origin(winner.origin));
+                    continue;
+                }
+                Value::Keyword(k) if k.eq_ignore_ascii_case("revert-layer") => {
+                    below = Some(Rollback::Layer((
+                        winner.origin.value(winner.value.important),
+                        layer_precedence(winner),
+                    )));
+                    continue;
+                }
+                Value::Raw(raw) if !key.starts_with("--") => self.substitute(raw, inherited),
+                value => Cascaded::Value(Cow::Borrowed(value)),
+            });
+        }
+    }
+
+    /// substitutes the `var()`s in `raw` and parses the result.
+    /// if that fails, the declaration is invalid at computed-value time, and behaves like `unset`
+    fn substitute(&self, raw: &str, inherited: bool) -> Cascaded<'_> {
+        let substituted = custom_properties::substitute(raw, &mut |name| {
+            self.custom_properties.get(name).cloned()
+        });
+        let Some(value) = substituted.and_then(|s| css::parse_property_value(&s)) else {
+            return Self::unset(inherited);
+        };
+
+        match &value {
+            Value::Keyword(k) if k.eq_ignore_ascii_case("inherit") => Cascaded::Inherit,
+            Value::Keyword(k) if k.eq_ignore_ascii_case("initial") => Cascaded::Initial,
+            Value::Keyword(k)
+                if ["unset", "revert", "revert-layer"]
+                    .iter()
+                    .any(|u| k.eq_ignore_ascii_case(u)) =>
+            {
+                Self::unset(inherited)
+            }
+            _ => Cascaded::Value(Cow::Owned(value)),
+        }
+    }
+
+    fn unset(inherited: bool) -> Cascaded<'static> {
+        match inherited {
+            true => Cascaded::Inherit,
+            false => Cascaded::Initial,
+        }
+    }
+
+    /// resolves the computed value of `key`.
+    /// `convert` returns `None` when the value is invalid, which then behaves like `unset`.
+    /// that only happens to values with `var()`, the others are checked when they are parsed, see `is_valid_value`
+    fn resolve<T>(
+        &self,
+        key: &str,
+        from_parent: impl FnOnce(&ComputedStyle) -> T,
+        initial: T,
+        convert: impl FnOnce(&Value) -> Option<T>,
+    ) -> T {
+        let cascaded = self.declared(key);
+        self.finish(cascaded, is_inherited(key), from_parent, initial, convert)
+    }
+
+    /// like `resolve`, but if `key` is not declared at all, `fallback_key` (usually a shorthand) is used,
+    /// see `declared_in_shorthand`
+    fn resolve_with_fallback<T>(
+        &self,
+        key: &str,
+        fallback_key: &str,
+        from_parent: impl FnOnce(&ComputedStyle) -> T,
+        initial: T,
+        convert: impl FnOnce(&Value) -> Option<T>,
+    ) -> T {
+        let cascaded = self
+            .declared(key)
+            .or_else(|| self.declared_in_shorthand(fallback_key, key));
+        self.finish(cascaded, is_inherited(key), from_parent, initial, convert)
+    }
+
+    /// the value that the `shorthand` declaration gives to `key`, if there's one.
+    /// shorthands are expanded into their longhands when they're parsed, except the ones with `var()`,
+    /// which are expanded here once they're substituted, see `shorthands::expand`.
+    /// the fallbacks that aren't shorthands we expand (eg. `overflow`) are returned as they are
+    fn declared_in_shorthand(&self, shorthand: &str, key: &str) -> Option<Cascaded<'_>> {
+        let cascaded = self.declared(shorthand)?;
+        let value = match &cascaded {
+            Cascaded::Value(value) if shorthands::is_shorthand(shorthand) => value,
+            _ => return Some(cascaded),
+        };
+        let longhand = shorthands::expand(shorthand, value.as_list())
+            .and_then(|longhands| longhands.into_iter().find(|(longhand, _)| longhand == key));
+        Some(match longhand {
+            Some((_, value)) => Cascaded::Value(Cow::Owned(value)),
+            None => Self::unset(is_inherited(key)),
+        })
+    }
+
+    fn finish<T>(
+        &self,
+        cascaded: Option<Cascaded>,
+        inherited: bool,
+        from_parent: impl FnOnce(&ComputedStyle) -> T,
+        initial: T,
+        convert: impl FnOnce(&Value) -> Option<T>,
+    ) -> T {
+        let cascaded = match cascaded {
+            Some(Cascaded::Value(value)) => match convert(&value) {
+                Some(computed) => return computed,
+                None => Self::unset(inherited),
+            },
+            Some(cascaded) => cascaded,
+            None => Self::unset(inherited),
+        };
+
+        match (cascaded, self.parent) {
+            (Cascaded::Inherit, Some(parent)) => from_parent(parent),
+            _ => initial,
+        }
+    }
+
+    fn length(&self, key: &str, initial: LengthAuto) -> LengthAuto {
+        self.resolve(
+            key,
+            |p| length_of(p, key),
+            initial,
+            |v| self.to_length_auto(key, v),
+        )
+    }
+
+    fn length_with_fallback(
+        &self,
+        key: &str,
+        fallback_key: &str,
+        initial: LengthAuto,
+    ) -> LengthAuto {
+        self.resolve_with_fallback(
+            key,
+            fallback_key,
+            |p| length_of(p, key),
+            initial,
+            |v| self.to_length_auto(key, v),
+        )
+    }
+
+    /// for padding and gaps, which can't be negative or `auto`
+    fn non_negative(&self, key: &str, fallback_key: &str) -> Length {
+        self.resolve_with_fallback(
+            key,
+            fallback_key,
+            |p| non_negative_of(p, key),
+            Length::Tb(0.0),
+            |v| self.non_negative_length(v),
+        )
+    }
+
+    fn non_negative_length(&self, v: &Value) -> Option<Length> {
+        match self.to_length(v)? {
+            // math functions are clamped to the allowed range instead of being invalid
+            LengthAuto::Tb(value) if matches!(v, Value::Math(_)) => {
+                Some(Length::Tb(value.max(0.0)))
+            }
+            LengthAuto::Tb(value) if value >= 0.0 => Some(Length::Tb(value)),
+            LengthAuto::Percent(fraction) if fraction >= 0.0 => Some(Length::Percent(fraction)),
+            // clamped once it's resolved, see `style_to_taffy::resolve_calc`
+            LengthAuto::Calc(calc) => Some(Length::Calc(calc)),
+            _ => None,
+        }
+    }
+
+    fn border_radius(&self, v: &Value) -> Option<Length> {
+        match self.to_length(v)? {
+            LengthAuto::Tb(value) if value >= 0.0 => Some(Length::Tb(value)),
+            LengthAuto::Percent(fraction) if fraction >= 0.0 => Some(Length::Percent(fraction)),
+            LengthAuto::Calc(calc) => Some(Length::Calc(calc)),
+            _ => None,
+        }
+    }
+
+    /// one length for both directions, or the horizontal one then the vertical one
+    fn border_spacing(&self, v: &Value) -> Option<(f32, f32)> {
+        let spacing = |v| match self.to_length(v)? {
+            LengthAuto::Tb(value) if value >= 0.0 => Some(value),
+            _ => None,
+        };
+        match v.as_list() {
+            [both] => spacing(both).map(|both| (both, both)),
+            [horizontal, vertical] => Some((spacing(horizontal)?, spacing(vertical)?)),
+            _ => None,
+        }
+    }
+
+    /// the width, style and color of a side of the border, from the longhands (eg. `border-top-width`).
+    /// like in CSS, the width is 0 if there's no style
+    fn border_side(&self, side: &str) -> (f32, BorderStyle, Option<Color>) {
+        let width = self.border_part(
+            side,
+            "width",
+            |p| *side_ref(&p.border_width, side),
+            BORDER_WIDTH_MEDIUM,
+            |v| self.border_width(v),
+        );
+        let border_style = self.border_part(
+            side,
+            "style",
+            |p| *side_ref(&p.border_style, side),
+            BorderStyle::None,
+            keyword,
+        );
+        let color = self.border_part(
+            side,
+            "color",
+            |p| *side_ref(&p.border_color, side),
+            None,
+            color_or_current,
+        );
+        match border_style.is_drawn() {
+            true => (width, border_style, color),
+            false => (0.0, border_style, color),
+        }
+    }
+
+    /// the value of `border-{side}-{part}`, which the `border-{part}`, `border-{side}` and `border`
+    /// shorthands set too, when they have `var()`s, see `declared_in_shorthand`
+    fn border_part<T>(
+        &self,
+        side: &str,
+        part: &str,
+        from_parent: impl FnOnce(&ComputedStyle) -> T,
+        initial: T,
+        convert: impl FnOnce(&Value) -> Option<T>,
+    ) -> T {
+        let longhand = format!("border-{side}-{part}");
+        let declared = self
+            .declared(&longhand)
+            .or_else(|| self.declared_in_shorthand(&format!("border-{part}"), &longhand))
+            .or_else(|| self.declared_in_shorthand(&format!("border-{side}"), &longhand))
+            .or_else(|| self.declared_in_shorthand("border", &longhand));
+        self.finish(declared, false, from_parent, initial, convert)
+    }
+
+    /// border widths can't be percentages either
+    fn border_width(&self, value: &Value) -> Option<f32> {
+        match value {
+            Value::Keyword(k) => match k.as_str() {
+                "thin" | "medium" | "thick" => Some(BORDER_WIDTH_MEDIUM),
+                _ => None,
+            },
+            v => match self.to_length(v)? {
+                LengthAuto::Tb(value) if matches!(v, Value::Math(_)) => Some(value.max(0.0)),
+                LengthAuto::Tb(value) if value >= 0.0 => Some(value),
+                _ => None,
+            },
+        }
+    }
+
+    /// `to_length`, plus the keyword that leaves `key` to layout, if it has one
+    fn to_length_auto(&self, key: &str, value: &Value) -> Option<LengthAuto> {
+        match value {
+            Value::Keyword(k) => {
+                let allowed = auto_keyword(key)?;
+                k.eq_ignore_ascii_case(allowed).then_some(LengthAuto::Auto)
+            }
+            v => self.to_length(v),
+        }
+    }
+
+    fn to_length(&self, value: &Value) -> Option<LengthAuto> {
+        match value {
+            Value::Dimension(dimension) => match dimension.unit {
+                Unit::Unitless if dimension.value == 0.0 => Some(LengthAuto::Tb(0.0)),
+                // left for taffy, which knows the size of the containing block
+                Unit::Percent => Some(LengthAuto::Percent(dimension.value as f32 / 100.0)),
+                _ => {
+                    let tb = dimension.as_tb_trivial(self.viewport, self.font).ok()?;
+                    Some(LengthAuto::Tb(tb.value as f32))
+                }
+            },
+            // percentages are only known in layout, see `CalcLength`
+            Value::Math(expr) if expr.has_percentages() => {
+                CalcLength::new(expr, self.viewport, self.font)
+                    .ok()
+                    .map(LengthAuto::Calc)
+            }
+            Value::Math(expr) => match expr.as_tb(None, self.viewport, self.font) {
+                Ok(tb) => Some(LengthAuto::Tb(tb.value as f32)),
+                Err(_) => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// the keyword that means `LengthAuto::Auto` for `key`: the maximum sizes have no limit with `none`,
+/// and the other sizes, insets, margins and `flex-basis` are left to layout with `auto`
+fn auto_keyword(key: &str) -> Option<&'static str> {
+    match key {
+        "max-width" | "max-height" => Some("none"),
+        "width" | "height" | "min-width" | "min-height" | "flex-basis" => Some("auto"),
+        "top" | "right" | "bottom" | "left" | "inset" => Some("auto"),
+        k if k.starts_with("margin") => Some("auto"),
+        _ => None,
+    }
+}
+
+/// used by `inherit` on properties that are not inherited by default
+fn length_of(style: &ComputedStyle, key: &str) -> LengthAuto {
+    match key {
+        "width" => &style.width,
+        "height" => &style.height,
+        "min-width" => &style.min_width,
+        "min-height" => &style.min_height,
+        "max-width" => &style.max_width,
+        "max-height" => &style.max_height,
+        "top" => &style.inset.top,
+        "right" => &style.inset.right,
+        "bottom" => &style.inset.bottom,
+        "left" => &style.inset.left,
+        "margin-top" => &style.margin.top,
+        "margin-right" => &style.margin.right,
+        "margin-bottom" => &style.margin.bottom,
+        "margin-left" => &style.margin.left,
+        "flex-basis" => &style.flex_basis,
+        _ => return LengthAuto::Auto,
+    }
+    .clone()
+}
+
+fn non_negative_of(style: &ComputedStyle, key: &str) -> Length {
+    match key {
+        "padding-top" => &style.padding.top,
+        "padding-right" => &style.padding.right,
+        "padding-bottom" => &style.padding.bottom,
+        "padding-left" => &style.padding.left,
+        "row-gap" => &style.row_gap,
+        "column-gap" => &style.column_gap,
+        _ => return Length::Tb(0.0),
+    }
+    .clone()
+}
+
+/// a side of `edges`, by its name in property names
+fn side_mut<'a, T>(edges: &'a mut Edges<T>, side: &str) -> &'a mut T {
+    match side {
+        "top" => &mut edges.top,
+        "right" => &mut edges.right,
+        "bottom" => &mut edges.bottom,
+        _ => &mut edges.left,
+    }
+}
+
+fn side_ref<'a, T>(edges: &'a Edges<T>, side: &str) -> &'a T {
+    match side {
+        "top" => &edges.top,
+        "right" => &edges.right,
+        "bottom" => &edges.bottom,
+        _ => &edges.left,
+    }
+}
+
+/// `None` is `currentcolor`
+fn color_or_current(value: &Value) -> Option<Option<Color>> {
+    match value {
+        Value::Keyword(k) if k.eq_ignore_ascii_case("currentcolor") => Some(None),
+        v => color(v).map(Some),
+    }
+}
+
+/// `None` is `auto`
+fn z_index(value: &Value) -> Option<Option<i32>> {
+    match value {
+        Value::Keyword(k) if k.eq_ignore_ascii_case("auto") => Some(None),
+        v => number(v)
+            .filter(|z| z.fract() == 0.0)
+            .map(|z| Some(z as i32)),
+    }
+}
+
+/// `overflow` is the x value then the y value, or one value for both
+fn overflow(value: &Value) -> Option<(Overflow, Overflow)> {
+    match value.as_list() {
+        [both] => keyword(both).map(|both| (both, both)),
+        [x, y] => Some((keyword(x)?, keyword(y)?)),
+        _ => None,
+    }
+}
+
+fn flex_direction(value: &Value) -> Option<taffy::FlexDirection> {
+    match keyword_str(value)? {
+        "row" => Some(taffy::FlexDirection::Row),
+        "row-reverse" => Some(taffy::FlexDirection::RowReverse),
+        "column" => Some(taffy::FlexDirection::Column),
+        "column-reverse" => Some(taffy::FlexDirection::ColumnReverse),
+        _ => None,
+    }
+}
+
+fn flex_wrap(value: &Value) -> Option<taffy::FlexWrap> {
+    match keyword_str(value)? {
+        "nowrap" => Some(taffy::FlexWrap::NoWrap),
+        "wrap" => Some(taffy::FlexWrap::Wrap),
+        "wrap-reverse" => Some(taffy::FlexWrap::WrapReverse),
+        _ => None,
+    }
+}
+
+/// `None` is `normal`
+fn justify_content(value: &Value) -> Option<Option<taffy::JustifyContent>> {
+    match keyword_str(value)? {
+        "normal" => Some(None),
+        "start" => Some(Some(taffy::JustifyContent::Start)),
+        "end" => Some(Some(taffy::JustifyContent::End)),
+        "flex-start" => Some(Some(taffy::JustifyContent::FlexStart)),
+        "flex-end" => Some(Some(taffy::JustifyContent::FlexEnd)),
+        "center" => Some(Some(taffy::JustifyContent::Center)),
+        "stretch" => Some(Some(taffy::JustifyContent::Stretch)),
+        "space-between" => Some(Some(taffy::JustifyContent::SpaceBetween)),
+        "space-evenly" => Some(Some(taffy::JustifyContent::SpaceEvenly)),
+        "space-around" => Some(Some(taffy::JustifyContent::SpaceAround)),
+        _ => None,
+    }
+}
+
+/// `None` is `normal`
+fn align_items(value: &Value) -> Option<Option<taffy::AlignItems>> {
+    match keyword_str(value)? {
+        "normal" => Some(None),
+        "start" => Some(Some(taffy::AlignItems::Start)),
+        "end" => Some(Some(taffy::AlignItems::End)),
+        "flex-start" => Some(Some(taffy::AlignItems::FlexStart)),
+        "flex-end" => Some(Some(taffy::AlignItems::FlexEnd)),
+        "center" => Some(Some(taffy::AlignItems::Center)),
+        "baseline" => Some(Some(taffy::AlignItems::Baseline)),
+        "stretch" => Some(Some(taffy::AlignItems::Stretch)),
+        _ => None,
+    }
+}
+
+fn font_weight(value: &Value, parent_weight: u16) -> Option<u16> {
+    match value {
+        Value::Keyword(k) => match k.to_ascii_lowercase().as_str() {
+            "normal" => Some(400),
+            "bold" => Some(700),
+            "bolder" => Some(bolder(parent_weight)),
+            "lighter" => Some(lighter(parent_weight)),
+            _ => None,
+        },
+        v => number(v)
+            .filter(|n| (1.0..=1000.0).contains(n))
+            .map(|n| n as u16),
+    }
+}
+
+fn text_decoration_line(value: &Value) -> Option<TextDecoration> {
+    match keyword_str(value)? {
+        "none" => Some(TextDecoration::default()),
+        "underline" => Some(TextDecoration {
+            underline: true,
+            ..Default::default()
+        }),
+        "overline" => Some(TextDecoration {
+            overline: true,
+            ..Default::default()
+        }),
+        "line-through" => Some(TextDecoration {
+            line_through: true,
+            ..Default::default()
+        }),
+        _ => None,
+    }
+}
+
+/// every width keyword is a single cell
+const BORDER_WIDTH_MEDIUM: f32 = 1.0;
+
+fn keyword_str(value: &Value) -> Option<&str> {
+    match value {
+        Value::Keyword(k) => Some(k.as_str()),
+        _ => None,
+    }
+}
+
+fn keyword<T: FromStr>(value: &Value) -> Option<T> {
+    T::from_str(keyword_str(value)?).ok()
+}
+
+fn number(value: &Value) -> Option<f32> {
+    match value {
+        Value::Dimension(dimension) if matches!(dimension.unit, Unit::Unitless) => {
+            Some(dimension.value as f32)
+        }
+        Value::Math(expr) => expr.as_number().ok().map(|n| n as f32),
+        _ => None,
+    }
+}
+
+fn content(value: &Value) -> Option<Content> {
+    match keyword_str(value).map(str::to_ascii_lowercase).as_deref() {
+        Some("normal") => return Some(Content::Normal),
+        Some("none") => return Some(Content::None),
+        _ => {}
+    }
+    value
+        .as_list()
+        .iter()
+        .map(content_item)
+        .collect::<Option<_>>()
+        .map(Content::Items)
+}
+
+fn content_item(value: &Value) -> Option<ContentItem> {
+    Some(match value {
+        Value::String(text) => ContentItem::String(text.clone()),
+        Value::Keyword(k) => match k.to_ascii_lowercase().as_str() {
+            "open-quote" => ContentItem::OpenQuote,
+            "close-quote" => ContentItem::CloseQuote,
+            "no-open-quote" => ContentItem::NoOpenQuote,
+            "no-close-quote" => ContentItem::NoCloseQuote,
+            _ => return None,
+        },
+        Value::Function(function, args) => match (function.as_str(), &args[..]) {
+            ("attr", [Value::Keyword(name)]) => ContentItem::Attr(name.clone()),
+            ("counter", [Value::Keyword(name)]) => {
+                ContentItem::Counter(name.clone(), CounterStyle::Decimal)
+            }
+            ("counter", [Value::Keyword(name), style]) => {
+                ContentItem::Counter(name.clone(), keyword(style)?)
+            }
+            ("counters", [Value::Keyword(name), Value::String(separator)]) => {
+                ContentItem::Counters(name.clone(), separator.clone(), CounterStyle::Decimal)
+            }
+            ("counters", [Value::Keyword(name), Value::String(separator), style]) => {
+                ContentItem::Counters(name.clone(), separator.clone(), keyword(style)?)
+            }
+            _ => return None,
+        },
+        _ => return None,
+    })
+}
+
+/// `auto`, `none`, or pairs of strings
+fn quotes(value: &Value) -> Option<Quotes> {
+    match keyword_str(value).map(str::to_ascii_lowercase).as_deref() {
+        Some("auto") => return Some(Quotes::Auto),
+        Some("none") => return Some(Quotes::None),
+        _ => {}
+    }
+    let strings = value
+        .as_list()
+        .iter()
+        .map(|v| match v {
+            Value::String(text) => Some(text.clone()),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    if strings.len() % 2 != 0 {
+        return None;
+    }
+    let pairs = strings
+        .chunks(2)
+        .map(|pair| (pair[0].clone(), pair[1].clone()));
+    Some(Quotes::Pairs(pairs.collect()))
+}
+
+/// `counter-reset: section 2 item`: names, each followed by an optional integer.
+/// `default` is used for the names without one
+fn counter_list(value: &Value, default: i32) -> Option<Vec<(String, i32)>> {
+    if keyword_str(value).is_some_and(|k| k.eq_ignore_ascii_case("none")) {
+        return Some(vec![]);
+    }
+
+    let mut counters: Vec<(String, Option<i32>)> = vec![];
+    for value in value.as_list() {
+        match value {
+            Value::Keyword(name) if !name.eq_ignore_ascii_case("none") => {
+                counters.push((name.clone(), None));
+            }
+            Value::Dimension(d) if matches!(d.unit, Unit::Unitless) && d.value.fract() == 0.0 => {
+                let (_, amount) = counters.last_mut()?;
+                if amount.replace(d.value as i32).is_some() {
+                    return None;
+                }
+            }
+            _ => return None,
+        }
+    }
+    let counters = counters.into_iter();
+    Some(
+        counters
+            .map(|(name, amount)| (name, amount.unwrap_or(default)))
+            .collect(),
+    )
+}
+
+fn color(value: &Value) -> Option<Color> {
+    match value {
+        Value::Color(color) => Some(*color),
+        Value::Keyword(k) => Color::from_name(k),
+        _ => None,
+    }
+}
+
+/// https://drafts.csswg.org/css-fonts/#relative-weights
+fn bolder(weight: u16) -> u16 {
+    match weight {
+        0..350 => 400,
+        350..550 => 700,
+        _ => 900,
+    }
+}
+
+fn lighter(weight: u16) -> u16 {
+    match weight {
+        0..550 => 100,
+        550..750 => 400,
+        _ => 700,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{
+        computed_style::{Display, FontStyle, TextAlign},
+        css,
+        dom::Computed as _,
+        html,
+        render::testing::{computed, dom_with},
+    };
+
+    #[test]
+    fn inheritance_and_css_wide_keywords() {
+        let dom = dom_with(
+            r#"
+            <div id="parent">
+                <p id="inherits">a</p>
+                <p id="initial">b</p>
+                <p id="unset">c</p>
+                <span id="inherit-width">d</span>
+                <em id="revert">e</em>
+            </div>
+            "#,
+            "em { font-style: italic; }",
+            r#"
+            #parent { color: #ff0000; width: 10tb; display: flex; font-style: italic; }
+            #initial { color: initial; }
+            #unset { color: unset; display: unset; }
+            #inherit-width { width: inherit; }
+            em { font-style: normal; }
+            #revert { font-style: revert; }
+            "#,
+        );
+
+        let red = Some(Color::rgba(255, 0, 0, 255));
+        assert_eq!(computed(&dom, "#parent").display, Display::Flex);
+        assert_eq!(computed(&dom, "#inherits").color, red);
+        assert_eq!(computed(&dom, "#inherits").font_style, FontStyle::Italic);
+        // width is not inherited by default
+        assert_eq!(computed(&dom, "#inherits").width, LengthAuto::Auto);
+        assert_eq!(computed(&dom, "#initial").color, None);
+        assert_eq!(computed(&dom, "#unset").color, red);
+        assert_eq!(computed(&dom, "#unset").display, Display::Inline);
+        assert_eq!(computed(&dom, "#inherit-width").width, LengthAuto::Tb(10.0));
+        // revert goes back to the agent stylesheet
+        assert_eq!(computed(&dom, "#revert").font_style, FontStyle::Italic);
+    }
+
+    #[test]
+    fn borders() {
+        let dom = dom_with(
+            r#"<p id="shorthand">a</p><p id="no-style">b</p><p id="sides">c</p>"#,
+            "",
+            "#shorthand { border: red 2tb double; border-left-style: none; border-radius: 1tb; }
+             #no-style { border-width: 3tb; }
+             #sides { border-top: dashed; border-color: blue; }",
+        );
+        let style = computed(&dom, "#shorthand");
+        assert_eq!(style.border_style.top, BorderStyle::Double);
+        assert_eq!(style.border_color.right, Color::from_name("red"));
+        assert_eq!(style.border_radius, Length::Tb(1.0));
+        // there's no border without a style
+        let width = style.border_width;
+        assert_eq!((width.top, width.right, width.left), (2.0, 2.0, 0.0));
+        assert_eq!(computed(&dom, "#no-style").border_width, Edges::all(0.0));
+
+        let style = computed(&dom, "#sides");
+        assert_eq!(style.border_style.top, BorderStyle::Dashed);
+        assert_eq!(style.border_width.top, 1.0);
+        assert_eq!(style.border_width.bottom, 0.0);
+        assert_eq!(style.border_color.top, Color::from_name("blue"));
+    }
+
+    #[test]
+    fn keywords_after_numbers() {
+        // `0 auto` is a number then a keyword, not a number with the unit `auto`
+        let css = "#box { margin: 0 auto; border: 0 solid red; }";
+        let sheet = css::parse_from_str(css, Origin::Author).unwrap();
+        // the four margins, and the width, style and color of the four sides
+        assert_eq!(sheet.rules[0].props.len(), 16);
+        assert!(sheet.warnings.is_empty());
+
+        let dom = dom_with(r#"<div id="box"></div>"#, "", css);
+        let style = computed(&dom, "#box");
+        assert_eq!(style.margin.top, LengthAuto::Tb(0.0));
+        assert_eq!(style.margin.left, LengthAuto::Auto);
+        assert_eq!(style.border_style.top, BorderStyle::Solid);
+        assert_eq!(style.border_color.left, Color::from_name("red"));
+        assert_eq!(style.border_width, Edges::all(0.0));
+    }
+
+    #[test]
+    fn shorthands() {
+        let dom = dom_with(
+            r#"<div id="two"></div><div id="three"></div><div id="four"></div><p class="a b"></p><span id="var"></span>"#,
+            "",
+            "#two { margin: 1tb 2tb; padding: 0 2tb; border-width: 1tb 2tb; border-style: solid; }
+             #three { margin: 1tb 2tb 3tb; inset: 1tb auto 2tb; gap: 1tb 2tb; }
+             #four { margin: 1tb 2tb 3tb 4tb; border-color: red green blue; }
+             .a { margin-top: 5tb; border-left: 3tb dashed; }
+             .a.b { margin: 0; border: 1tb solid; }
+             #var { --sides: 1tb 2tb; --border: 2tb double; margin: var(--sides); border-top: var(--border); }",
+        );
+        let sides = |edges: &Edges<LengthAuto>| {
+            let e = edges.clone();
+            [e.top, e.right, e.bottom, e.left]
+        };
+        let tb = LengthAuto::Tb;
+
+        let style = computed(&dom, "#two");
+        assert_eq!(sides(&style.margin), [tb(1.0), tb(2.0), tb(1.0), tb(2.0)]);
+        let width = style.border_width;
+        assert_eq!(
+            (width.top, width.right, width.bottom, width.left),
+            (1.0, 2.0, 1.0, 2.0)
+        );
+        assert_eq!(style.padding.top, Length::Tb(0.0));
+        assert_eq!(style.padding.right, Length::Tb(2.0));
+        assert_eq!(style.padding.left, Length::Tb(2.0));
+
+        let style = computed(&dom, "#three");
+        assert_eq!(sides(&style.margin), [tb(1.0), tb(2.0), tb(3.0), tb(2.0)]);
+        let auto = LengthAuto::Auto;
+        assert_eq!(sides(&style.inset), [tb(1.0), auto.clone(), tb(2.0), auto]);
+        assert_eq!(
+            (style.row_gap.clone(), style.column_gap.clone()),
+            (Length::Tb(1.0), Length::Tb(2.0))
+        );
+
+        let style = computed(&dom, "#four");
+        assert_eq!(sides(&style.margin), [tb(1.0), tb(2.0), tb(3.0), tb(4.0)]);
+        assert_eq!(style.border_color.top, Color::from_name("red"));
+        assert_eq!(style.border_color.right, Color::from_name("green"));
+        assert_eq!(style.border_color.bottom, Color::from_name("blue"));
+        assert_eq!(style.border_color.left, Color::from_name("green"));
+
+        // `.a.b` sets every margin and border, and wins over the less specific longhands of `.a`
+        let style = computed(&dom, "p");
+        assert_eq!(sides(&style.margin), [tb(0.0), tb(0.0), tb(0.0), tb(0.0)]);
+        assert_eq!(style.border_width, Edges::all(1.0));
+        assert_eq!(style.border_style.left, BorderStyle::Solid);
+
+        // shorthands with variables are expanded once they're substituted
+        let style = computed(&dom, "#var");
+        assert_eq!(sides(&style.margin), [tb(1.0), tb(2.0), tb(1.0), tb(2.0)]);
+        assert_eq!(style.border_style.top, BorderStyle::Double);
+        assert_eq!(style.border_width.top, 2.0);
+        assert!(supports_declaration("margin", "0 auto"));
+        assert!(!supports_declaration("margin", "0 bogus"));
+
+        // too many values, and a css-wide keyword with others, make the whole declaration invalid
+        let sheet = css::parse_from_str(
+            "p { margin: 1tb 2tb 3tb 4tb 5tb; padding: inherit 1tb; }",
+            Origin::Author,
+        )
+        .unwrap();
+        assert!(sheet.rules[0].props.is_empty());
+    }
+
+    #[test]
+    fn overflow() {
+        let dom = dom_with(
+            r#"<p id="both">a</p><p id="one-axis">b</p><p id="clip">c</p>"#,
+            "",
+            "#both { overflow: hidden scroll; scrollbar-width: none; }
+             #one-axis { overflow-y: auto; }
+             #clip { overflow: clip; }",
+        );
+        let style = computed(&dom, "#both");
+        assert_eq!(
+            (style.overflow_x, style.overflow_y),
+            (Overflow::Hidden, Overflow::Scroll)
+        );
+        assert_eq!(style.scrollbar_width.cells(), 0.0);
+        // `visible` can't be combined with scrolling
+        let style = computed(&dom, "#one-axis");
+        assert_eq!(
+            (style.overflow_x, style.overflow_y),
+            (Overflow::Auto, Overflow::Auto)
+        );
+        let style = computed(&dom, "#clip");
+        assert_eq!(
+            (style.overflow_x, style.overflow_y),
+            (Overflow::Clip, Overflow::Clip)
+        );
+    }
+
+    #[test]
+    fn box_model_and_colors() {
+        let dom = dom_with(
+            r#"<div id="box">text</div>"#,
+            "",
+            r#"
+            #box {
+                width: 80px;
+                height: 50vh;
+                margin: auto;
+                margin-top: 2tb;
+                padding: 16px;
+                padding-left: 0;
+                border-width: thin;
+                border-style: solid;
+                color: navy;
+                background-color: currentcolor;
+                font-weight: bold;
+                text-decoration: underline;
+                flex-grow: 2;
+            }
+            "#,
+        );
+
+        let style = computed(&dom, "#box");
+        assert_eq!(style.width, LengthAuto::Tb(10.0));
+        assert_eq!(style.height, LengthAuto::Tb(12.0));
+        assert_eq!(style.margin.top, LengthAuto::Tb(2.0));
+        assert_eq!(style.margin.left, LengthAuto::Auto);
+        assert_eq!(style.padding.top, Length::Tb(2.0));
+        assert_eq!(style.padding.left, Length::Tb(0.0));
+        assert_eq!(style.border_width.bottom, 1.0);
+        assert_eq!(style.color, Color::from_name("navy"));
+        assert_eq!(style.background_color, style.color);
+        assert!(style.is_bold());
+        assert_eq!(style.flex_grow, 2.0);
+
+        // the text inside inherits the text properties, and the decoration
+        let text = dom.query_select("#box").unwrap()[0];
+        let text = dom
+            .node(dom.children(text).next().unwrap())
+            .computed_style();
+        assert_eq!(text.color, style.color);
+        assert!(text.text_decoration.underline);
+        assert_eq!(text.background_color, None);
+        assert_eq!(text.padding.top, Length::Tb(0.0));
+    }
+
+    #[test]
+    fn auto_and_none_lengths() {
+        let dom = dom_with(
+            r#"<div id="box"></div>"#,
+            "",
+            "#box { min-width: auto; max-height: none; padding-top: auto; }",
+        );
+        let style = computed(&dom, "#box");
+        assert_eq!(style.min_width, LengthAuto::Auto);
+        assert_eq!(style.max_height, LengthAuto::Auto);
+        assert_eq!(style.padding.top, Length::Tb(0.0));
+
+        assert!(supports_declaration("width", "auto"));
+        assert!(supports_declaration("margin-left", "auto"));
+        assert!(supports_declaration("max-width", "none"));
+        assert!(!supports_declaration("width", "none"));
+        assert!(!supports_declaration("max-width", "auto"));
+        assert!(!supports_declaration("padding-top", "auto"));
+    }
+
+    #[test]
+    fn invalid_values_are_dropped() {
+        let css = "#box { display: flex; display: -webkit-box; color: red; color: bogus; width: 4tb; width: none; }";
+        let sheet = css::parse_from_str(css, Origin::Author).unwrap();
+        assert_eq!(sheet.rules[0].props.len(), 3);
+        assert_eq!(sheet.warnings.len(), 3);
+
+        // so are several values for a property that takes one
+        let sheet =
+            css::parse_from_str("#box { color: red blue; width: 1tb 2tb; }", Origin::Author);
+        assert!(sheet.unwrap().rules[0].props.is_empty());
+        assert!(!supports_declaration("display", "block flex"));
+
+        // so the earlier declarations are used, instead of the initial values
+        let dom = dom_with(r#"<div id="box"></div>"#, "", css);
+        let style = computed(&dom, "#box");
+        assert_eq!(style.display, Display::Flex);
+        assert_eq!(style.color, Color::from_name("red"));
+        assert_eq!(style.width, LengthAuto::Tb(4.0));
+
+        // unless the value has a variable, which can only be checked once it's substituted
+        let dom = dom_with(
+            r#"<div id="box"></div>"#,
+            "",
+            "#box { --kind: -webkit-box; display: flex; display: var(--kind); }",
+        );
+        assert_eq!(computed(&dom, "#box").display, Display::Inline);
+    }
+
+    /// a declaration of `color` on `#target` per rule, in the given order and origin
+    fn winning_color(html_input: &str, agent_css: &str, author_css: &str) -> Option<Color> {
+        computed(&dom_with(html_input, agent_css, author_css), "#target").color
+    }
+
+    #[test]
+    fn cascade_conformance() {
+        let html_input = r#"<div class="a b c" id="outer"><p id="target" class="a b" style="color: lime">x</p><span id="other" class="a">y</span></div>"#;
+        let red = Color::from_name("red");
+        let blue = Color::from_name("blue");
+        let lime = Color::from_name("lime");
+
+        // equal specificity: the last declared wins
+        let dom = dom_with(html_input, "", ".a { color: red; } .a { color: blue; }");
+        assert_eq!(computed(&dom, "#other").color, blue);
+        let dom = dom_with(html_input, "", ".a { color: blue; } .a { color: red; }");
+        assert_eq!(computed(&dom, "#other").color, red);
+
+        // ...even across stylesheets of the same origin
+        let mut dom = html::parse_from_str(html_input).unwrap();
+        dom.stylesheets
+            .push(css::parse_from_str(".a { color: red; }", Origin::Author).unwrap());
+        dom.stylesheets
+            .push(css::parse_from_str(".a { color: blue; }", Origin::Author).unwrap());
+        dom.refresh_styles().unwrap();
+        assert_eq!(computed(&dom, "#other").color, blue);
+
+        // specificity beats order
+        let dom = dom_with(
+            html_input,
+            "",
+            ".a.b + .a { color: red; } .a { color: blue; }",
+        );
+        assert_eq!(computed(&dom, "#other").color, red);
+        let dom = dom_with(html_input, "", "p + .a { color: red; } .a { color: blue; }");
+        assert_eq!(computed(&dom, "#other").color, red);
+
+        // one id beats any amount of classes, one class beats any amount of tags
+        let dom = dom_with(
+            html_input,
+            "",
+            "#other { color: red; } .a.a.a.a.a.a.a.a.a.a.a.a { color: blue; }",
+        );
+        assert_eq!(computed(&dom, "#other").color, red);
+        let dom = dom_with(
+            html_input,
+            "",
+            ".a { color: red; } div p ~ span { color: blue; }",
+        );
+        assert_eq!(computed(&dom, "#other").color, red);
+        // tags still count
+        let dom = dom_with(
+            html_input,
+            "",
+            "div span { color: red; } span { color: blue; }",
+        );
+        assert_eq!(computed(&dom, "#other").color, red);
+
+        // inline styles beat every selector of the same origin
+        assert_eq!(
+            winning_color(html_input, "", "#outer #target.a.b { color: red; }"),
+            lime
+        );
+
+        // author beats agent, no matter the specificity
+        assert_eq!(
+            winning_color(
+                r#"<p id="target">x</p>"#,
+                "#target { color: red; }",
+                "p { color: blue; }"
+            ),
+            blue
+        );
+    }
+
+    #[test]
+    fn math_functions() {
+        let dom = dom_with(
+            r#"<div id="parent"><p id="child">a</p></div>"#,
+            "",
+            r#"
+            #parent { width: 20tb; }
+            #child {
+                width: calc(100% - 2rem);
+                height: clamp(2tb, 50vh, 5tb);
+                margin-left: calc(-1 * 2tb);
+                padding-top: calc(1tb - 3tb);
+                flex-grow: calc(1 + 1);
+                min-width: calc(1tb * 2tb);
+            }
+            "#,
+        );
+
+        let style = computed(&dom, "#child");
+        // 2rem is 32px, and the percentage is only known in layout
+        assert_eq!(style.width.calc().unwrap().resolve(20.0), 16.0);
+        assert_eq!(style.height, LengthAuto::Tb(5.0));
+        assert_eq!(style.margin.left, LengthAuto::Tb(-2.0));
+        assert_eq!(style.padding.top, Length::Tb(0.0));
+        assert_eq!(style.flex_grow, 2.0);
+        assert_eq!(style.min_width, LengthAuto::Auto);
+    }
+
+    #[test]
+    fn font_relative_units() {
+        use crate::engine::computed_style::FontEmphasis;
+
+        let dom = dom_with(
+            r#"
+            <div id="root">
+                <h1 id="title">Big title</h1>
+                <p id="para"><span id="small">s</span></p>
+            </div>
+            "#,
+            "h1 { font-size: 2em; }",
+            r#"
+            #root { font-size: 20px; width: 10foo; }
+            #para { font-size: 150%; margin-left: 1em; width: 10ch; padding-left: 2rem; }
+            #small { font-size: smaller; height: 2lh; min-width: calc(1em + 1ex); }
+            "#,
+        );
+
+        let root = computed(&dom, "#root");
+        assert_eq!(root.font_size, 20.0);
+        assert_eq!(root.width, LengthAuto::Auto);
+        assert!(!dom.stylesheets[1].warnings.is_empty());
+
+        let para = computed(&dom, "#para");
+        assert_eq!(para.font_size, 30.0);
+        assert_eq!(para.margin.left, LengthAuto::Tb(4.0));
+        assert_eq!(para.width, LengthAuto::Tb(19.0));
+        assert_eq!(para.padding.left, Length::Tb(5.0));
+
+        let small = computed(&dom, "#small");
+        assert_eq!(small.font_size, 25.0);
+        assert_eq!(small.height, LengthAuto::Tb(3.0));
+        assert_eq!(small.min_width, LengthAuto::Tb(5.0));
+        assert_eq!(small.font_emphasis(), FontEmphasis::Bold);
+        assert!(small.is_bold());
+
+        let title = computed(&dom, "#title");
+        assert_eq!(title.font_size, 40.0);
+        assert_eq!(title.transform_text("Big title"), "BIG TITLE");
+        assert_eq!(root.transform_text("a"), "a");
+    }
+
+    #[test]
+    fn important_declarations() {
+        let dom = dom_with(
+            r#"<p id="a" class="b" style="color: green">a</p>"#,
+            "p { width: 5tb !important; }",
+            r#"
+            p { color: red !important; }
+            #a.b { color: blue; width: 10tb !important; }
+            "#,
+        );
+
+        let style = computed(&dom, "#a");
+        assert_eq!(style.color, Color::from_name("red"));
+        // important agent declarations win over important author ones
+        assert_eq!(style.width, LengthAuto::Tb(5.0));
+    }
+
+    #[test]
+    fn cascade_layers() {
+        let mut dom = html::parse_from_str(
+            r#"
+            <p id="order" class="x">a</p>
+            <p id="unlayered">b</p>
+            <p id="nested">c</p>
+            <p id="important">d</p>
+            <p id="inline" style="color: green !important; width: 3tb">e</p>
+            <p id="anonymous">f</p>
+            <p id="revert">g</p>
+            <p id="merged">h</p>
+            "#,
+        )
+        .unwrap();
+        dom.stylesheets.push(
+            css::parse_from_str(
+                "p { font-style: italic; } #revert { text-align: center; }",
+                Origin::Agent,
+            )
+            .unwrap(),
+        );
+        dom.stylesheets.push(
+            css::parse_from_str(
+                r#"
+                @layer reset, base, merged-a, merged-b;
+
+                /* the order is fixed by the statement, not by the blocks */
+                @layer base { p { color: red; } }
+                @layer reset { #order.x { color: blue; } }
+
+                @layer base { #unlayered { color: blue; } }
+                #unlayered { color: red; }
+
+                /* a layer's own rules win over its sublayers */
+                @layer outer {
+                    #nested { color: red; }
+                    @layer inner { #nested { color: blue; } }
+                }
+                @layer outer.inner { #nested { width: 1tb; } }
+                @layer outer { #nested { width: 2tb; } }
+
+                /* important declarations go the other way around */
+                @layer reset { #important { color: red !important; } }
+                @layer base { #important { color: blue !important; } }
+                #important { color: yellow !important; }
+                @layer reset { #inline { color: red !important; width: 5tb; } }
+
+                @layer { #anonymous { color: blue; } }
+                @layer { #anonymous { color: red; } }
+
+                @layer base { #revert { font-style: normal; color: red; } }
+                @layer outer {
+                    #revert { font-style: revert-layer; color: revert-layer; }
+                    #revert { text-align: revert-layer; }
+                }
+                @layer reset { #revert { text-align: revert-layer; } }
+                "#,
+                Origin::Author,
+            )
+            .unwrap(),
+        );
+        // layers with the same name in different stylesheets are the same layer
+        dom.stylesheets.push(
+            css::parse_from_str(
+                r#"
+                @layer merged-b { #merged { color: red; } }
+                @layer merged-a { #merged { color: blue; } }
+                "#,
+                Origin::Author,
+            )
+            .unwrap(),
+        );
+        dom.refresh_styles().unwrap();
+
+        let red = Color::from_name("red");
+        assert_eq!(computed(&dom, "#order").color, red);
+        assert_eq!(computed(&dom, "#unlayered").color, red);
+        assert_eq!(computed(&dom, "#nested").color, red);
+        assert_eq!(computed(&dom, "#nested").width, LengthAuto::Tb(2.0));
+        assert_eq!(computed(&dom, "#important").color, red);
+        assert_eq!(computed(&dom, "#inline").color, Color::from_name("green"));
+        assert_eq!(computed(&dom, "#inline").width, LengthAuto::Tb(3.0));
+        assert_eq!(computed(&dom, "#anonymous").color, red);
+        assert_eq!(computed(&dom, "#merged").color, red);
+
+        let revert = computed(&dom, "#revert");
+        assert_eq!(revert.font_style, FontStyle::Normal);
+        assert_eq!(revert.color, red);
+        // reverting past the lowest layer goes back to the agent stylesheet
+        assert_eq!(revert.text_align, TextAlign::Center);
+    }
+
+    #[test]
+    fn custom_properties() {
+        use crate::engine::dom::AskStyle as _;
+
+        let dom = dom_with(
+            r#"
+            <div id="root">
+                <section id="middle">
+                    <p id="deep">x</p>
+                    <p id="fallback">x</p>
+                    <p id="invalid">x</p>
+                    <p id="cycle">x</p>
+                    <p id="initial">x</p>
+                </section>
+            </div>
+            "#,
+            "",
+            r#"
+            #root { --main: #ff0000; --gap: 2tb; --size: var(--gap); color: blue; }
+            #middle { --accent: var(--main); }
+            #deep { color: var(--accent); padding: var(--size); }
+            #fallback { color: var(--missing, var(--also-missing, lime)); width: var(--nope, 3tb); }
+            #invalid { color: var(--missing); width: var(--main); }
+            #cycle { --a: var(--b); --b: var(--a, red); color: var(--b, navy); }
+            #initial { --main: initial; color: var(--main, yellow); }
+            "#,
+        );
+
+        // custom properties go through the INHERITABLE_PROPERTIES path of ask_style
+        let deep = dom.node(dom.query_select("#deep").unwrap()[0]);
+        let main = deep.ask_style("--main").unwrap();
+        assert!(matches!(&main.value.value, Value::Raw(raw) if raw == "#ff0000"));
+
+        let deep = computed(&dom, "#deep");
+        assert_eq!(deep.custom_properties["--accent"], "#ff0000");
+        assert_eq!(deep.custom_properties["--size"], "2tb");
+        assert_eq!(deep.color, Color::from_hex("#ff0000"));
+        assert_eq!(deep.padding.left, Length::Tb(2.0));
+
+        let fallback = computed(&dom, "#fallback");
+        assert_eq!(fallback.color, Color::from_name("lime"));
+        assert_eq!(fallback.width, LengthAuto::Tb(3.0));
+
+        // invalid at computed-value time: color is inherited, width goes back to initial
+        let invalid = computed(&dom, "#invalid");
+        assert_eq!(invalid.color, Color::from_name("blue"));
+        assert_eq!(invalid.width, LengthAuto::Auto);
+
+        let cycle = computed(&dom, "#cycle");
+        assert!(!cycle.custom_properties.contains_key("--a"));
+        assert!(!cycle.custom_properties.contains_key("--b"));
+        assert_eq!(cycle.color, Color::from_name("navy"));
+
+        let initial = computed(&dom, "#initial");
+        assert_eq!(initial.color, Color::from_name("yellow"));
+    }
+}
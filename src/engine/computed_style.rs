@@ -0,0 +1,671 @@
+use std::{borrow::Cow, sync::Arc};
+
+use strum_macros::{Display, EnumString};
+
+use super::{
+    calc::CalcLength, counters::CounterStyle, custom_properties::CustomProperties,
+    stylesheet::Color,
+};
+
+/// The fully resolved style of a node, produced by the cascade.
+///
+/// Every length is already converted to tb units (1tb == 1 cell).
+/// `None` colors mean "use the terminal's default".
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComputedStyle {
+    pub display: Display,
+    pub position: Position,
+    /// `None` is `auto`
+    pub z_index: Option<i32>,
+    pub visibility: Visibility,
+    /// never `visible` or `clip` when the other axis scrolls
+    pub overflow_x: Overflow,
+    pub overflow_y: Overflow,
+    pub scrollbar_width: ScrollbarWidth,
+
+    pub color: Option<Color>,
+    pub background_color: Option<Color>,
+
+    pub width: LengthAuto,
+    pub height: LengthAuto,
+    pub min_width: LengthAuto,
+    pub min_height: LengthAuto,
+    /// `Auto` means `none`
+    pub max_width: LengthAuto,
+    /// `Auto` means `none`
+    pub max_height: LengthAuto,
+    pub inset: Edges<LengthAuto>,
+    pub margin: Edges<LengthAuto>,
+    pub padding: Edges<Length>,
+    /// 0 on the sides whose style is `none` or `hidden`
+    pub border_width: Edges<f32>,
+    pub border_style: Edges<BorderStyle>,
+    /// `None` means `currentcolor`
+    pub border_color: Edges<Option<Color>>,
+    /// only tells if the corners are rounded, a cell can't be rounded any further
+    pub border_radius: Length,
+
+    pub flex_direction: taffy::FlexDirection,
+    pub flex_wrap: taffy::FlexWrap,
+    pub flex_grow: f32,
+    pub flex_shrink: f32,
+    pub flex_basis: LengthAuto,
+    pub justify_content: Option<taffy::JustifyContent>,
+    pub align_items: Option<taffy::AlignItems>,
+    pub row_gap: Length,
+    pub column_gap: Length,
+
+    pub border_collapse: BorderCollapse,
+    /// horizontal, then vertical
+    pub border_spacing: (f32, f32),
+    pub caption_side: CaptionSide,
+
+    /// in px. a terminal can't draw text in other sizes, see `font_emphasis`
+    pub font_size: f32,
+    pub font_weight: u16,
+    pub font_style: FontStyle,
+    /// unlike in CSS, this also includes the decorations of the ancestors,
+    /// since in a terminal they can only be drawn by the text itself
+    pub text_decoration: TextDecoration,
+    pub text_align: TextAlign,
+    pub text_transform: TextTransform,
+    pub white_space: WhiteSpace,
+
+    pub list_style_type: CounterStyle,
+
+    /// only used by `::marker`, `::before` and `::after`
+    pub content: Content,
+    pub quotes: Quotes,
+    pub counter_reset: Vec<(String, i32)>,
+    pub counter_increment: Vec<(String, i32)>,
+    pub counter_set: Vec<(String, i32)>,
+
+    pub custom_properties: Arc<CustomProperties>,
+}
+
+impl Default for ComputedStyle {
+    /// every property set to its initial value
+    fn default() -> Self {
+        Self {
+            display: Display::Inline,
+            position: Position::Static,
+            z_index: None,
+            visibility: Visibility::Visible,
+            overflow_x: Overflow::Visible,
+            overflow_y: Overflow::Visible,
+            scrollbar_width: ScrollbarWidth::Auto,
+
+            color: None,
+            background_color: None,
+
+            width: LengthAuto::Auto,
+            height: LengthAuto::Auto,
+            min_width: LengthAuto::Auto,
+            min_height: LengthAuto::Auto,
+            max_width: LengthAuto::Auto,
+            max_height: LengthAuto::Auto,
+            inset: Edges::all(LengthAuto::Auto),
+            margin: Edges::all(LengthAuto::Tb(0.0)),
+            padding: Edges::all(Length::Tb(0.0)),
+            border_width: Edges::all(0.0),
+            border_style: Edges::all(BorderStyle::None),
+            border_color: Edges::all(None),
+            border_radius: Length::Tb(0.0),
+
+            flex_direction: taffy::FlexDirection::Row,
+            flex_wrap: taffy::FlexWrap::NoWrap,
+            flex_grow: 0.0,
+            flex_shrink: 1.0,
+            flex_basis: LengthAuto::Auto,
+            justify_content: None,
+            align_items: None,
+            row_gap: Length::Tb(0.0),
+            column_gap: Length::Tb(0.0),
+
+            border_collapse: BorderCollapse::Separate,
+            border_spacing: (0.0, 0.0),
+            caption_side: CaptionSide::Top,
+
+            font_size: 16.0,
+            font_weight: 400,
+            font_style: FontStyle::Normal,
+            text_decoration: TextDecoration::default(),
+            text_align: TextAlign::Start,
+            text_transform: TextTransform::None,
+            white_space: WhiteSpace::Normal,
+
+            list_style_type: CounterStyle::Disc,
+
+            content: Content::Normal,
+            quotes: Quotes::Auto,
+            counter_reset: vec![],
+            counter_increment: vec![],
+            counter_set: vec![],
+
+            custom_properties: Arc::default(),
+        }
+    }
+}
+
+impl ComputedStyle {
+    /// whether some of its lengths are math functions that are only resolved in layout,
+    /// see `style_to_taffy::resolve_calc`
+    pub fn has_calc(&self) -> bool {
+        let lengths = [
+            &self.width,
+            &self.height,
+            &self.min_width,
+            &self.min_height,
+            &self.max_width,
+            &self.max_height,
+            &self.flex_basis,
+        ];
+        let edges = [&self.inset, &self.margin];
+        let spacings = [&self.padding.top, &self.padding.right, &self.padding.bottom]
+            .into_iter()
+            .chain([&self.padding.left, &self.row_gap, &self.column_gap]);
+
+        lengths.into_iter().any(|length| length.calc().is_some())
+            || edges.into_iter().any(|edges| {
+                [&edges.top, &edges.right, &edges.bottom, &edges.left]
+                    .into_iter()
+                    .any(|length| length.calc().is_some())
+            })
+            || spacings.into_iter().any(|length| length.calc().is_some())
+    }
+
+    /// the style of an anonymous box (eg. a text run) inside a box with the `parent` style:
+    /// inherited properties are taken from the parent, the others are left to their initial value.
+    pub fn anonymous(parent: &ComputedStyle) -> Self {
+        Self {
+            visibility: parent.visibility,
+            color: parent.color,
+            font_size: parent.font_size,
+            font_weight: parent.font_weight,
+            font_style: parent.font_style,
+            text_decoration: parent.text_decoration,
+            text_align: parent.text_align,
+            text_transform: parent.text_transform,
+            white_space: parent.white_space,
+            list_style_type: parent.list_style_type,
+            border_collapse: parent.border_collapse,
+            border_spacing: parent.border_spacing,
+            caption_side: parent.caption_side,
+            quotes: parent.quotes.clone(),
+            custom_properties: Arc::clone(&parent.custom_properties),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_bold(&self) -> bool {
+        self.font_weight >= 600 || self.font_emphasis() >= FontEmphasis::Bold
+    }
+
+    pub fn font_emphasis(&self) -> FontEmphasis {
+        FontEmphasis::from_font_size(self.font_size)
+    }
+
+    /// applies `text-transform` to `text`, and uppercases text that's too big to be drawn otherwise
+    pub fn transform_text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if self.font_emphasis() == FontEmphasis::Uppercase {
+            return Cow::Owned(text.to_uppercase());
+        }
+        match self.text_transform {
+            TextTransform::None => Cow::Borrowed(text),
+            TextTransform::Uppercase => Cow::Owned(text.to_uppercase()),
+            TextTransform::Lowercase => Cow::Owned(text.to_lowercase()),
+            TextTransform::Capitalize => {
+                let mut capitalized = String::with_capacity(text.len());
+                let mut word_start = true;
+                for c in text.chars() {
+                    match word_start {
+                        true => capitalized.extend(c.to_uppercase()),
+                        false => capitalized.push(c),
+                    }
+                    word_start = c.is_whitespace();
+                }
+                Cow::Owned(capitalized)
+            }
+        }
+    }
+
+    /// the terminal style that text with this style should be drawn with
+    pub fn to_ratatui_style(&self) -> ratatui::style::Style {
+        use ratatui::style::Modifier;
+
+        let mut style = ratatui::style::Style::default();
+        if let Some(color) = self.color.and_then(to_ratatui_color) {
+            style = style.fg(color);
+        }
+        if let Some(color) = self.background_color.and_then(to_ratatui_color) {
+            style = style.bg(color);
+        }
+        if self.is_bold() {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        if self.font_style != FontStyle::Normal {
+            style = style.add_modifier(Modifier::ITALIC);
+        }
+        if self.text_decoration.underline {
+            style = style.add_modifier(Modifier::UNDERLINED);
+        }
+        if self.text_decoration.line_through {
+            style = style.add_modifier(Modifier::CROSSED_OUT);
+        }
+        if self.visibility != Visibility::Visible {
+            style = style.add_modifier(Modifier::HIDDEN);
+        }
+        style
+    }
+
+    /// the terminal style of a side of the border, `None` being `currentcolor`.
+    /// unlike text, borders don't take the font's modifiers
+    pub fn to_ratatui_border_style(&self, color: Option<Color>) -> ratatui::style::Style {
+        let mut style = ratatui::style::Style::default();
+        if let Some(color) = color.or(self.color).and_then(to_ratatui_color) {
+            style = style.fg(color);
+        }
+        if let Some(color) = self.background_color.and_then(to_ratatui_color) {
+            style = style.bg(color);
+        }
+        style
+    }
+}
+
+/// fully transparent colors are dropped, every other alpha is ignored
+fn to_ratatui_color(color: Color) -> Option<ratatui::style::Color> {
+    match color.a {
+        0 => None,
+        _ => Some(ratatui::style::Color::Rgb(color.r, color.g, color.b)),
+    }
+}
+
+/// Percentages are kept as a fraction of the containing block,
+/// which is only known once taffy lays the tree out
+#[derive(Debug, Clone, PartialEq)]
+pub enum LengthAuto {
+    Auto,
+    Tb(f32),
+    Percent(f32),
+    Calc(CalcLength),
+}
+
+impl LengthAuto {
+    /// a math function starts out as `auto`, until it's resolved after layout, see `LayoutManager::compute`
+    pub fn to_taffy(&self) -> taffy::LengthPercentageAuto {
+        match *self {
+            LengthAuto::Auto | LengthAuto::Calc(_) => taffy::LengthPercentageAuto::auto(),
+            LengthAuto::Tb(value) => taffy::LengthPercentageAuto::length(value),
+            LengthAuto::Percent(fraction) => taffy::LengthPercentageAuto::percent(fraction),
+        }
+    }
+
+    pub fn to_taffy_dimension(&self) -> taffy::Dimension {
+        match *self {
+            LengthAuto::Auto | LengthAuto::Calc(_) => taffy::Dimension::auto(),
+            LengthAuto::Tb(value) => taffy::Dimension::length(value),
+            LengthAuto::Percent(fraction) => taffy::Dimension::percent(fraction),
+        }
+    }
+
+    pub fn calc(&self) -> Option<&CalcLength> {
+        match self {
+            LengthAuto::Calc(calc) => Some(calc),
+            _ => None,
+        }
+    }
+}
+
+/// A `LengthAuto` that can't be `auto`, for padding and gaps
+#[derive(Debug, Clone, PartialEq)]
+pub enum Length {
+    Tb(f32),
+    Percent(f32),
+    Calc(CalcLength),
+}
+
+impl Length {
+    /// a math function starts out as 0, like `LengthAuto::to_taffy`
+    pub fn to_taffy(&self) -> taffy::LengthPercentage {
+        match *self {
+            Length::Tb(value) => taffy::LengthPercentage::length(value),
+            Length::Percent(fraction) => taffy::LengthPercentage::percent(fraction),
+            Length::Calc(_) => taffy::LengthPercentage::length(0.0),
+        }
+    }
+
+    pub fn calc(&self) -> Option<&CalcLength> {
+        match self {
+            Length::Calc(calc) => Some(calc),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edges<T> {
+    pub top: T,
+    pub right: T,
+    pub bottom: T,
+    pub left: T,
+}
+
+impl<T: Clone> Edges<T> {
+    pub fn all(value: T) -> Self {
+        Edges {
+            top: value.clone(),
+            right: value.clone(),
+            bottom: value.clone(),
+            left: value,
+        }
+    }
+
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> taffy::Rect<U> {
+        taffy::Rect {
+            left: f(&self.left),
+            right: f(&self.right),
+            top: f(&self.top),
+            bottom: f(&self.bottom),
+        }
+    }
+}
+
+#[derive(Debug, Display, EnumString, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "kebab-case", ascii_case_insensitive)]
+pub enum Display {
+    Inline,
+    Block,
+    InlineBlock,
+    Flex,
+    InlineFlex,
+    Grid,
+    InlineGrid,
+    ListItem,
+    Table,
+    InlineTable,
+    TableRowGroup,
+    TableHeaderGroup,
+    TableFooterGroup,
+    TableRow,
+    TableCell,
+    TableColumnGroup,
+    TableColumn,
+    TableCaption,
+    Contents,
+    None,
+}
+
+impl Display {
+    /// whether the box sits in a line, next to text, instead of starting a new one.
+    /// `contents` counts as inline, since its children take its place
+    pub fn is_inline_level(self) -> bool {
+        matches!(
+            self,
+            Display::Inline
+                | Display::InlineBlock
+                | Display::InlineFlex
+                | Display::InlineGrid
+                | Display::InlineTable
+                | Display::Contents
+        )
+    }
+
+    pub fn is_table(self) -> bool {
+        matches!(self, Display::Table | Display::InlineTable)
+    }
+
+    /// the boxes that only make sense inside a table, and are wrapped in an anonymous one otherwise
+    pub fn is_table_internal(self) -> bool {
+        matches!(
+            self,
+            Display::TableRowGroup
+                | Display::TableHeaderGroup
+                | Display::TableFooterGroup
+                | Display::TableRow
+                | Display::TableCell
+                | Display::TableColumnGroup
+                | Display::TableColumn
+                | Display::TableCaption
+        )
+    }
+}
+
+#[derive(Debug, Display, EnumString, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "kebab-case", ascii_case_insensitive)]
+pub enum Position {
+    Static,
+    Relative,
+    Absolute,
+    Fixed,
+    Sticky,
+}
+
+impl Position {
+    pub fn is_positioned(self) -> bool {
+        self != Position::Static
+    }
+}
+
+#[derive(Debug, Display, EnumString, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "kebab-case", ascii_case_insensitive)]
+pub enum Overflow {
+    Visible,
+    Hidden,
+    Clip,
+    Scroll,
+    Auto,
+}
+
+impl Overflow {
+    /// whether the content of the box can be scrolled, by the user or programmatically
+    pub fn is_scroll_container(self) -> bool {
+        matches!(self, Overflow::Hidden | Overflow::Scroll | Overflow::Auto)
+    }
+
+    /// `auto` always keeps room for its scrollbar, since a box can't be laid out again
+    /// once it's known to overflow. the scrollbar is only drawn when it does
+    pub fn to_taffy(self) -> taffy::Overflow {
+        match self {
+            Overflow::Visible => taffy::Overflow::Visible,
+            Overflow::Hidden => taffy::Overflow::Hidden,
+            Overflow::Clip => taffy::Overflow::Clip,
+            Overflow::Scroll | Overflow::Auto => taffy::Overflow::Scroll,
+        }
+    }
+}
+
+#[derive(Debug, Display, EnumString, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "kebab-case", ascii_case_insensitive)]
+pub enum ScrollbarWidth {
+    Auto,
+    Thin,
+    None,
+}
+
+impl ScrollbarWidth {
+    /// a scrollbar can't be thinner than a cell
+    pub fn cells(self) -> f32 {
+        match self {
+            ScrollbarWidth::Auto | ScrollbarWidth::Thin => 1.0,
+            ScrollbarWidth::None => 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Display, EnumString, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "kebab-case", ascii_case_insensitive)]
+pub enum BorderStyle {
+    None,
+    Hidden,
+    Dotted,
+    Dashed,
+    Solid,
+    Double,
+    Groove,
+    Ridge,
+    Inset,
+    Outset,
+}
+
+impl BorderStyle {
+    /// whether the side has a border at all
+    pub fn is_drawn(self) -> bool {
+        !matches!(self, BorderStyle::None | BorderStyle::Hidden)
+    }
+}
+
+#[derive(Debug, Display, EnumString, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "kebab-case", ascii_case_insensitive)]
+pub enum BorderCollapse {
+    Separate,
+    /// neighbouring cells share their borders
+    Collapse,
+}
+
+#[derive(Debug, Display, EnumString, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "kebab-case", ascii_case_insensitive)]
+pub enum CaptionSide {
+    Top,
+    Bottom,
+}
+
+#[derive(Debug, Display, EnumString, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "kebab-case", ascii_case_insensitive)]
+pub enum Visibility {
+    Visible,
+    Hidden,
+    Collapse,
+}
+
+/// How big text is shown, since a terminal only has one font size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FontEmphasis {
+    Normal,
+    Bold,
+    /// bold and uppercase
+    Uppercase,
+}
+
+impl FontEmphasis {
+    /// `large` (18px, about an `h3`) and up is bold, `xx-large` (32px, an `h1`) and up is uppercase
+    pub fn from_font_size(font_size: f32) -> Self {
+        match font_size {
+            28.0.. => FontEmphasis::Uppercase,
+            18.0.. => FontEmphasis::Bold,
+            _ => FontEmphasis::Normal,
+        }
+    }
+
+    /// for `<h1>` to `<h6>`, when there's no font size to go by (eg. in reader mode)
+    pub fn from_heading_level(level: u8) -> Self {
+        match level {
+            1 => FontEmphasis::Uppercase,
+            2 | 3 => FontEmphasis::Bold,
+            _ => FontEmphasis::Normal,
+        }
+    }
+}
+
+#[derive(Debug, Display, EnumString, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "kebab-case", ascii_case_insensitive)]
+pub enum FontStyle {
+    Normal,
+    Italic,
+    Oblique,
+}
+
+#[derive(Debug, Display, EnumString, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "kebab-case", ascii_case_insensitive)]
+pub enum TextAlign {
+    Start,
+    End,
+    Left,
+    Right,
+    Center,
+    Justify,
+}
+
+#[derive(Debug, Display, EnumString, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "kebab-case", ascii_case_insensitive)]
+pub enum TextTransform {
+    None,
+    Uppercase,
+    Lowercase,
+    Capitalize,
+}
+
+#[derive(Debug, Display, EnumString, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "kebab-case", ascii_case_insensitive)]
+pub enum WhiteSpace {
+    Normal,
+    Nowrap,
+    Pre,
+    PreWrap,
+    PreLine,
+    BreakSpaces,
+}
+
+/// `normal` and `none` both mean that no `::before`/`::after` box is generated
+#[derive(Debug, Clone, PartialEq)]
+pub enum Content {
+    Normal,
+    None,
+    Items(Vec<ContentItem>),
+}
+
+/// a part of `content`. attributes, counters and quotes are only resolved
+/// when the generated box is created, see `generated_content`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContentItem {
+    String(String),
+    /// `attr(name)`
+    Attr(String),
+    /// `counter(name, style)`
+    Counter(String, CounterStyle),
+    /// `counters(name, separator, style)`
+    Counters(String, String, CounterStyle),
+    OpenQuote,
+    CloseQuote,
+    NoOpenQuote,
+    NoCloseQuote,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Quotes {
+    /// `“` `”`, then `‘` `’` for nested quotes
+    Auto,
+    None,
+    /// open and close quotes, by nesting level
+    Pairs(Arc<[(String, String)]>),
+}
+
+impl Quotes {
+    /// the open and close quotes at nesting level `depth`. the last pair is reused for deeper levels
+    pub fn pair(&self, depth: usize) -> (&str, &str) {
+        const AUTO: [(&str, &str); 2] = [("“", "”"), ("‘", "’")];
+        match self {
+            Quotes::Auto => AUTO[depth.min(AUTO.len() - 1)],
+            Quotes::None => ("", ""),
+            Quotes::Pairs(pairs) => pairs
+                .get(depth.min(pairs.len().saturating_sub(1)))
+                .map_or(("", ""), |(open, close)| (open.as_str(), close.as_str())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextDecoration {
+    pub underline: bool,
+    pub overline: bool,
+    pub line_through: bool,
+}
+
+impl TextDecoration {
+    pub fn union(self, other: TextDecoration) -> Self {
+        TextDecoration {
+            underline: self.underline || other.underline,
+            overline: self.overline || other.overline,
+            line_through: self.line_through || other.line_through,
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use strum_macros::{Display, EnumString};
+
+/// How a counter is turned into text, in `counter()` and `counters()`
+#[derive(Debug, Display, EnumString, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "kebab-case", ascii_case_insensitive)]
+pub enum CounterStyle {
+    Decimal,
+    DecimalLeadingZero,
+    LowerAlpha,
+    UpperAlpha,
+    LowerLatin,
+    UpperLatin,
+    LowerRoman,
+    UpperRoman,
+    LowerGreek,
+    Disc,
+    Circle,
+    Square,
+    None,
+}
+
+impl CounterStyle {
+    /// values a style can't represent (eg. `0` in `lower-alpha`) fall back to `decimal`
+    pub fn format(self, value: i32) -> String {
+        let alphabetic = |letters: &[char]| {
+            if value < 1 {
+                return None;
+            }
+            let mut n = value as usize;
+            let mut text = vec![];
+            while n > 0 {
+                n -= 1;
+                text.push(letters[n % letters.len()]);
+                n /= letters.len();
+            }
+            Some(text.into_iter().rev().collect())
+        };
+        let latin: Vec<char> = ('a'..='z').collect();
+        let greek: Vec<char> = ('α'..='ω').filter(|&c| c != 'ς').collect();
+
+        let formatted = match self {
+            CounterStyle::Decimal => None,
+            CounterStyle::DecimalLeadingZero => match value {
+                0..=9 => Some(format!("0{value}")),
+                -9..=-1 => Some(format!("-0{}", -value)),
+                _ => None,
+            },
+            CounterStyle::LowerAlpha | CounterStyle::LowerLatin => alphabetic(&latin),
+            CounterStyle::UpperAlpha | CounterStyle::UpperLatin => {
+                alphabetic(&latin).map(|text: String| text.to_uppercase())
+            }
+            CounterStyle::LowerRoman => roman(value),
+            CounterStyle::UpperRoman => roman(value).map(|text| text.to_uppercase()),
+            CounterStyle::LowerGreek => alphabetic(&greek),
+            CounterStyle::Disc => Some("•".into()),
+            CounterStyle::Circle => Some("◦".into()),
+            CounterStyle::Square => Some("▪".into()),
+            CounterStyle::None => Some(String::new()),
+        };
+        formatted.unwrap_or_else(|| value.to_string())
+    }
+}
+
+fn roman(value: i32) -> Option<String> {
+    const NUMERALS: [(i32, &str); 13] = [
+        (1000, "m"),
+        (900, "cm"),
+        (500, "d"),
+        (400, "cd"),
+        (100, "c"),
+        (90, "xc"),
+        (50, "l"),
+        (40, "xl"),
+        (10, "x"),
+        (9, "ix"),
+        (5, "v"),
+        (4, "iv"),
+        (1, "i"),
+    ];
+    if !(1..4000).contains(&value) {
+        return None;
+    }
+
+    let mut n = value;
+    let mut text = String::new();
+    for (amount, numeral) in NUMERALS {
+        while n >= amount {
+            text.push_str(numeral);
+            n -= amount;
+        }
+    }
+    Some(text)
+}
+
+/// The counters in scope while the tree is walked in document order.
+///
+/// A counter created by an element is visible to the element, its descendants,
+/// and its following siblings with their descendants,
+/// so the counters created at `depth` are dropped once their parent is done.
+#[derive(Debug, Default)]
+pub struct Counters {
+    /// innermost last
+    stack: Vec<Counter>,
+}
+
+#[derive(Debug)]
+struct Counter {
+    name: String,
+    value: i32,
+    /// the depth of the element that created it
+    depth: usize,
+    /// created by `<ol reversed>`, so list items count down
+    reversed: bool,
+}
+
+impl Counters {
+    /// `counter-reset`: creates a new counter, which replaces the one created by a previous sibling
+    pub fn reset(&mut self, name: &str, value: i32, depth: usize) {
+        self.stack.retain(|c| c.name != name || c.depth != depth);
+        self.stack.push(Counter {
+            name: name.to_string(),
+            value,
+            depth,
+            reversed: false,
+        });
+    }
+
+    /// marks the innermost counter called `name` as counting down, see `is_reversed`
+    pub fn set_reversed(&mut self, name: &str, reversed: bool) {
+        if let Some(counter) = self.stack.iter_mut().rev().find(|c| c.name == name) {
+            counter.reversed = reversed;
+        }
+    }
+
+    pub fn is_reversed(&self, name: &str) -> bool {
+        self.stack
+            .iter()
+            .rev()
+            .find(|c| c.name == name)
+            .is_some_and(|c| c.reversed)
+    }
+
+    /// `counter-increment`: a counter that doesn't exist yet is created at 0 first
+    pub fn increment(&mut self, name: &str, by: i32, depth: usize) {
+        let counter = self.innermost_or_reset(name, depth);
+        counter.value = counter.value.saturating_add(by);
+    }
+
+    /// `counter-set`
+    pub fn set(&mut self, name: &str, value: i32, depth: usize) {
+        self.innermost_or_reset(name, depth).value = value;
+    }
+
+    fn innermost_or_reset(&mut self, name: &str, depth: usize) -> &mut Counter {
+        if !self.stack.iter().any(|c| c.name == name) {
+            self.reset(name, 0, depth);
+        }
+        self.stack
+            .iter_mut()
+            .rev()
+            .find(|c| c.name == name)
+            .unwrap()
+    }
+
+    /// the value of the innermost counter called `name`, 0 if there's none
+    pub fn value(&self, name: &str) -> i32 {
+        self.stack
+            .iter()
+            .rev()
+            .find(|c| c.name == name)
+            .map_or(0, |c| c.value)
+    }
+
+    /// the values of every counter called `name`, outermost first, for `counters()`
+    pub fn values(&self, name: &str) -> Vec<i32> {
+        self.stack
+            .iter()
+            .filter(|c| c.name == name)
+            .map(|c| c.value)
+            .collect()
+    }
+
+    /// drops the counters created at `depth`, once every element at that depth has been seen
+    pub fn leave(&mut self, depth: usize) {
+        self.stack.retain(|c| c.depth < depth);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::render::testing::dom_with;
+
+    #[test]
+    fn styles() {
+        assert_eq!(CounterStyle::Decimal.format(-3), "-3");
+        assert_eq!(CounterStyle::DecimalLeadingZero.format(7), "07");
+        assert_eq!(CounterStyle::LowerAlpha.format(1), "a");
+        assert_eq!(CounterStyle::LowerAlpha.format(28), "ab");
+        assert_eq!(CounterStyle::UpperAlpha.format(0), "0");
+        assert_eq!(CounterStyle::UpperRoman.format(1994), "MCMXCIV");
+        assert_eq!(CounterStyle::LowerRoman.format(4000), "4000");
+        assert_eq!(CounterStyle::LowerGreek.format(2), "β");
+        assert_eq!(CounterStyle::Square.format(3), "▪");
+    }
+
+    #[test]
+    fn list_markers() {
+        use crate::engine::simply::elements::{List, ListKind};
+
+        let dom = dom_with(
+            r#"
+            <ul id="bullets"><li>a</li><li>b</li></ul>
+            <ol id="start" start="3"><li>a</li><li value="10">b</li><li>c</li></ol>
+            <ol id="reversed" reversed><li>a</li><li>b</li><li>c</li></ol>
+            <ol id="roman"><li>a <ol><li>nested</li></ol></li><li>b</li></ol>
+            <ol id="custom"><li>a</li><li>b</li></ol>
+            "#,
+            "li { display: list-item; } ol { list-style-type: decimal; }",
+            r#"
+            #bullets { list-style: square; }
+            #roman { list-style-type: upper-roman; }
+            #roman ol { list-style-type: lower-alpha; }
+            #custom { counter-reset: list-item 5; }
+            #custom li::marker { content: "(" counter(list-item) ") "; color: red; }
+            "#,
+        );
+
+        let markers = |query: &str| -> Vec<String> {
+            let list = dom.query_select(query).unwrap()[0];
+            List::from_node(dom.node(list))
+                .items
+                .into_iter()
+                .map(|item| item.marker)
+                .collect()
+        };
+        assert_eq!(markers("#bullets"), ["▪ ", "▪ "]);
+        assert_eq!(markers("#start"), ["3. ", "10. ", "11. "]);
+        assert_eq!(markers("#reversed"), ["3. ", "2. ", "1. "]);
+        assert_eq!(markers("#roman"), ["I. ", "II. "]);
+        assert_eq!(markers("#roman ol"), ["a. "]);
+        assert_eq!(markers("#custom"), ["(6) ", "(7) "]);
+
+        let list = List::from_node(dom.node(dom.query_select("#roman").unwrap()[0]));
+        assert!(matches!(list.kind, ListKind::Ordered));
+        assert_eq!(list.items[0].content.content[0].content, "a nested");
+    }
+}
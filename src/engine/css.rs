@@ -1,9 +1,19 @@
-use std::{collections::HashMap, rc::Rc, str::FromStr};
+use std::{collections::HashMap, str::FromStr, sync::Arc};
 
-use pest::{iterators::Pair, Parser as _};
+use pest::{
+    iterators::{Pair, Pairs},
+    Parser as _,
+};
 use pest_derive::Parser;
 
-use super::stylesheet::{self, Dimension, Origin, Stylesheet};
+use super::{
+    calc::MathExpr,
+    cascade, custom_properties,
+    loader::{self, ResourceLoader},
+    media::{Comparison, MediaCondition, MediaFeature, MediaQuery, MediaQueryList},
+    shorthands,
+    stylesheet::{self, Dimension, Origin, Stylesheet},
+};
 
 #[derive(Parser)]
 #[grammar = "grammar/css.pest"]
@@ -13,49 +23,447 @@ pub struct CssParser;
 pub fn parse_from_str(
     css: &str,
     origin: Origin,
+) -> Result<stylesheet::Stylesheet, pest::error::Error<Rule>> {
+    parse(css, origin, None, "")
+}
+
+/// like `parse_from_str`, but `@import`s are fetched through `loader`,
+/// relative to the `url` of this stylesheet
+#[allow(clippy::result_large_err)]
+pub fn parse_with_imports(
+    css: &str,
+    origin: Origin,
+    url: &str,
+    loader: &dyn ResourceLoader,
+) -> Result<stylesheet::Stylesheet, pest::error::Error<Rule>> {
+    parse(css, origin, Some(loader), url)
+}
+
+#[allow(clippy::result_large_err)]
+fn parse(
+    css: &str,
+    origin: Origin,
+    loader: Option<&dyn ResourceLoader>,
+    url: &str,
 ) -> Result<stylesheet::Stylesheet, pest::error::Error<Rule>> {
     let pairs = CssParser::parse(Rule::stylesheet, css)?;
 
-    let mut sheet = Stylesheet::new(None, origin);
+    let mut ctx = SheetContext {
+        sheet: Stylesheet::new(None, origin),
+        loader,
+        urls: vec![url.to_string()],
+    };
+    parse_rule_list(pairs, &Scope::default(), &mut ctx, true);
+
+    Ok(ctx.sheet)
+}
+
+/// what applies to every rule in a block
+#[derive(Debug, Clone, Default)]
+struct Scope {
+    /// the conditions of the `@media` blocks (and `@import`s) the rules are in
+    media: Vec<Arc<MediaQueryList>>,
+    /// index into `Stylesheet::layers`
+    layer: usize,
+}
+
+impl Scope {
+    fn with_media(&self, media: MediaQueryList) -> Scope {
+        Scope {
+            media: [&self.media[..], &[Arc::new(media)]].concat(),
+            layer: self.layer,
+        }
+    }
+
+    fn with_layer(&self, layer: usize) -> Scope {
+        Scope {
+            media: self.media.clone(),
+            layer,
+        }
+    }
+}
+
+/// everything parsed into one `Stylesheet`, including the sheets it imports
+struct SheetContext<'a> {
+    sheet: Stylesheet,
+    /// `None` if imports can't be loaded
+    loader: Option<&'a dyn ResourceLoader>,
+    /// the url of the sheet being parsed, after the urls of the sheets importing it.
+    /// used to resolve relative imports and to detect cycles
+    urls: Vec<String>,
+}
+
+/// parses the rules of a stylesheet or of an at-rule's block into `ctx`
+fn parse_rule_list(pairs: Pairs<Rule>, scope: &Scope, ctx: &mut SheetContext, top_level: bool) {
+    // `@import` is only valid at the start of a stylesheet
+    let mut imports_allowed = top_level;
 
     for pair in pairs {
-        let qualified_rule = match pair.as_rule() {
-            Rule::qualified_rule => parse_qualified_rule(pair),
+        match pair.as_rule() {
+            Rule::qualified_rule => {
+                let mut rule = parse_qualified_rule(pair, &mut ctx.sheet.warnings);
+                rule.media = scope.media.clone();
+                rule.layer = scope.layer;
+                ctx.sheet.rules.push(rule);
+            }
+            Rule::at_rule => {
+                let name = at_rule_name(&pair);
+                let is_statement = pair.clone().into_inner().nth(2).is_none();
+                let allowed = imports_allowed;
+                imports_allowed &=
+                    name == "import" || name == "charset" || (name == "layer" && is_statement);
+                parse_at_rule(pair, &name, scope, ctx, allowed);
+                continue;
+            }
+            Rule::bad_rule => ctx
+                .sheet
+                .warnings
+                .push(warning(&pair, stylesheet::ParseWarningKind::InvalidRule)),
+            Rule::stray_brace => ctx
+                .sheet
+                .warnings
+                .push(warning(&pair, stylesheet::ParseWarningKind::StrayBrace)),
             Rule::EOI => break,
             _ => unreachable!(),
-        };
+        }
+        imports_allowed = false;
+    }
+}
+
+fn at_rule_name(pair: &Pair<Rule>) -> String {
+    let keyword = pair.clone().into_inner().next().unwrap();
+    keyword.as_str()[1..].to_ascii_lowercase()
+}
 
-        sheet.rules.push(qualified_rule);
+fn parse_at_rule(
+    pair: Pair<Rule>,
+    name: &str,
+    scope: &Scope,
+    ctx: &mut SheetContext,
+    imports_allowed: bool,
+) {
+    let unknown = warning(&pair, stylesheet::ParseWarningKind::UnknownAtRule);
+    let invalid = warning(&pair, stylesheet::ParseWarningKind::InvalidRule);
+    let mut inner = pair.into_inner().skip(1);
+    let prelude = inner.next().unwrap().as_str().trim();
+    let block = inner.next();
+
+    match (name, block) {
+        ("media", Some(block)) => {
+            let scope = scope.with_media(parse_media_query_list(prelude));
+            parse_rule_list(block.into_inner(), &scope, ctx, false);
+        }
+        ("supports", Some(block)) => {
+            if evaluate_supports_str(prelude) {
+                parse_rule_list(block.into_inner(), scope, ctx, false);
+            }
+        }
+        ("layer", block) => match (parse_layer_names(prelude), block) {
+            // `@layer a, b;` only declares the layers, to fix their order
+            (Some(names), None) if !names.is_empty() => {
+                for name in names {
+                    ctx.sheet.declare_layer(scope.layer, Some(name));
+                }
+            }
+            (Some(names), Some(block)) if names.len() <= 1 => {
+                let layer = ctx.sheet.declare_layer(scope.layer, names.first().copied());
+                parse_rule_list(block.into_inner(), &scope.with_layer(layer), ctx, false);
+            }
+            _ => ctx.sheet.warnings.push(invalid),
+        },
+        ("import", None) if imports_allowed => parse_import(prelude, scope, ctx, invalid),
+        ("import", _) => ctx.sheet.warnings.push(invalid),
+        _ => ctx.sheet.warnings.push(unknown),
     }
+}
 
-    Ok(sheet)
+/// inlines the rules of an imported stylesheet, in place of the `@import`.
+/// `warning` is used if the prelude is invalid, or if the sheet can't be imported
+fn parse_import(
+    prelude: &str,
+    scope: &Scope,
+    ctx: &mut SheetContext,
+    mut warning: stylesheet::ParseWarning,
+) {
+    let Some(import) = CssParser::parse(Rule::import_prelude, prelude)
+        .ok()
+        .and_then(|mut pairs| pairs.next())
+    else {
+        ctx.sheet.warnings.push(warning);
+        return;
+    };
+
+    let mut href = "";
+    let mut import_media = MediaQueryList::default();
+    let mut layer = None;
+    for part in import.into_inner() {
+        match part.as_rule() {
+            Rule::import_url => {
+                let url = part.into_inner().next().unwrap();
+                href = match url.as_rule() {
+                    Rule::string => &url.as_str()[1..url.as_str().len() - 1],
+                    _ => url.as_str(),
+                };
+            }
+            // `layer` alone is an anonymous layer
+            Rule::import_layer => {
+                layer = Some(part.into_inner().next().unwrap());
+            }
+            Rule::import_supports => {
+                // a failing condition is not an error, the sheet is just not imported
+                if !evaluate_supports(part.into_inner().next().unwrap()) {
+                    return;
+                }
+            }
+            Rule::import_media => import_media = parse_media_query_list(part.as_str()),
+            Rule::EOI => {}
+            _ => unreachable!("unexpected {:?} in an @import", part.as_rule()),
+        }
+    }
+
+    warning.kind = stylesheet::ParseWarningKind::UnresolvedImport;
+    let url = loader::resolve_url(ctx.urls.last().unwrap(), href);
+    let text = match ctx.loader {
+        Some(loader) if !ctx.urls.contains(&url) => loader.load(&url),
+        _ => None,
+    };
+    let Some(pairs) = text
+        .as_deref()
+        .and_then(|text| CssParser::parse(Rule::stylesheet, text).ok())
+    else {
+        ctx.sheet.warnings.push(warning);
+        return;
+    };
+
+    let mut scope = match import_media.0.is_empty() {
+        true => scope.clone(),
+        false => scope.with_media(import_media),
+    };
+    if let Some(layer) = layer {
+        let name = (layer.as_rule() == Rule::layer_name).then_some(layer.as_str());
+        scope.layer = ctx.sheet.declare_layer(scope.layer, name);
+    }
+    ctx.urls.push(url);
+    parse_rule_list(pairs, &scope, ctx, true);
+    ctx.urls.pop();
+}
+
+/// the names in the prelude of `@layer`, or `None` if they're not valid
+fn parse_layer_names(raw: &str) -> Option<Vec<&str>> {
+    let names = CssParser::parse(Rule::layer_names, raw).ok()?.next()?;
+    Some(
+        names
+            .into_inner()
+            .filter(|name| name.as_rule() == Rule::layer_name)
+            .map(|name| name.as_str())
+            .collect(),
+    )
+}
+
+/// evaluates the prelude of `@supports`. invalid conditions are never supported
+pub fn evaluate_supports_str(raw: &str) -> bool {
+    CssParser::parse(Rule::supports_query, raw)
+        .ok()
+        .and_then(|mut pairs| pairs.next())
+        .and_then(|query| query.into_inner().next())
+        .is_some_and(evaluate_supports)
+}
+
+/// evaluates a `supports_condition` or one of its parts
+fn evaluate_supports(pair: Pair<Rule>) -> bool {
+    match pair.as_rule() {
+        Rule::supports_condition | Rule::supports_in_parens => {
+            evaluate_supports(pair.into_inner().next().unwrap())
+        }
+        Rule::supports_not => !evaluate_supports(pair.into_inner().next().unwrap()),
+        Rule::supports_and => pair.into_inner().all(evaluate_supports),
+        Rule::supports_or => pair.into_inner().any(evaluate_supports),
+        Rule::declaration => {
+            let mut inner = pair.into_inner();
+            let key = inner.next().unwrap().as_str();
+            let raw = inner.next().map(|raw| raw.as_str()).unwrap_or("");
+            cascade::supports_declaration(key, raw)
+        }
+        Rule::supports_selector => {
+            let raw = pair.into_inner().next().unwrap().as_str().trim();
+            CssParser::parse(Rule::complex_selector, raw)
+                .ok()
+                .and_then(|mut pairs| pairs.next())
+                .is_some_and(|selector| selector.as_str().len() == raw.len())
+        }
+        Rule::general_enclosed => false,
+        _ => unreachable!("unexpected {:?} in a supports condition", pair.as_rule()),
+    }
+}
+
+/// a warning about `pair` being dropped
+fn warning(pair: &Pair<Rule>, kind: stylesheet::ParseWarningKind) -> stylesheet::ParseWarning {
+    let (line, column) = pair.line_col();
+    stylesheet::ParseWarning {
+        kind,
+        line,
+        column,
+        text: pair.as_str().trim().to_string(),
+    }
+}
+
+/// parses the prelude of a `@media` rule.
+/// queries that are not valid turn into `not all`, without affecting the others
+pub fn parse_media_query_list(raw: &str) -> MediaQueryList {
+    if raw.trim().is_empty() {
+        return MediaQueryList::default();
+    }
+
+    let mut queries = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in raw.char_indices().chain([(raw.len(), ',')]) {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                let query = CssParser::parse(Rule::media_query, raw[start..i].trim())
+                    .ok()
+                    .and_then(|mut pairs| pairs.next())
+                    .map(parse_media_query)
+                    .unwrap_or_else(MediaQuery::not_all);
+                queries.push(query);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    MediaQueryList(queries)
 }
 
-pub fn parse_qualified_rule(pair: Pair<Rule>) -> stylesheet::Rule {
+fn parse_media_query(pair: Pair<Rule>) -> MediaQuery {
+    let mut query = MediaQuery {
+        negated: false,
+        media_type: None,
+        condition: None,
+    };
+
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::media_modifier => query.negated = inner.as_str().eq_ignore_ascii_case("not"),
+            Rule::media_type => query.media_type = Some(inner.as_str().to_string()),
+            Rule::media_type_condition | Rule::media_condition => {
+                query.condition = Some(parse_media_condition(inner))
+            }
+            Rule::EOI => {}
+            _ => unreachable!("unexpected {:?} in a media query", inner.as_rule()),
+        }
+    }
+
+    query
+}
+
+/// parses a `media_condition`, `media_type_condition` or one of their parts
+fn parse_media_condition(pair: Pair<Rule>) -> MediaCondition {
+    match pair.as_rule() {
+        Rule::media_condition | Rule::media_type_condition | Rule::media_in_parens => {
+            parse_media_condition(pair.into_inner().next().unwrap())
+        }
+        Rule::media_not => MediaCondition::Not(Box::new(parse_media_condition(
+            pair.into_inner().next().unwrap(),
+        ))),
+        Rule::media_and => {
+            MediaCondition::And(pair.into_inner().map(parse_media_condition).collect())
+        }
+        Rule::media_or => {
+            MediaCondition::Or(pair.into_inner().map(parse_media_condition).collect())
+        }
+        Rule::media_feature => MediaCondition::Feature(parse_media_feature(pair)),
+        _ => unreachable!("unexpected {:?} in a media condition", pair.as_rule()),
+    }
+}
+
+fn parse_media_feature(pair: Pair<Rule>) -> MediaFeature {
+    let inner = pair.into_inner().next().unwrap();
+    let is_range = inner.as_rule() == Rule::media_range;
+
+    let mut name = String::new();
+    let mut comparisons = vec![];
+    // a value on the left of the name is compared the other way around
+    let mut pending: Option<stylesheet::Value> = None;
+    let mut comparison = Comparison::Eq;
+    for part in inner.into_inner() {
+        match part.as_rule() {
+            Rule::media_feature_name => name = part.as_str().to_ascii_lowercase(),
+            Rule::media_comparison => {
+                comparison = match part.as_str() {
+                    "<" => Comparison::Lt,
+                    "<=" => Comparison::Le,
+                    ">" => Comparison::Gt,
+                    ">=" => Comparison::Ge,
+                    _ => Comparison::Eq,
+                };
+                if let Some(value) = pending.take() {
+                    comparisons.push((comparison.flip(), value));
+                }
+            }
+            Rule::value if name.is_empty() => pending = Some(parse_value(part)),
+            Rule::value => comparisons.push((comparison, parse_value(part))),
+            _ => unreachable!("unexpected {:?} in a media feature", part.as_rule()),
+        }
+    }
+
+    if !is_range {
+        if let Some(stripped) = name.strip_prefix("min-") {
+            comparisons
+                .iter_mut()
+                .for_each(|(c, _)| *c = Comparison::Ge);
+            name = stripped.to_string();
+        } else if let Some(stripped) = name.strip_prefix("max-") {
+            comparisons
+                .iter_mut()
+                .for_each(|(c, _)| *c = Comparison::Le);
+            name = stripped.to_string();
+        }
+    }
+
+    MediaFeature { name, comparisons }
+}
+
+pub fn parse_qualified_rule(
+    pair: Pair<Rule>,
+    warnings: &mut Vec<stylesheet::ParseWarning>,
+) -> stylesheet::Rule {
     let mut inner = pair.into_inner();
     let selector = inner.next().unwrap();
     let selector = parse_selector(selector);
 
     let declarations = inner.next().unwrap();
-    let prop_map = parse_declarations(declarations);
+    let prop_map = parse_declarations(declarations, warnings);
     stylesheet::Rule {
         selector,
         props: prop_map,
+        media: vec![],
+        layer: 0,
     }
 }
 
-pub fn parse_declarations(pair: Pair<Rule>) -> stylesheet::PropMap {
+/// parses a `declaration_list`, dropping the invalid declarations into `warnings`
+pub fn parse_declarations(
+    pair: Pair<Rule>,
+    warnings: &mut Vec<stylesheet::ParseWarning>,
+) -> stylesheet::PropMap {
     let mut prop_map = HashMap::new();
     for declaration in pair.into_inner() {
-        let decls = parse_declaration(declaration);
+        let invalid = warning(
+            &declaration,
+            stylesheet::ParseWarningKind::InvalidDeclaration,
+        );
+        let decls = match declaration.as_rule() {
+            Rule::declaration => parse_declaration(declaration),
+            _ => vec![],
+        };
+        if decls.is_empty() {
+            warnings.push(invalid);
+        }
         for (key, value) in decls {
-            prop_map.insert(
-                Rc::from(key),
-                Rc::from(stylesheet::PropertyValue {
-                    value,
-                    important: false, // TODO: parse the important
-                }),
-            );
+            prop_map.insert(Arc::from(key), Arc::from(value));
         }
     }
     prop_map
@@ -65,6 +473,7 @@ pub fn parse_selector(pair: Pair<Rule>) -> stylesheet::ComplexSelector {
     let mut selector = stylesheet::ComplexSelector {
         inner: vec![],
         combinators: vec![],
+        pseudo_element: None,
     };
 
     for compound_or_combinator in pair.into_inner() {
@@ -88,6 +497,10 @@ pub fn parse_selector(pair: Pair<Rule>) -> stylesheet::ComplexSelector {
 
                 selector.inner.push(compound);
             },
+            Rule::pseudo_element => {
+                let name = compound_or_combinator.into_inner().next().unwrap().as_str();
+                selector.pseudo_element = Some(stylesheet::PseudoElement::from_str(name).unwrap());
+            },
             Rule::combinator => {
                 match compound_or_combinator.as_str().trim() {
                     "" => selector.combinators.push(stylesheet::Combinator::Descendant),
@@ -104,17 +517,122 @@ pub fn parse_selector(pair: Pair<Rule>) -> stylesheet::ComplexSelector {
     selector
 }
 
-pub fn parse_declaration(pair: Pair<Rule>) -> Vec<(String, stylesheet::Value)> {
+/// returns nothing if the value is not valid
+pub fn parse_declaration(pair: Pair<Rule>) -> Vec<(String, stylesheet::PropertyValue)> {
     let mut inner = pair.into_inner();
-    let key = inner.next().unwrap().as_str().to_string();
-    let mut decls = vec![];
+    let key = inner.next().unwrap().as_str();
+    let raw = inner.next().map(|raw| raw.as_str()).unwrap_or("");
+    parse_declaration_str(key, raw)
+}
+
+/// parses the declaration `key: raw`, with shorthands expanded into their longhands, see `shorthands::expand`.
+/// returns nothing if the value is not valid for every longhand
+pub fn parse_declaration_str(key: &str, raw: &str) -> Vec<(String, stylesheet::PropertyValue)> {
+    // a shorthand with `var()` is only expanded once it's substituted, by the cascade
+    if !shorthands::is_shorthand(key) || custom_properties::contains_var(raw) {
+        let value = parse_declaration_value(key, raw);
+        return value
+            .map(|value| vec![(key.to_string(), value)])
+            .unwrap_or_default();
+    }
+
+    let (raw, important) = strip_important(raw.trim());
+    let Some(value) = parse_property_value(raw) else {
+        return vec![];
+    };
+    let Some(longhands) = shorthands::expand(key, value.as_list()) else {
+        return vec![];
+    };
+    if !longhands
+        .iter()
+        .all(|(key, value)| cascade::is_valid_value(key, value))
+    {
+        return vec![];
+    }
+    longhands
+        .into_iter()
+        .map(|(key, value)| (key, stylesheet::PropertyValue { value, important }))
+        .collect()
+}
+
+/// parses the value of the `key` declaration, along with its `!important`.
+/// returns `None` if it's not valid, including when it parses but isn't a value of `key`,
+/// so that an earlier declaration of `key` is used instead
+pub fn parse_declaration_value(key: &str, raw: &str) -> Option<stylesheet::PropertyValue> {
+    let (raw, important) = strip_important(raw.trim());
 
-    // TEMP: should support multi value syntax
-    let value = parse_value(inner.next().unwrap());
+    if key.starts_with("--") {
+        let value = custom_properties::parse_custom_value(raw);
+        return Some(stylesheet::PropertyValue { value, important });
+    }
+    if custom_properties::contains_var(raw) {
+        // can only be parsed once the variables are substituted, at computed-value time
+        let value = stylesheet::Value::Raw(raw.to_string());
+        return Some(stylesheet::PropertyValue { value, important });
+    }
+
+    let value = parse_property_value(raw)?;
+    cascade::is_valid_value(key, &value).then_some(stylesheet::PropertyValue { value, important })
+}
 
-    decls.push((key, value));
+/// parses the value of a declaration (without `!important`)
+pub fn parse_property_value(raw: &str) -> Option<stylesheet::Value> {
+    let value = parse_value_str(raw)?;
+    // a unit we don't know makes the whole declaration invalid
+    (!has_invalid_unit(&value)).then_some(value)
+}
 
-    decls
+fn has_invalid_unit(value: &stylesheet::Value) -> bool {
+    fn math(expr: &MathExpr) -> bool {
+        match expr {
+            MathExpr::Value(dimension) => matches!(dimension.unit, stylesheet::Unit::Invalid),
+            MathExpr::Add(lhs, rhs)
+            | MathExpr::Sub(lhs, rhs)
+            | MathExpr::Mul(lhs, rhs)
+            | MathExpr::Div(lhs, rhs) => math(lhs) || math(rhs),
+            MathExpr::Min(args) | MathExpr::Max(args) => args.iter().any(math),
+            MathExpr::Clamp(min, value, max) => math(min) || math(value) || math(max),
+        }
+    }
+
+    match value {
+        stylesheet::Value::Dimension(dimension) => {
+            matches!(dimension.unit, stylesheet::Unit::Invalid)
+        }
+        stylesheet::Value::Math(expr) => math(expr),
+        stylesheet::Value::Function(_, values) | stylesheet::Value::List(values) => {
+            values.iter().any(has_invalid_unit)
+        }
+        _ => false,
+    }
+}
+
+/// splits the `!important` (or `! IMPORTANT`) off the end of a value
+fn strip_important(raw: &str) -> (&str, bool) {
+    match raw.rfind('!') {
+        Some(bang) if raw[bang + 1..].trim().eq_ignore_ascii_case("important") => {
+            (raw[..bang].trim_end(), true)
+        }
+        _ => (raw, false),
+    }
+}
+
+/// parses the text of a declaration's value, with a `Value::List` if it has several values.
+/// a single value is never wrapped in a list, so that css-wide keywords still work,
+/// and several values for a property that takes one are invalid once they're converted.
+/// returns `None` if it's empty or not a valid value
+pub fn parse_value_str(raw: &str) -> Option<stylesheet::Value> {
+    let value_list = CssParser::parse(Rule::value_list, raw).ok()?.next()?;
+    let mut values: Vec<_> = value_list
+        .into_inner()
+        .filter(|pair| matches!(pair.as_rule(), Rule::value))
+        .map(parse_value)
+        .collect();
+    match values.len() {
+        0 => None,
+        1 => values.pop(),
+        _ => Some(stylesheet::Value::List(values)),
+    }
 }
 
 pub fn parse_value(value: Pair<Rule>) -> stylesheet::Value {
@@ -126,13 +644,277 @@ pub fn parse_value(value: Pair<Rule>) -> stylesheet::Value {
 
     match inner.as_rule() {
         Rule::ident => stylesheet::Value::Keyword(inner.as_str().to_string()),
-        Rule::dimension => {
-            let mut dimension_inner = inner.into_inner();
-            stylesheet::Value::Dimension(Dimension {
-                value: dimension_inner.next().unwrap().as_str().parse().unwrap(),
-                unit: stylesheet::Unit::from_str(dimension_inner.next().unwrap().as_str()).unwrap(),
-            })
+        Rule::hex_color => match stylesheet::Color::from_hex(inner.as_str()) {
+            Some(color) => stylesheet::Value::Color(color),
+            None => unreachable!("the grammar only allows valid hex colors"),
+        },
+        Rule::dimension => stylesheet::Value::Dimension(parse_dimension(inner)),
+        Rule::math_function => stylesheet::Value::Math(parse_math_function(inner)),
+        Rule::string => stylesheet::Value::String(unescape_string(inner.as_str())),
+        Rule::function => {
+            let mut inner = inner.into_inner();
+            let name = inner.next().unwrap().as_str().trim_end_matches('(');
+            stylesheet::Value::Function(name.to_ascii_lowercase(), inner.map(parse_value).collect())
         }
         _ => unreachable!(),
     }
 }
+
+/// turns a quoted `string` into its contents, eg. `"\2014  a\"b"` into `— a"b`
+fn unescape_string(quoted: &str) -> String {
+    let mut chars = quoted[1..quoted.len() - 1].chars().peekable();
+    let mut unescaped = String::new();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        let mut hex = String::new();
+        while let Some(digit) = chars.next_if(|d| d.is_ascii_hexdigit() && hex.len() < 6) {
+            hex.push(digit);
+        }
+        if hex.is_empty() {
+            // an escaped newline is a line continuation
+            match chars.next() {
+                Some('\n') | None => {}
+                Some(c) => unescaped.push(c),
+            }
+            continue;
+        }
+        // a single whitespace ends the escape
+        chars.next_if(|c| c.is_ascii_whitespace());
+        let code = u32::from_str_radix(&hex, 16).unwrap();
+        unescaped.push(
+            char::from_u32(code)
+                .filter(|&c| c != '\0')
+                .unwrap_or('\u{fffd}'),
+        );
+    }
+
+    unescaped
+}
+
+fn parse_dimension(pair: Pair<Rule>) -> Dimension {
+    let mut inner = pair.into_inner();
+    Dimension {
+        value: inner.next().unwrap().as_str().parse().unwrap(),
+        // the unit is optional (eg. `flex-grow: 2`)
+        unit: stylesheet::Unit::from_str(inner.next().map(|unit| unit.as_str()).unwrap_or(""))
+            .unwrap(),
+    }
+}
+
+fn parse_math_function(pair: Pair<Rule>) -> MathExpr {
+    let function = pair.into_inner().next().unwrap();
+    let rule = function.as_rule();
+    let mut args: Vec<MathExpr> = function.into_inner().map(parse_calc_sum).collect();
+
+    match rule {
+        Rule::calc_function => args.pop().unwrap(),
+        Rule::min_function => MathExpr::Min(args),
+        Rule::max_function => MathExpr::Max(args),
+        Rule::clamp_function => {
+            let max = args.pop().unwrap();
+            let value = args.pop().unwrap();
+            let min = args.pop().unwrap();
+            MathExpr::Clamp(Box::new(min), Box::new(value), Box::new(max))
+        }
+        _ => unreachable!("math_function contains something that isn't a math function"),
+    }
+}
+
+/// parses a `calc_sum` or `calc_product`, folding the operators left to right
+fn parse_calc_sum(pair: Pair<Rule>) -> MathExpr {
+    let mut inner = pair.into_inner();
+    let mut expr = parse_calc_operand(inner.next().unwrap());
+
+    while let (Some(operator), Some(operand)) = (inner.next(), inner.next()) {
+        let lhs = Box::new(expr);
+        let rhs = Box::new(parse_calc_operand(operand));
+        expr = match operator.as_str() {
+            "+" => MathExpr::Add(lhs, rhs),
+            "-" => MathExpr::Sub(lhs, rhs),
+            "*" => MathExpr::Mul(lhs, rhs),
+            "/" => MathExpr::Div(lhs, rhs),
+            _ => unreachable!("wrong math operator"),
+        };
+    }
+
+    expr
+}
+
+fn parse_calc_operand(pair: Pair<Rule>) -> MathExpr {
+    match pair.as_rule() {
+        Rule::calc_sum | Rule::calc_product => parse_calc_sum(pair),
+        Rule::dimension => MathExpr::Value(parse_dimension(pair)),
+        Rule::math_function => parse_math_function(pair),
+        _ => unreachable!("unexpected {:?} in a math expression", pair.as_rule()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylesheet::ParseWarningKind;
+
+    #[test]
+    fn error_recovery() {
+        let sheet = parse_from_str(
+            r#"
+            @font-face { font-family: x; src: url("a.woff"); }
+            a::selection { content: "}"; }
+            p { color: red; *zoom: 1; width: 10tb !important; height: 1tb !ie; margin: 1tb }
+            @charset "utf-8";
+            }
+            a:hover, b { color: blue; }
+            @media screen { li:nth-child(2n) { color: red; } ul { color: blue; } }
+            div { filter: progid:DXImageTransform.Microsoft.gradient(a=1); color: green
+            "#,
+            Origin::Author,
+        )
+        .unwrap();
+
+        let selectors: Vec<_> = sheet
+            .rules
+            .iter()
+            .map(|rule| rule.selector.inner[0].tag_name.as_deref().unwrap())
+            .collect();
+        assert_eq!(selectors, ["p", "ul", "div"]);
+
+        let p = &sheet.rules[0].props;
+        let mut keys: Vec<_> = p.keys().map(|k| k.as_str()).collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            [
+                "color",
+                "margin-bottom",
+                "margin-left",
+                "margin-right",
+                "margin-top",
+                "width"
+            ]
+        );
+        assert!(p[&Arc::new("width".to_string())].important);
+        assert!(!p[&Arc::new("color".to_string())].important);
+        assert_eq!(sheet.rules[2].props.len(), 1);
+
+        let kinds: Vec<_> = sheet.warnings.iter().map(|w| w.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                ParseWarningKind::UnknownAtRule,
+                ParseWarningKind::InvalidRule,
+                ParseWarningKind::InvalidDeclaration,
+                ParseWarningKind::InvalidDeclaration,
+                ParseWarningKind::UnknownAtRule,
+                ParseWarningKind::StrayBrace,
+                ParseWarningKind::InvalidRule,
+                ParseWarningKind::InvalidRule,
+                ParseWarningKind::InvalidDeclaration,
+            ]
+        );
+        assert_eq!(sheet.warnings[2].text, "*zoom: 1");
+        assert_eq!(sheet.warnings[2].line, 4);
+    }
+
+    fn tags(sheet: &Stylesheet) -> Vec<&str> {
+        sheet
+            .rules
+            .iter()
+            .map(|rule| rule.selector.inner[0].tag_name.as_deref().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn imports() {
+        let loader: HashMap<String, String> = [
+            (
+                "https://a.com/css/base.css",
+                "@import '../reset.css'; base {}",
+            ),
+            ("https://a.com/reset.css", "reset {}"),
+            ("https://a.com/css/print.css", "print {}"),
+            ("https://a.com/css/grid.css", "grid {}"),
+            (
+                "https://a.com/css/loop.css",
+                "@import url(loop.css); loop {}",
+            ),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        let sheet = parse_with_imports(
+            r#"
+            @charset "utf-8";
+            @import url("base.css");
+            @import "print.css" print;
+            @import url(grid.css) supports(display: grid);
+            @import url(flow.css) supports(display: run-in);
+            @import "/css/loop.css" layer;
+            @import "missing.css";
+            main {}
+            @import "base.css";
+            "#,
+            Origin::Author,
+            "https://a.com/css/main.css",
+            &loader,
+        )
+        .unwrap();
+
+        assert_eq!(
+            tags(&sheet),
+            ["reset", "base", "print", "grid", "loop", "main"]
+        );
+        assert!(sheet.rules[0].media.is_empty());
+        assert_eq!(sheet.rules[2].media.len(), 1);
+        // `layer` alone puts the imported rules in an anonymous layer
+        assert_eq!(sheet.rules[4].layer, 1);
+        assert_eq!(sheet.layers[1].name, None);
+
+        let kinds: Vec<_> = sheet.warnings.iter().map(|w| w.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                ParseWarningKind::UnknownAtRule,
+                ParseWarningKind::UnresolvedImport,
+                ParseWarningKind::UnresolvedImport,
+                ParseWarningKind::InvalidRule,
+            ]
+        );
+        assert_eq!(sheet.warnings[1].text, "@import url(loop.css);");
+
+        assert_eq!(loader::resolve_url("a/b.css", "./c/../d.css"), "a/d.css");
+        assert_eq!(
+            loader::resolve_url("http://x.org", "/y.css"),
+            "http://x.org/y.css"
+        );
+    }
+
+    #[test]
+    fn supports() {
+        let sheet = parse_from_str(
+            r#"
+            @supports (display: flex) { flex {} }
+            @supports (display: table-cell) { table {} }
+            @supports not (display: table-cell) { fallback {} }
+            @supports (width: calc(100% - 1tb)) and (color: var(--x)) { calc {} }
+            @supports (colour: red) or (float: left) { unknown {} }
+            @supports selector(a > b) or font-tech(color-COLRv1) { selector {} }
+            @supports selector(a:has(b)) { has {} }
+            @supports (--anything: { }) { custom {} }
+            @supports (width: red) { invalid {} }
+            "#,
+            Origin::Author,
+        )
+        .unwrap();
+
+        assert_eq!(
+            tags(&sheet),
+            ["flex", "table", "calc", "selector", "custom"]
+        );
+        assert!(sheet.warnings.is_empty(), "{:?}", sheet.warnings);
+    }
+}
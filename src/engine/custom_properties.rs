@@ -0,0 +1,264 @@
+use std::collections::{HashMap, HashSet};
+
+use super::stylesheet::{Value, CSS_WIDE_KEYWORDS};
+
+/// The computed custom properties (`--x`) of a node, with every `var()` already substituted.
+/// A property that is missing has the guaranteed-invalid value.
+pub type CustomProperties = HashMap<String, String>;
+
+/// substitution results longer than this are treated as invalid,
+/// so that a few exponentially nested variables can't eat all the memory
+const MAX_SUBSTITUTION_LEN: usize = 64 * 1024;
+
+/// the value of a custom property declaration: css-wide keywords are kept as keywords,
+/// everything else is stored as written
+pub fn parse_custom_value(raw: &str) -> Value {
+    let raw = raw.trim();
+    match CSS_WIDE_KEYWORDS
+        .iter()
+        .find(|keyword| keyword.eq_ignore_ascii_case(raw))
+    {
+        Some(keyword) => Value::Keyword(keyword.to_string()),
+        None => Value::Raw(raw.to_string()),
+    }
+}
+
+pub fn contains_var(raw: &str) -> bool {
+    raw.to_ascii_lowercase().contains("var(")
+}
+
+/// replaces every `var(--name, fallback)` in `raw`.
+///
+/// `lookup` gives the value of a custom property, or `None` if it's guaranteed-invalid,
+/// in which case the fallback is used.
+/// returns `None` if a variable has neither a value nor a fallback,
+/// which makes the whole declaration invalid at computed-value time.
+pub fn substitute(raw: &str, lookup: &mut dyn FnMut(&str) -> Option<String>) -> Option<String> {
+    let mut result = String::new();
+    let mut rest = raw;
+
+    while let Some(start) = find_var(rest) {
+        result.push_str(&rest[..start]);
+        let args_start = start + "var(".len();
+        let args_len = closing_paren(&rest[args_start..])?;
+        let args = &rest[args_start..args_start + args_len];
+
+        let (name, fallback) = match top_level_comma(args) {
+            Some(comma) => (args[..comma].trim(), Some(&args[comma + 1..])),
+            None => (args.trim(), None),
+        };
+        if !name.starts_with("--") {
+            return None;
+        }
+
+        let value = match (lookup(name), fallback) {
+            (Some(value), _) => value,
+            (None, Some(fallback)) => substitute(fallback.trim(), lookup)?,
+            (None, None) => return None,
+        };
+        result.push_str(&value);
+        if result.len() > MAX_SUBSTITUTION_LEN {
+            return None;
+        }
+
+        rest = &rest[args_start + args_len + 1..];
+    }
+
+    result.push_str(rest);
+    Some(result)
+}
+
+/// finds the next `var(`, ignoring strings and names that just end in `var` (eg. `myvar(`)
+fn find_var(raw: &str) -> Option<usize> {
+    let bytes = raw.as_bytes();
+    let mut quote = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        match (quote, bytes[i]) {
+            (Some(_), b'\\') => i += 1,
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, b'"' | b'\'') => quote = Some(bytes[i]),
+            (None, _) => {
+                let is_var = raw
+                    .get(i..i + 4)
+                    .is_some_and(|s| s.eq_ignore_ascii_case("var("));
+                let starts_word = i == 0
+                    || !(bytes[i - 1].is_ascii_alphanumeric() || b"-_".contains(&bytes[i - 1]));
+                if is_var && starts_word {
+                    return Some(i);
+                }
+            }
+        }
+        i += 1;
+    }
+    None
+}
+
+/// returns the position of the `)` closing the block that `raw` is the inside of
+fn closing_paren(raw: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in raw.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(i),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn top_level_comma(raw: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in raw.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Resolves the custom properties declared on a node, on top of the inherited ones.
+///
+/// Properties that reference each other in a cycle are all guaranteed-invalid,
+/// even if they have a fallback.
+pub struct CustomResolver<'a> {
+    /// the raw value of every custom property declared on the node.
+    /// `None` means the property is declared as guaranteed-invalid (eg. with `initial`)
+    pub declared: HashMap<&'a str, Option<&'a str>>,
+    pub inherited: &'a CustomProperties,
+    resolved: HashMap<String, Option<String>>,
+    stack: Vec<String>,
+    cyclic: HashSet<String>,
+}
+
+impl<'a> CustomResolver<'a> {
+    pub fn new(
+        declared: HashMap<&'a str, Option<&'a str>>,
+        inherited: &'a CustomProperties,
+    ) -> Self {
+        Self {
+            declared,
+            inherited,
+            resolved: HashMap::new(),
+            stack: vec![],
+            cyclic: HashSet::new(),
+        }
+    }
+
+    pub fn resolve_all(mut self) -> CustomProperties {
+        let mut properties = self.inherited.clone();
+        let names: Vec<&str> = self.declared.keys().copied().collect();
+        for name in names {
+            match self.get(name) {
+                Some(value) => properties.insert(name.to_string(), value),
+                None => properties.remove(name),
+            };
+        }
+        properties
+    }
+
+    pub fn get(&mut self, name: &str) -> Option<String> {
+        if let Some(resolved) = self.resolved.get(name) {
+            return resolved.clone();
+        }
+        let Some(raw) = self.declared.get(name).copied() else {
+            return self.inherited.get(name).cloned();
+        };
+        let raw = raw?;
+
+        if let Some(position) = self.stack.iter().position(|n| n == name) {
+            self.cyclic.extend(self.stack[position..].iter().cloned());
+            return None;
+        }
+
+        self.stack.push(name.to_string());
+        let value = substitute(raw, &mut |n| self.get(n));
+        self.stack.pop();
+
+        let value = value.filter(|_| !self.cyclic.contains(name));
+        self.resolved.insert(name.to_string(), value.clone());
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> CustomProperties {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn substitution_and_fallbacks() {
+        let props = vars(&[("--a", "red"), ("--b", "1px 2px")]);
+        let mut lookup = |name: &str| props.get(name).cloned();
+
+        assert_eq!(substitute("var(--a)", &mut lookup).as_deref(), Some("red"));
+        assert_eq!(
+            substitute("var(--b) var(--a)", &mut lookup).as_deref(),
+            Some("1px 2px red")
+        );
+        assert_eq!(
+            substitute("var(--missing, blue)", &mut lookup).as_deref(),
+            Some("blue")
+        );
+        assert_eq!(
+            substitute("var(--missing, var(--a))", &mut lookup).as_deref(),
+            Some("red")
+        );
+        assert_eq!(
+            substitute("var(--a, var(--missing))", &mut lookup).as_deref(),
+            Some("red")
+        );
+        assert_eq!(
+            substitute("var(--missing,)", &mut lookup).as_deref(),
+            Some("")
+        );
+        assert_eq!(substitute("var(--missing)", &mut lookup), None);
+        assert_eq!(
+            substitute("var(--missing, var(--missing2))", &mut lookup),
+            None
+        );
+        assert_eq!(substitute("var(a)", &mut lookup), None);
+        assert_eq!(
+            substitute("\"var(--a)\" myvar(--a)", &mut lookup).as_deref(),
+            Some("\"var(--a)\" myvar(--a)")
+        );
+    }
+
+    #[test]
+    fn cycles() {
+        let inherited = vars(&[("--inherited", "green")]);
+        let declared = HashMap::from([
+            ("--a", Some("var(--b)")),
+            ("--b", Some("var(--a, red)")),
+            ("--self", Some("var(--self, blue)")),
+            ("--uses-cycle", Some("var(--a, yellow)")),
+            ("--uses-inherited", Some("var(--inherited)")),
+            ("--initial", None),
+            (
+                "--inherited-overridden",
+                Some("var(--inherited) var(--inherited)"),
+            ),
+        ]);
+
+        let props = CustomResolver::new(declared, &inherited).resolve_all();
+        assert_eq!(props.get("--a"), None);
+        assert_eq!(props.get("--b"), None);
+        assert_eq!(props.get("--self"), None);
+        assert_eq!(props.get("--initial"), None);
+        assert_eq!(props["--uses-cycle"], "yellow");
+        assert_eq!(props["--uses-inherited"], "green");
+        assert_eq!(props["--inherited-overridden"], "green green");
+        assert_eq!(props["--inherited"], "green");
+    }
+}
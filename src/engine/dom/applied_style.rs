@@ -1,11 +1,17 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::engine::stylesheet::{self, PropertyValue};
 
 #[derive(Debug, Clone)]
 pub struct AppliedStyle {
-    pub key: Rc<String>,
-    pub value: Rc<PropertyValue>,
+    pub key: Arc<String>,
+    pub value: Arc<PropertyValue>,
     pub origin: stylesheet::Origin,
     pub rule_specificity: stylesheet::Specificity,
+    /// position of the declaring rule across all stylesheets, in the order they were applied.
+    /// when everything else is equal, the style declared last wins.
+    pub source_order: usize,
+    /// position of the rule's cascade layer, see `stylesheet::layer_orders`.
+    /// `STYLE_ATTRIBUTE_LAYER` for inline styles
+    pub layer_order: usize,
 }
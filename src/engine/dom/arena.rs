@@ -0,0 +1,401 @@
+use std::ops::{Index, IndexMut};
+
+use super::{
+    node_ref::{Error, Result},
+    Dom, Node,
+};
+
+/// A node of a `Dom`: the index of the node in the dom's arena.
+/// it only means something for the dom it came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    /// the root is the first node of every dom
+    pub(super) const ROOT: NodeId = NodeId(0);
+
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl Index<NodeId> for Dom {
+    type Output = Node;
+
+    fn index(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0]
+    }
+}
+
+impl IndexMut<NodeId> for Dom {
+    fn index_mut(&mut self, id: NodeId) -> &mut Node {
+        &mut self.nodes[id.0]
+    }
+}
+
+impl Dom {
+    /// moves `node` into the arena, as an orphan that can be appended somewhere with `append_child`
+    pub fn create_node(&mut self, node: Node) -> NodeId {
+        self.nodes.push(node);
+        NodeId(self.nodes.len() - 1)
+    }
+
+    /// links the orphan `child` as the last child of `parent`, and returns it
+    ///
+    /// # Errors
+    /// see `insert_before`
+    pub fn append_child(&mut self, parent: NodeId, child: NodeId) -> Result<NodeId> {
+        self.insert_before(parent, child, None)
+    }
+
+    /// links the orphan `child` into the children of `parent`, right before `reference`,
+    /// or as the last child if there's no `reference`, and returns it.
+    /// nothing changes if it fails
+    ///
+    /// # Errors
+    /// `NotOrphan` if `child` already has a parent, `HierarchyRequest` if it's the root or an ancestor of `parent`,
+    /// and `NotAChild` if `reference` isn't a child of `parent`
+    pub fn insert_before(
+        &mut self,
+        parent: NodeId,
+        child: NodeId,
+        reference: Option<NodeId>,
+    ) -> Result<NodeId> {
+        self.check_insertable(parent, child)?;
+        if reference.is_some_and(|reference| self[reference].parent != Some(parent)) {
+            return Err(Error::NotAChild);
+        }
+        self.link(parent, child, reference);
+        Ok(child)
+    }
+
+    /// whether `child` can be inserted into `parent`, see `insert_before`
+    pub(super) fn check_insertable(&self, parent: NodeId, child: NodeId) -> Result<()> {
+        if self[child].parent.is_some() {
+            return Err(Error::NotOrphan);
+        }
+        if child == self.root || self.is_inclusive_ancestor(child, parent) {
+            return Err(Error::HierarchyRequest);
+        }
+        Ok(())
+    }
+
+    /// `insert_before` without the checks, for when they're known to pass, eg. for a new node
+    pub(super) fn link(&mut self, parent: NodeId, child: NodeId, reference: Option<NodeId>) {
+        let (previous, next) = match reference {
+            Some(reference) => (self[reference].previous_sibling, Some(reference)),
+            None => (self[parent].last_child, None),
+        };
+        match previous {
+            Some(previous) => self[previous].next_sibling = Some(child),
+            None => self[parent].first_child = Some(child),
+        }
+        match next {
+            Some(next) => self[next].previous_sibling = Some(child),
+            None => self[parent].last_child = Some(child),
+        }
+
+        let node = &mut self[child];
+        node.parent = Some(parent);
+        node.previous_sibling = previous;
+        node.next_sibling = next;
+    }
+
+    /// unlinks `id` from its parent and siblings, along with its subtree.
+    /// it stays in the arena as an orphan, which can be inserted somewhere else.
+    /// does nothing if it's already an orphan
+    pub fn detach(&mut self, id: NodeId) {
+        let Some(parent) = self[id].parent else {
+            return;
+        };
+        let node = &mut self[id];
+        let (previous, next) = (node.previous_sibling, node.next_sibling);
+        node.parent = None;
+        node.previous_sibling = None;
+        node.next_sibling = None;
+
+        match previous {
+            Some(previous) => self[previous].next_sibling = next,
+            None => self[parent].first_child = next,
+        }
+        match next {
+            Some(next) => self[next].previous_sibling = previous,
+            None => self[parent].last_child = previous,
+        }
+    }
+
+    /// copies `id` into a new orphan node, with its subtree if `deep`, in document order.
+    /// only the node itself is copied (its tag, attributes or text): the copy still has to be styled
+    pub fn clone_node(&mut self, id: NodeId, deep: bool) -> NodeId {
+        let clone = self.create_node(Node::new(self[id].node_type.clone()));
+        if !deep {
+            return clone;
+        }
+
+        // the nodes left to copy, with the copy they go into. the last child is pushed first,
+        // so that the first one is copied next
+        let mut stack = vec![];
+        let push_children = |dom: &Dom, stack: &mut Vec<_>, original: NodeId, copy: NodeId| {
+            let mut child = dom[original].last_child;
+            while let Some(id) = child {
+                stack.push((id, copy));
+                child = dom[id].previous_sibling;
+            }
+        };
+        push_children(self, &mut stack, id, clone);
+        while let Some((original, parent)) = stack.pop() {
+            let copy = self.create_node(Node::new(self[original].node_type.clone()));
+            self.link(parent, copy, None);
+            push_children(self, &mut stack, original, copy);
+        }
+        clone
+    }
+
+    /// the position of `id` in its parent's children, if it has a parent
+    pub fn index_in_parent(&self, id: NodeId) -> Option<usize> {
+        let parent = self[id].parent?;
+        self.children(parent).position(|child| child == id)
+    }
+
+    /// whether `ancestor` is `id` or one of its ancestors
+    pub fn is_inclusive_ancestor(&self, ancestor: NodeId, id: NodeId) -> bool {
+        let mut node = Some(id);
+        while let Some(current) = node {
+            if current == ancestor {
+                return true;
+            }
+            node = self[current].parent;
+        }
+        false
+    }
+
+    /// the children of `id`, in order
+    pub fn children(&self, id: NodeId) -> Children<'_> {
+        Children {
+            dom: self,
+            next: self[id].first_child,
+        }
+    }
+}
+
+/// The children of a node, following the `next_sibling` links, see `Dom::children`
+#[derive(Debug, Clone)]
+pub struct Children<'a> {
+    dom: &'a Dom,
+    next: Option<NodeId>,
+}
+
+impl Iterator for Children<'_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.next?;
+        self.next = self.dom[id].next_sibling;
+        Some(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::dom::{Append as _, ElementData, NodeType};
+
+    #[test]
+    fn links() {
+        let mut dom = Dom::new("html");
+        let body = dom.node_mut(dom.root).append_element("body", None);
+        let h1 = dom.node_mut(body).append_element("h1", None);
+        let text = dom.node_mut(body).append_text("some text");
+        let p = dom.node_mut(body).append_element("p", None);
+
+        assert_eq!(dom.children(dom.root).collect::<Vec<_>>(), [body]);
+        assert_eq!(dom.children(body).collect::<Vec<_>>(), [h1, text, p]);
+        assert_eq!(dom[body].first_child(), Some(h1));
+        assert_eq!(dom[body].last_child(), Some(p));
+        assert_eq!(dom[text].previous_sibling(), Some(h1));
+        assert_eq!(dom[text].next_sibling(), Some(p));
+        assert_eq!(dom[h1].previous_sibling(), None);
+        assert_eq!(dom[p].next_sibling(), None);
+        assert_eq!(dom[p].parent(), Some(body));
+        assert!(matches!(&dom[text].node_type, NodeType::Text(t) if t == "some text"));
+    }
+
+    /// a xorshift, since the same sequences have to be run every time
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+    }
+
+    /// checks that the links agree with each other, and with the children in `model`
+    fn check_links(dom: &Dom, model: &[Vec<NodeId>]) {
+        assert_eq!(dom[dom.root].parent(), None);
+        for (index, expected) in model.iter().enumerate() {
+            let id = NodeId(index);
+            let children = dom.children(id).take(model.len() + 1).collect::<Vec<_>>();
+            assert_eq!(&children, expected, "children of {id:?}");
+            assert_eq!(dom[id].first_child(), children.first().copied());
+            assert_eq!(dom[id].last_child(), children.last().copied());
+            for (i, &child) in children.iter().enumerate() {
+                assert_eq!(dom[child].parent(), Some(id));
+                assert_eq!(dom.index_in_parent(child), Some(i));
+                let previous = i.checked_sub(1).map(|i| children[i]);
+                assert_eq!(dom[child].previous_sibling(), previous);
+            }
+        }
+        for index in 0..model.len() {
+            let id = NodeId(index);
+            if dom[id].parent().is_none() {
+                assert_eq!(dom[id].previous_sibling(), None);
+                assert_eq!(dom[id].next_sibling(), None);
+                assert_eq!(dom.index_in_parent(id), None);
+            }
+        }
+    }
+
+    #[test]
+    fn random_mutations() {
+        use crate::engine::dom::Mutate as _;
+
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        let mut dom = Dom::new("html");
+        let mut model = vec![vec![]];
+
+        for _ in 0..2000 {
+            let count = model.len();
+            let (a, b) = (NodeId(rng.below(count)), NodeId(rng.below(count)));
+            match rng.below(6) {
+                // a new node, or an orphan, goes before some child of `a` or at its end
+                0 | 1 => {
+                    let child = if rng.below(2) == 0 || dom[b].parent().is_some() {
+                        model.push(vec![]);
+                        dom.create_node(Node::new(NodeType::Text("new".into())))
+                    } else {
+                        b
+                    };
+                    let siblings = &mut model[a.0];
+                    let at = rng.below(siblings.len() + 1);
+                    let reference = siblings.get(at).copied();
+                    let inserted = dom.node_mut(a).insert_before(child, reference);
+                    if child == dom.root || dom.is_inclusive_ancestor(child, a) {
+                        assert!(matches!(inserted, Err(Error::HierarchyRequest)));
+                    } else {
+                        assert_eq!(inserted.unwrap(), child);
+                        siblings.insert(at, child);
+                    }
+                }
+                2 => {
+                    let Some(parent) = dom[a].parent() else {
+                        continue;
+                    };
+                    model[parent.0].retain(|&child| child != a);
+                    assert_eq!(dom.node_mut(parent).remove_child(a).unwrap(), a);
+                }
+                3 => {
+                    let Some(parent) = dom[a].parent() else {
+                        continue;
+                    };
+                    if b == dom.root
+                        || dom[b].parent().is_some()
+                        || dom.is_inclusive_ancestor(b, parent)
+                    {
+                        // and nothing changes
+                        assert!(dom.node_mut(parent).replace_child(b, a).is_err());
+                        check_links(&dom, &model);
+                        continue;
+                    }
+                    for child in &mut model[parent.0] {
+                        if *child == a {
+                            *child = b;
+                        }
+                    }
+                    assert_eq!(dom.node_mut(parent).replace_child(b, a).unwrap(), a);
+                }
+                4 => {
+                    if let Some(parent) = dom[a].parent() {
+                        model[parent.0].retain(|&child| child != a);
+                    }
+                    dom.node_mut(a).detach();
+                }
+                _ => {
+                    let before = dom.nodes.len();
+                    let clone = dom.clone_node(a, rng.below(2) == 0);
+                    // the copies are all new, in the same order
+                    let copied = (before..dom.nodes.len()).map(NodeId);
+                    let mut copies = copied.clone().collect::<Vec<_>>();
+                    assert_eq!(copies.remove(0), clone);
+                    model.extend(copied.map(|copy| dom.children(copy).collect()));
+                    assert_eq!(dom.nodes.len(), model.len());
+                }
+            }
+            check_links(&dom, &model);
+        }
+    }
+
+    #[test]
+    fn mutation_errors() {
+        use crate::engine::dom::Mutate as _;
+
+        let mut dom = Dom::new("html");
+        let body = dom.node_mut(dom.root).append_element("body", None);
+        let p = dom.node_mut(body).append_element("p", None);
+        let orphan = dom.create_node(Node::new(NodeType::Text("orphan".into())));
+
+        let result = dom.node_mut(dom.root).remove_child(p);
+        assert!(matches!(result, Err(Error::NotAChild)));
+        let result = dom.node_mut(body).insert_before(orphan, Some(body));
+        assert!(matches!(result, Err(Error::NotAChild)));
+        let result = dom.node_mut(dom.root).append_child(p);
+        assert!(matches!(result, Err(Error::NotOrphan)));
+        let root = dom.root;
+        let result = dom.node_mut(p).append_child(root);
+        assert!(matches!(result, Err(Error::HierarchyRequest)));
+        let result = dom.node_mut(dom.root).replace_child(body, body);
+        assert!(matches!(result, Err(Error::NotOrphan)));
+
+        assert_eq!(dom.children(body).collect::<Vec<_>>(), [p]);
+        assert_eq!(dom[orphan].parent(), None);
+    }
+
+    #[test]
+    fn deep_clone() {
+        let mut dom = Dom::new("html");
+        let body = dom.node_mut(dom.root).append_element("body", None);
+        let p = dom.node_mut(body).append_element("p", None);
+        dom.node_mut(p).append_text("some text");
+
+        let shallow = dom.clone_node(body, false);
+        assert_eq!(dom[shallow].first_child(), None);
+
+        let deep = dom.clone_node(body, true);
+        assert_eq!(dom[deep].parent(), None);
+        let copy = dom.children(deep).collect::<Vec<_>>();
+        assert_eq!(copy.len(), 1);
+        assert_ne!(copy[0], p);
+        assert_eq!(dom[copy[0]].to_string(), "p");
+        let text = dom[copy[0]].first_child().unwrap();
+        assert!(matches!(&dom[text].node_type, NodeType::Text(t) if t == "some text"));
+
+        // deep enough that copying it recursively would overflow the stack
+        let mut node = p;
+        for _ in 0..100_000 {
+            let child =
+                dom.create_node(Node::new(NodeType::Element(ElementData::new("div", None))));
+            dom.link(node, child, None);
+            node = child;
+        }
+        let deep = dom.clone_node(body, true);
+        assert_eq!(dom.pre_order(deep).count(), 100_003);
+    }
+
+    #[test]
+    fn dom_is_send() {
+        fn assert_send<T: Send + Sync>() {}
+        assert_send::<Dom>();
+    }
+}
@@ -1,46 +1,79 @@
-use std::{collections::HashMap, rc::Rc, str::FromStr as _};
+use std::{collections::HashMap, str::FromStr as _, sync::Arc};
 
 use pest::Parser;
 
 use crate::engine::{
+    bloom::AncestorBloom,
+    cascade::Cascade,
+    computed_style::{ComputedStyle, Content, Display},
     css,
-    stylesheet::{self, ComplexSelector, Specificity, Stylesheet},
+    generated_content::{ContentContext, GeneratedBox, ListHints},
+    media::Device,
+    rule_hash::RuleHash,
+    stylesheet::{self, ComplexSelector, FontMetrics, PseudoElement, Specificity, Stylesheet},
 };
 
-use super::{
-    iterator::NodeIterator, AppliedStyle, ElementData, GetSetAttr, Node, NodeType, Select as _,
-    SharedNode,
-};
+use super::{AppliedStyle, ElementData, Node, NodeId, NodeType, Select as _};
+
+/// What a pass of `compute_style_recursive` needs besides the nodes
+struct Restyle<'a> {
+    viewport: (u16, u16),
+    content: ContentContext,
+    /// recompute every node
+    force: bool,
+    /// the nodes to recompute even if they and their parent didn't change
+    also: &'a dyn Fn(&Node) -> bool,
+}
+
+/// The styles of the rules that a node matches, see `Dom::match_rules`
+struct Matched {
+    node: NodeId,
+    applied_styles: Vec<AppliedStyle>,
+    pseudo_applied_styles: HashMap<PseudoElement, Vec<AppliedStyle>>,
+}
 
 #[derive(Debug)]
 pub struct Dom {
     pub doctype: String,
-    pub root: SharedNode,
+    /// every node ever created in this dom, see `NodeId`.
+    /// nodes are never freed, not even once they're detached or removed:
+    /// an orphan can still be inserted again, and its id has to stay valid for that
+    pub(super) nodes: Vec<Node>,
+    pub root: NodeId,
     pub stylesheets: Vec<Stylesheet>,
+    /// size of the terminal in cells, used to resolve `vw`/`vh` and percentages
+    pub viewport: (u16, u16),
+    /// what `@media` queries are evaluated against, along with `viewport`
+    pub device: Device,
 }
 
 impl Dom {
     pub fn new(doctype: &str) -> Self {
+        let root = Node::new(NodeType::Element(ElementData {
+            tag: "root".into(),
+            attrs: HashMap::new(),
+        }));
         Dom {
             doctype: String::from(doctype),
-            root: Node::new(NodeType::Element(ElementData {
-                tag: "root".into(),
-                attrs: HashMap::new(),
-            }))
-            .into_shared(),
+            nodes: vec![root],
+            root: NodeId::ROOT,
             stylesheets: Vec::new(),
+            viewport: (80, 24),
+            device: Device::default(),
         }
     }
 
-    pub fn query_select(&self, query: &str) -> super::super::Result<Vec<SharedNode>> {
-        Ok(self.root.select(&ComplexSelector::from_str(query)?)?)
+    pub fn query_select(&self, query: &str) -> super::super::Result<Vec<NodeId>> {
+        Ok(self
+            .node(self.root)
+            .select(&ComplexSelector::from_str(query)?)?)
     }
 
     pub fn select(
         &self,
         selector: &stylesheet::ComplexSelector,
-    ) -> super::super::Result<Vec<SharedNode>> {
-        Ok(self.root.select(selector)?)
+    ) -> super::super::Result<Vec<NodeId>> {
+        Ok(self.node(self.root).select(selector)?)
     }
 
     pub fn apply_stylesheet(&mut self, stylesheet: Stylesheet) -> super::super::Result<()> {
@@ -48,56 +81,419 @@ impl Dom {
         self.refresh_styles()
     }
 
+    /// recomputes the `applied_styles` of every element.
+    ///
+    /// rules are looked up through a [`RuleHash`] for every element,
+    /// instead of running every selector over the whole tree.
     pub fn refresh_styles(&mut self) -> super::super::Result<()> {
-        // reset styles on every Element,
-        for node in NodeIterator::try_from(&self.root)? {
-            let node = node?;
-            let mut w = node.write().unwrap();
-            w.applied_styles.clear();
+        let root = self.root;
+        self[root].dirty.style = true;
+        self.update_styles()
+    }
+
+    /// restyles what changed since the last time, see `MarkDirty`.
+    ///
+    /// rules are only matched again for the style-dirty nodes, their descendants and their following siblings,
+    /// which selectors can depend on. then only the nodes that were matched again, or whose parent's style changed,
+    /// go through the cascade. the ones whose computed style or generated boxes changed are marked layout-dirty,
+    /// for `LayoutManager::update`
+    pub fn update_styles(&mut self) -> super::super::Result<()> {
+        let inline_source_order = self.inline_source_order();
+        let rule_hash = RuleHash::new(&self.stylesheets, &self.device, self.viewport);
+        let matched = self.match_rules(&rule_hash)?;
+        drop(rule_hash);
+
+        // the nodes that are matched again are left style-dirty, for `compute_style_recursive`
+        for matched in matched {
+            let w = &mut self[matched.node];
+            w.applied_styles = matched.applied_styles;
+            w.pseudo_applied_styles = matched.pseudo_applied_styles;
+            w.dirty.style = true;
+            self.apply_inline_style(matched.node, inline_source_order)?;
+        }
+
+        self.restyle(false, &|_| false)
+    }
+
+    /// changes the viewport (eg. when the terminal is resized) and recomputes the styles that depend on it.
+    /// the whole cascade only runs again if some `@media` rule started or stopped applying
+    pub fn set_viewport(&mut self, viewport: (u16, u16)) -> super::super::Result<()> {
+        let old_viewport = self.viewport;
+        self.viewport = viewport;
+        if self.media_changed(&self.device, old_viewport) {
+            self.refresh_styles()
+        } else {
+            // the descendants of those are recomputed if their style changes
+            self.restyle(false, &|node| {
+                let pseudo_styles = node.pseudo_applied_styles.values().flatten();
+                node.applied_styles
+                    .iter()
+                    .chain(pseudo_styles)
+                    .any(|style| style.value.value.is_viewport_relative())
+            })
+        }
+    }
+
+    pub fn set_device(&mut self, device: Device) -> super::super::Result<()> {
+        let old_device = std::mem::replace(&mut self.device, device);
+        if self.media_changed(&old_device, self.viewport) {
+            self.refresh_styles()?;
         }
+        Ok(())
+    }
+
+    /// whether any rule's `@media` conditions evaluate differently than they did
+    /// with the `old_device` and `old_viewport`
+    fn media_changed(&self, old_device: &Device, old_viewport: (u16, u16)) -> bool {
+        self.stylesheets
+            .iter()
+            .flat_map(|sheet| &sheet.rules)
+            .filter(|rule| !rule.media.is_empty())
+            .any(|rule| {
+                rule.media_matches(old_device, old_viewport)
+                    != rule.media_matches(&self.device, self.viewport)
+            })
+    }
 
-        // apply styles
-        for sheet in &self.stylesheets {
-            for rule in &sheet.rules {
-                let nodes = self.select(&rule.selector)?;
-
-                for node in &nodes {
-                    let mut w = node.write().unwrap();
-                    for (k, v) in &rule.props {
-                        w.applied_styles.push(AppliedStyle {
-                            key: k.clone(),
-                            value: v.clone(),
-                            origin: sheet.origin,
-                            rule_specificity: rule.selector.specificity(),
-                        });
+    /// runs the cascade on every node, filling in their `computed_style`
+    /// from the `applied_styles` left by `refresh_styles`,
+    /// along with their `::before` and `::after` boxes
+    pub fn compute_styles(&mut self) -> super::super::Result<()> {
+        self.restyle(true, &|_| false)
+    }
+
+    /// runs `compute_style_recursive` over the whole tree.
+    /// every node is recomputed with `force`, otherwise only the dirty ones and the ones `also` picks
+    fn restyle(&mut self, force: bool, also: &dyn Fn(&Node) -> bool) -> super::super::Result<()> {
+        let mut pass = Restyle {
+            viewport: self.viewport,
+            content: ContentContext::default(),
+            force,
+            also,
+        };
+        let root_font_size = FontMetrics::DEFAULT_FONT_SIZE as f32;
+        self.compute_style_recursive(self.root, None, 0, root_font_size, false, &mut pass);
+        Ok(())
+    }
+
+    /// computes the style of `node` if it has to be, with `recompute` when its parent's style changed,
+    /// then goes on with its children. counters and generated boxes are always updated,
+    /// since they depend on everything before them in document order.
+    ///
+    /// leaves the nodes that changed layout-dirty, and returns whether there's one in the subtree
+    fn compute_style_recursive(
+        &mut self,
+        node: NodeId,
+        parent_style: Option<&ComputedStyle>,
+        depth: usize,
+        root_font_size: f32,
+        recompute: bool,
+        pass: &mut Restyle,
+    ) -> bool {
+        let (style, changed, recompute, hints) = {
+            let r = &self[node];
+            let old_style = r.computed_style.as_ref();
+            let recompute = recompute || pass.force || r.dirty.style || (pass.also)(r);
+            let (style, hints) = match &r.node_type {
+                NodeType::Element(element) => {
+                    let items = self
+                        .children(node)
+                        .filter(|&child| {
+                            matches!(&self[child].node_type, NodeType::Element(e) if e.tag == "li")
+                        })
+                        .count();
+                    let hints = ListHints::new(element, items);
+                    if let (Some(style), false) = (old_style, recompute) {
+                        (Arc::clone(style), hints)
+                    } else {
+                        let mut style = Cascade::new(
+                            &r.applied_styles,
+                            parent_style,
+                            pass.viewport,
+                            root_font_size,
+                        )
+                        .compute();
+                        // there's no user agent stylesheet to do it
+                        let declares_display = r.applied_styles.iter().any(|s| *s.key == "display");
+                        if element.is_hidden() {
+                            style.display = Display::None;
+                        } else if let (Some(display), false) =
+                            (element.table_display(), declares_display)
+                        {
+                            style.display = display;
+                        }
+                        (Arc::new(style), hints)
                     }
                 }
-            }
+                NodeType::Text(_) | NodeType::Comment(_) => match (old_style, recompute) {
+                    (Some(style), false) => (Arc::clone(style), ListHints::default()),
+                    _ => (
+                        Arc::new(ComputedStyle::anonymous(
+                            parent_style.unwrap_or(&ComputedStyle::default()),
+                        )),
+                        ListHints::default(),
+                    ),
+                },
+            };
+            // an equal style is kept, so that it's still shared with the boxes built from it
+            let changed = old_style
+                .is_none_or(|old_style| !Arc::ptr_eq(old_style, &style) && **old_style != *style);
+            let style = match (old_style, changed) {
+                (Some(old_style), false) => Arc::clone(old_style),
+                _ => style,
+            };
+            (style, changed, recompute, hints)
+        };
+
+        if changed {
+            let w = &mut self[node];
+            w.computed_style = Some(Arc::clone(&style));
+            w.dirty.layout = true;
+        }
+        if style.display != Display::None {
+            pass.content.update_counters(&style, hints, depth);
+        }
+
+        // the boxes of a node that isn't recomputed keep their styles, only their `content` is resolved again
+        let old_generated = std::mem::take(&mut self[node].generated);
+        let old = |pseudo| {
+            old_generated
+                .iter()
+                .find(|generated| !recompute && generated.pseudo_element == pseudo)
+        };
+        let mut generated = vec![];
+        if style.display == Display::ListItem {
+            generated.extend(self.generate_box(
+                node,
+                &style,
+                PseudoElement::Marker,
+                old(PseudoElement::Marker),
+                depth,
+                root_font_size,
+                pass,
+            ));
+        }
+        generated.extend(self.generate_box(
+            node,
+            &style,
+            PseudoElement::Before,
+            old(PseudoElement::Before),
+            depth,
+            root_font_size,
+            pass,
+        ));
+        // `rem` is relative to the document element, the only child of the root
+        let child_root_font_size = match depth {
+            1 => style.font_size,
+            _ => root_font_size,
+        };
+        let mut descendants = false;
+        let mut child = self[node].first_child;
+        while let Some(id) = child {
+            descendants |= self.compute_style_recursive(
+                id,
+                Some(&style),
+                depth + 1,
+                child_root_font_size,
+                changed,
+                pass,
+            );
+            child = self[id].next_sibling;
+        }
+        generated.extend(self.generate_box(
+            node,
+            &style,
+            PseudoElement::After,
+            old(PseudoElement::After),
+            depth,
+            root_font_size,
+            pass,
+        ));
+        pass.content.counters.leave(depth + 1);
+
+        let w = &mut self[node];
+        if old_generated != generated {
+            w.generated = generated;
+            w.dirty.layout = true;
+        } else {
+            w.generated = old_generated;
         }
+        w.dirty.style = false;
+        w.dirty.descendants = descendants;
+        w.dirty.layout || descendants
+    }
 
-        // add styles from the `style` attribute
-        for node in NodeIterator::try_from(&self.root)? {
-            let node = node?;
-            if let Some(raw_style) = node.get_attr("style")? {
-                let Some(css) = css::CssParser::parse(css::Rule::declaration_list, &raw_style)
-                    .map_err(Box::new)?
-                    .next()
-                else {
-                    continue;
+    /// computes the style of the `pseudo` element of `node` and resolves its `content`.
+    /// returns `None` if it doesn't generate a box
+    #[allow(clippy::too_many_arguments)]
+    fn generate_box(
+        &self,
+        node: NodeId,
+        style: &ComputedStyle,
+        pseudo: PseudoElement,
+        old: Option<&GeneratedBox>,
+        depth: usize,
+        root_font_size: f32,
+        pass: &mut Restyle,
+    ) -> Option<GeneratedBox> {
+        let r = &self[node];
+        let pseudo_style = match old {
+            Some(old) => Arc::clone(&old.style),
+            None => {
+                // list items always have a marker, the other boxes need some `content`
+                let styles = match (r.pseudo_applied_styles.get(&pseudo), pseudo) {
+                    (Some(styles), _) => &styles[..],
+                    (None, PseudoElement::Marker) => &[],
+                    (None, _) => return None,
                 };
-                let prop_map = css::parse_declarations(css);
-                let mut w = node.write().unwrap();
-                for (k, v) in prop_map {
-                    w.applied_styles.push(AppliedStyle {
-                        key: Rc::clone(&k),
-                        value: Rc::clone(&v),
-                        origin: stylesheet::Origin::Author,
-                        rule_specificity: Specificity(1, 0, 0, 0),
-                    });
+                let pseudo_style = Cascade::new(styles, Some(style), pass.viewport, root_font_size);
+                Arc::new(pseudo_style.compute())
+            }
+        };
+        let generates = match pseudo_style.content {
+            Content::Items(_) => true,
+            Content::Normal => pseudo == PseudoElement::Marker,
+            Content::None => false,
+        };
+        if style.display == Display::None || pseudo_style.display == Display::None || !generates {
+            return None;
+        }
+
+        // the box is like a first or last child
+        pass.content
+            .update_counters(&pseudo_style, ListHints::default(), depth + 1);
+        let NodeType::Element(element) = &r.node_type else {
+            return None;
+        };
+        let text = match pseudo_style.content {
+            Content::Normal => pass.content.marker(&pseudo_style),
+            _ => pass.content.resolve(&pseudo_style, element),
+        };
+        text.map(|text| GeneratedBox {
+            pseudo_element: pseudo,
+            style: pseudo_style,
+            text,
+        })
+    }
+
+    /// matches the rules again for the nodes that have to be, in document order.
+    /// a style-dirty node is matched again with its subtree, and so are its following siblings,
+    /// which `+` and `~` can match depending on it. the subtrees with nothing to match are skipped
+    fn match_rules(&self, rule_hash: &RuleHash) -> super::super::Result<Vec<Matched>> {
+        let mut matched = vec![];
+        let mut bloom = AncestorBloom::new();
+        // the ancestors of the current node, each with whether its children are matched again from there on.
+        // the elements among them are in the bloom filter, except for the root
+        let mut ancestors = vec![(self.root, self[self.root].dirty.style)];
+        let mut nodes = self.pre_order(self.root);
+        // the root itself is never matched
+        nodes.next();
+
+        while let Some(node) = nodes.next() {
+            let r = &self[node];
+            while ancestors
+                .last()
+                .is_some_and(|&(id, _)| Some(id) != r.parent)
+            {
+                let (id, _) = ancestors.pop().expect("checked above");
+                if let NodeType::Element(element) = &self[id].node_type {
+                    bloom.pop_element(element);
                 }
+            }
+            let (_, children_rematch) = ancestors
+                .last_mut()
+                .expect("the root is an ancestor of every node");
+            *children_rematch |= r.dirty.style;
+            let rematch = *children_rematch;
+
+            let NodeType::Element(element) = &r.node_type else {
+                nodes.skip_children();
+                continue;
+            };
+            if !rematch && !r.dirty.descendants {
+                nodes.skip_children();
+                continue;
+            }
+            if rematch {
+                matched.push(self.match_node(node, element, rule_hash, &bloom)?);
+            }
+            bloom.push_element(element);
+            ancestors.push((node, rematch));
+        }
+
+        Ok(matched)
+    }
+
+    /// the styles of the rules that `node` matches, with the `bloom` filter of its ancestors
+    fn match_node(
+        &self,
+        node: NodeId,
+        element: &ElementData,
+        rule_hash: &RuleHash,
+        bloom: &AncestorBloom,
+    ) -> super::super::Result<Matched> {
+        let mut applied_styles = vec![];
+        let mut pseudo_applied_styles: HashMap<_, Vec<_>> = HashMap::new();
+        for indexed in rule_hash.candidates(element, bloom) {
+            if !self.node(node).matches(&indexed.rule.selector)? {
+                continue;
+            }
+
+            let styles = match indexed.rule.selector.pseudo_element {
+                Some(pseudo) => pseudo_applied_styles.entry(pseudo).or_default(),
+                None => &mut applied_styles,
             };
+            for (k, v) in &indexed.rule.props {
+                styles.push(AppliedStyle {
+                    key: k.clone(),
+                    value: v.clone(),
+                    origin: indexed.origin,
+                    rule_specificity: indexed.rule.selector.specificity(),
+                    source_order: indexed.source_order,
+                    layer_order: indexed.layer_order,
+                });
+            }
         }
 
+        Ok(Matched {
+            node,
+            applied_styles,
+            pseudo_applied_styles,
+        })
+    }
+
+    /// inline styles come after every rule
+    fn inline_source_order(&self) -> usize {
+        self.stylesheets.iter().map(|s| s.rules.len()).sum()
+    }
+
+    /// add styles from the `style` attribute of `node`
+    fn apply_inline_style(
+        &mut self,
+        node: NodeId,
+        source_order: usize,
+    ) -> super::super::Result<()> {
+        let Some(raw_style) = self.node(node).get_attr("style") else {
+            return Ok(());
+        };
+        let Some(css) = css::CssParser::parse(css::Rule::declaration_list, &raw_style)
+            .map_err(Box::new)?
+            .next()
+        else {
+            return Ok(());
+        };
+        // TODO: surface the warnings of inline styles somewhere
+        let prop_map = css::parse_declarations(css, &mut vec![]);
+        for (k, v) in prop_map {
+            self[node].applied_styles.push(AppliedStyle {
+                key: Arc::clone(&k),
+                value: Arc::clone(&v),
+                origin: stylesheet::Origin::Author,
+                rule_specificity: Specificity::INLINE,
+                source_order,
+                layer_order: stylesheet::STYLE_ATTRIBUTE_LAYER,
+            });
+        }
         Ok(())
     }
 }
@@ -1,11 +1,18 @@
+#[allow(clippy::module_inception)]
 pub mod dom;
 pub use dom::*;
 
+pub mod arena;
+pub use arena::*;
+
+pub mod traversal;
+pub use traversal::*;
+
 pub mod node;
 pub use node::*;
 
-pub mod shared_node;
-pub use shared_node::*;
+pub mod node_ref;
+pub use node_ref::*;
 
 pub mod applied_style;
 pub use applied_style::*;
@@ -1,14 +1,18 @@
 use std::{
     collections::{HashMap, HashSet},
     fmt::Display,
-    sync::{Arc, RwLock},
+    sync::Arc,
 };
 
-use crate::engine::stylesheet;
+use crate::engine::{
+    computed_style::{ComputedStyle, Display as CssDisplay},
+    generated_content::GeneratedBox,
+    stylesheet::{self, PseudoElement},
+};
 
-use super::{AppliedStyle, SharedNode, WeakSharedNode};
+use super::{AppliedStyle, NodeId};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum NodeType {
     Text(String),
     Comment(String),
@@ -18,10 +22,37 @@ pub enum NodeType {
 #[derive(Debug)]
 pub struct Node {
     pub node_type: NodeType,
-    pub parent: Option<WeakSharedNode>,
-    pub children: Vec<SharedNode>,
+    /// the links to the nodes around it in its `Dom`, which only the dom changes, see `Dom::insert_before` and `Dom::detach`
+    pub(super) parent: Option<NodeId>,
+    pub(super) first_child: Option<NodeId>,
+    pub(super) last_child: Option<NodeId>,
+    pub(super) previous_sibling: Option<NodeId>,
+    pub(super) next_sibling: Option<NodeId>,
 
     pub applied_styles: Vec<AppliedStyle>,
+    /// the styles of the rules with a pseudo-element, like `p::before`
+    pub pseudo_applied_styles: HashMap<PseudoElement, Vec<AppliedStyle>>,
+    /// filled in by `Dom::compute_styles`
+    pub computed_style: Option<Arc<ComputedStyle>>,
+    /// the `::marker`, `::before` and `::after` boxes, in that order. filled in by `Dom::compute_styles`
+    pub generated: Vec<GeneratedBox>,
+    /// how far the content of the node's box is scrolled, in cells, if it's a scroll container.
+    /// it's kept across layouts, and only clamped to what the box can scroll when used, see `scroll`
+    pub scroll: taffy::Point<f32>,
+    /// what changed since the styles and the layout were last updated
+    pub dirty: Dirty,
+}
+
+/// What has to be done again for a node, see `Dom::update_styles` and `LayoutManager::update`.
+/// set through `MarkDirty`, which also marks the ancestors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Dirty {
+    /// its rules have to be matched again, which restyles its subtree too
+    pub style: bool,
+    /// its boxes have to be built again
+    pub layout: bool,
+    /// some node below it is dirty
+    pub descendants: bool,
 }
 
 impl Node {
@@ -30,14 +61,50 @@ impl Node {
         Self {
             node_type,
             parent: None,
-            children: Vec::new(),
+            first_child: None,
+            last_child: None,
+            previous_sibling: None,
+            next_sibling: None,
             applied_styles: Vec::new(),
+            pseudo_applied_styles: HashMap::new(),
+            computed_style: None,
+            generated: Vec::new(),
+            scroll: taffy::Point::ZERO,
+            // a new node hasn't been styled nor laid out yet
+            dirty: Dirty {
+                style: true,
+                layout: true,
+                descendants: false,
+            },
         }
     }
 
-    /// consumes the node and moves it into a Arc<RwLock<Node>> (aka SharedNode)
-    pub fn into_shared(self) -> SharedNode {
-        Arc::new(RwLock::new(self))
+    /// where the styles of a rule go: `applied_styles`, or the styles of one of its pseudo-elements
+    pub fn applied_styles_mut(&mut self, pseudo: Option<PseudoElement>) -> &mut Vec<AppliedStyle> {
+        match pseudo {
+            Some(pseudo) => self.pseudo_applied_styles.entry(pseudo).or_default(),
+            None => &mut self.applied_styles,
+        }
+    }
+
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn first_child(&self) -> Option<NodeId> {
+        self.first_child
+    }
+
+    pub fn last_child(&self) -> Option<NodeId> {
+        self.last_child
+    }
+
+    pub fn previous_sibling(&self) -> Option<NodeId> {
+        self.previous_sibling
+    }
+
+    pub fn next_sibling(&self) -> Option<NodeId> {
+        self.next_sibling
     }
 }
 
@@ -63,7 +130,7 @@ impl Display for Node {
 
 pub type AttrMap = HashMap<String, String>;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ElementData {
     pub tag: String,
     pub attrs: AttrMap,
@@ -88,6 +155,33 @@ impl ElementData {
         }
     }
 
+    /// elements that browsers hide in their user agent stylesheet:
+    /// the ones with a `hidden` attribute, and metadata like `<head>` and `<script>`
+    pub fn is_hidden(&self) -> bool {
+        const NOT_RENDERED: [&str; 10] = [
+            "head", "title", "meta", "link", "base", "style", "script", "noscript", "template",
+            "datalist",
+        ];
+        self.attrs.contains_key("hidden") || NOT_RENDERED.contains(&self.tag.as_str())
+    }
+
+    /// the `display` of the table elements in the user agent stylesheet,
+    /// which they need to be laid out as a table
+    pub fn table_display(&self) -> Option<CssDisplay> {
+        Some(match self.tag.as_str() {
+            "table" => CssDisplay::Table,
+            "caption" => CssDisplay::TableCaption,
+            "colgroup" => CssDisplay::TableColumnGroup,
+            "col" => CssDisplay::TableColumn,
+            "thead" => CssDisplay::TableHeaderGroup,
+            "tbody" => CssDisplay::TableRowGroup,
+            "tfoot" => CssDisplay::TableFooterGroup,
+            "tr" => CssDisplay::TableRow,
+            "td" | "th" => CssDisplay::TableCell,
+            _ => return None,
+        })
+    }
+
     pub fn matches_selector(&self, selector: &stylesheet::Selector) -> bool {
         let id_ok = selector.id.is_none() || selector.id.as_ref() == self.id();
         let tag_ok = selector.tag_name.is_none() || selector.tag_name.as_ref() == Some(&self.tag);
@@ -0,0 +1,57 @@
+use crate::engine::dom::{AttrMap, ElementData, Node, NodeId, NodeType};
+
+use super::{MarkDirty as _, NodeMut, Result};
+
+pub trait Append {
+    fn append_node(&mut self, node: Node) -> NodeId;
+    fn append_child(&mut self, child: NodeId) -> Result<NodeId>;
+    fn append_element(&mut self, tag: &str, attrs: Option<AttrMap>) -> NodeId;
+    fn append_text(&mut self, text: &str) -> NodeId;
+    fn append_comment(&mut self, text: &str) -> NodeId;
+}
+
+impl Append for NodeMut<'_> {
+    /// moves a new node into the dom, as the last child of this node.
+    /// the new node has to be styled, and this one's boxes have to be built again, see `MarkDirty`
+    fn append_node(&mut self, node: Node) -> NodeId {
+        let child = self.dom.create_node(node);
+        // a new node can always be inserted
+        self.dom.link(self.id, child, None);
+        self.mark_inserted(child);
+        child
+    }
+
+    /// appends an orphan node of the dom, see `Dom::append_child`
+    fn append_child(&mut self, child: NodeId) -> Result<NodeId> {
+        self.dom.append_child(self.id, child)?;
+        self.mark_inserted(child);
+        Ok(child)
+    }
+
+    fn append_element(&mut self, tag: &str, attrs: Option<AttrMap>) -> NodeId {
+        let node = Node::new(NodeType::Element(ElementData {
+            tag: tag.into(),
+            attrs: attrs.unwrap_or_default(),
+        }));
+
+        self.append_node(node)
+    }
+
+    fn append_text(&mut self, text: &str) -> NodeId {
+        let node = Node::new(NodeType::Text(text.into()));
+        self.append_node(node)
+    }
+
+    fn append_comment(&mut self, text: &str) -> NodeId {
+        let node = Node::new(NodeType::Comment(text.into()));
+        self.append_node(node)
+    }
+}
+
+impl NodeMut<'_> {
+    /// `child` was just inserted into this node, so it has to be styled, and this node's boxes built again
+    pub(super) fn mark_inserted(&mut self, child: NodeId) {
+        self.mark_layout_dirty();
+        self.dom.node_mut(child).mark_style_dirty();
+    }
+}
@@ -1,15 +1,18 @@
-use crate::engine::{dom::AppliedStyle, stylesheet};
+use crate::engine::{cascade, dom::AppliedStyle, stylesheet};
 
-use super::{Error, Result, SharedNode};
+use super::{NodeRef, Parent as _};
 
+/// Asks the cascade for the declared value of a single property.
+///
+/// This walks the applied styles every time, so it's meant for debugging:
+/// layout and rendering should read the `ComputedStyle` instead.
 pub trait AskStyle {
-    fn ask_style(&self, key: &str) -> Result<Option<AppliedStyle>>;
+    fn ask_style(&self, key: &str) -> Option<AppliedStyle>;
 
     // convenient helper function for ask_style
     // if no value is found given
     fn get_style(&self, key: &str) -> stylesheet::Value {
-        let result = self.ask_style(key);
-        if let Ok(Some(applied_style)) = result {
+        if let Some(applied_style) = self.ask_style(key) {
             applied_style.value.value.clone()
         } else {
             stylesheet::Value::None
@@ -19,13 +22,11 @@ pub trait AskStyle {
     // convenient helper function for ask_style.
     // if the `key` is not found, try the `fallback_key`
     fn get_style_with_fallback(&self, key: &str, fallback_key: &str) -> stylesheet::Value {
-        let result = self.ask_style(key);
-        if let Ok(Some(applied_style)) = result {
+        if let Some(applied_style) = self.ask_style(key) {
             return applied_style.value.value.clone();
         }
 
-        let result = self.ask_style(fallback_key);
-        if let Ok(Some(applied_style)) = result {
+        if let Some(applied_style) = self.ask_style(fallback_key) {
             return applied_style.value.value.clone();
         }
 
@@ -41,13 +42,11 @@ pub trait AskStyle {
         fallback_key: &str,
         default_value: &stylesheet::Value,
     ) -> stylesheet::Value {
-        let result = self.ask_style(key);
-        if let Ok(Some(applied_style)) = result {
+        if let Some(applied_style) = self.ask_style(key) {
             return applied_style.value.value.clone();
         }
 
-        let result = self.ask_style(fallback_key);
-        if let Ok(Some(applied_style)) = result {
+        if let Some(applied_style) = self.ask_style(fallback_key) {
             return applied_style.value.value.clone();
         }
 
@@ -55,50 +54,28 @@ pub trait AskStyle {
     }
 }
 
-impl AskStyle for SharedNode {
-    fn ask_style(&self, key: &str) -> Result<Option<AppliedStyle>> {
-        let r = self.read()?;
-        let mut filtered: Vec<_> = r
-            .applied_styles
-            .iter()
-            .filter(|v| v.key.as_str() == key)
-            .collect();
-
-        if !filtered.is_empty() {
-            let max_origin = filtered
-                .iter()
-                .map(|s| s.origin.value(s.value.important))
-                .max();
-            if let Some(max) = max_origin {
-                filtered.retain(|s| s.origin.value(s.value.important) == max)
-            }
-
-            let max_specificity = filtered.iter().map(|s| s.rule_specificity).max();
-            if let Some(max) = max_specificity {
-                filtered.retain(|s| s.rule_specificity == max)
-            }
-
-            // Cloning AppliedStyle is cheap.. it only contains Rc, enum and Specificity
-            Ok(filtered.first().map(|s| (*s).clone()))
+impl AskStyle for NodeRef<'_> {
+    fn ask_style(&self, key: &str) -> Option<AppliedStyle> {
+        // Cloning AppliedStyle is cheap.. it only contains Arc, enum and Specificity
+        if let Some(style) = cascade::cascaded_value(&self.node().applied_styles, key) {
+            Some(style.clone())
         } else {
-            if !INHERITABLE_PROPERTIES.contains(&key) {
-                return Ok(None);
+            if !is_inherited(key) {
+                return None;
             }
 
-            let r = self.read()?;
-
-            // if r.Parent is None, this is the root node
-            let Some(ref parent) = r.parent else {
-                return Ok(None);
-            };
-
-            let parent = parent.upgrade().ok_or(Error::MissingParentUpgrade)?;
-
-            parent.ask_style(key)
+            // if there's no parent, this is the root node
+            self.parent()?.ask_style(key)
         }
     }
 }
 
+/// whether the property is inherited by default when it's not declared.
+/// custom properties (`--x`) always are
+pub fn is_inherited(key: &str) -> bool {
+    key.starts_with("--") || INHERITABLE_PROPERTIES.contains(&key)
+}
+
 static INHERITABLE_PROPERTIES: [&str; 41] = [
     "azimuth",
     "border-collapse",
@@ -0,0 +1,17 @@
+use std::sync::Arc;
+
+use crate::engine::computed_style::ComputedStyle;
+
+use super::NodeRef;
+
+pub trait Computed {
+    /// returns the style computed by the last `Dom::compute_styles`,
+    /// or the initial style if the node hasn't been styled yet
+    fn computed_style(&self) -> Arc<ComputedStyle>;
+}
+
+impl Computed for NodeRef<'_> {
+    fn computed_style(&self) -> Arc<ComputedStyle> {
+        self.node().computed_style.clone().unwrap_or_default()
+    }
+}
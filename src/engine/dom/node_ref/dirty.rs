@@ -0,0 +1,34 @@
+use super::NodeMut;
+
+pub trait MarkDirty {
+    /// its rules have to be matched again, eg. after one of its attributes changed
+    fn mark_style_dirty(&mut self);
+    /// its boxes have to be built again, eg. after its text changed
+    fn mark_layout_dirty(&mut self);
+}
+
+impl MarkDirty for NodeMut<'_> {
+    fn mark_style_dirty(&mut self) {
+        self.node().dirty.style = true;
+        self.mark_ancestors();
+    }
+
+    fn mark_layout_dirty(&mut self) {
+        self.node().dirty.layout = true;
+        self.mark_ancestors();
+    }
+}
+
+impl NodeMut<'_> {
+    /// sets `descendants` up to the root.
+    /// it doesn't stop at an ancestor that already has it: the layout clears it on the nodes it rebuilt,
+    /// even when there are dirty nodes left below them that it skipped, like in a `display: none` subtree
+    fn mark_ancestors(&mut self) {
+        let mut ancestor = self.node().parent;
+        while let Some(id) = ancestor {
+            let node = &mut self.dom[id];
+            node.dirty.descendants = true;
+            ancestor = node.parent;
+        }
+    }
+}
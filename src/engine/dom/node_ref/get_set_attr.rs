@@ -0,0 +1,42 @@
+use crate::engine::dom::NodeType;
+
+use super::{MarkDirty as _, NodeMut, NodeRef};
+
+pub trait GetSetAttr {
+    fn set_attr(&mut self, key: &str, value: &str);
+    fn get_attr(&self, key: &str) -> Option<String>;
+}
+
+impl GetSetAttr for NodeMut<'_> {
+    /// the node has to be restyled afterwards, since selectors can depend on any attribute,
+    /// and laid out again, since some attributes like `colspan` change its boxes directly
+    fn set_attr(&mut self, key: &str, value: &str) {
+        match &mut self.node().node_type {
+            NodeType::Element(element_data) => {
+                element_data
+                    .attrs
+                    .entry(String::from(key))
+                    .and_modify(|v| *v = String::from(value))
+                    .or_insert(String::from(value));
+            }
+            NodeType::Text(_) => unreachable!("text nodes cannot have attributes"),
+            NodeType::Comment(_) => unreachable!("comment nodes cannot have attributes"),
+        }
+        self.mark_style_dirty();
+        self.mark_layout_dirty();
+    }
+
+    fn get_attr(&self, key: &str) -> Option<String> {
+        self.dom.node(self.id).get_attr(key)
+    }
+}
+
+impl NodeRef<'_> {
+    pub fn get_attr(&self, key: &str) -> Option<String> {
+        match &self.node().node_type {
+            NodeType::Element(element_data) => element_data.attrs.get(key).cloned(),
+            NodeType::Text(_) => None,
+            NodeType::Comment(_) => None,
+        }
+    }
+}
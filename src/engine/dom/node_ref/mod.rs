@@ -0,0 +1,93 @@
+use super::{Dom, Node, NodeId};
+
+pub mod append;
+pub use append::*;
+
+pub mod get_set_attr;
+pub use get_set_attr::*;
+
+pub mod pretty_print_tree;
+pub use pretty_print_tree::*;
+
+pub mod select;
+pub use select::*;
+
+pub mod ask_style;
+pub use ask_style::*;
+
+pub mod parent;
+pub use parent::Parent;
+
+pub mod computed;
+pub use computed::Computed;
+
+pub mod dirty;
+pub use dirty::MarkDirty;
+
+pub mod mutate;
+pub use mutate::Mutate;
+
+use thiserror::Error;
+
+/// A node along with the dom it's in, which the read-only traits
+/// (`Select`, `AskStyle`, `Computed`, ...) are implemented for. see `Dom::node`
+#[derive(Debug, Clone, Copy)]
+pub struct NodeRef<'a> {
+    pub dom: &'a Dom,
+    pub id: NodeId,
+}
+
+impl<'a> NodeRef<'a> {
+    pub fn node(&self) -> &'a Node {
+        &self.dom[self.id]
+    }
+}
+
+/// A node along with the dom it's in, which the traits that change the tree
+/// (`Append`, `Mutate`, `GetSetAttr`, `MarkDirty`) are implemented for. see `Dom::node_mut`
+#[derive(Debug)]
+pub struct NodeMut<'a> {
+    pub dom: &'a mut Dom,
+    pub id: NodeId,
+}
+
+impl NodeMut<'_> {
+    pub fn node(&mut self) -> &mut Node {
+        &mut self.dom[self.id]
+    }
+}
+
+impl Dom {
+    pub fn node(&self, id: NodeId) -> NodeRef<'_> {
+        NodeRef { dom: self, id }
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> NodeMut<'_> {
+        NodeMut { dom: self, id }
+    }
+}
+
+#[derive(Error, Debug)]
+/// These errors should never happen, but you never know...
+pub enum UnreachableError {
+    #[error("selector's inner simple selector list is empty")]
+    SelectorHasNoSimpleSelectors,
+    #[error("selector has more combinators than inner selectors")]
+    SelectorHasMoreCombinatorsThanSelectors,
+}
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("unreachable error: {0} (congratulations on finding this. please file an issue at github.com/Tesohh/tb)")]
+    Unreachable(#[from] UnreachableError),
+    #[error("selector parsing error. TODO: show more information")]
+    SelectorParsing,
+    #[error("the node is not a child of this node")]
+    NotAChild,
+    #[error("a node can't be inserted into itself or one of its descendants, and the root can't be inserted anywhere")]
+    HierarchyRequest,
+    #[error("only an orphan node can be inserted, it has to be removed from its parent first")]
+    NotOrphan,
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
@@ -0,0 +1,89 @@
+use crate::engine::dom::NodeId;
+
+use super::{Append as _, Error, MarkDirty as _, NodeMut, Result};
+
+/// Changes the children of a node, marking what has to be styled and laid out again, see `MarkDirty`.
+/// removed nodes stay in the dom as orphans, so they can be inserted somewhere else,
+/// and they're never freed, see `Dom::nodes`.
+///
+/// the methods fail like the `Dom` methods they are built on, eg. `Dom::insert_before`,
+/// and leave the tree as it was when they do
+pub trait Mutate {
+    /// unlinks `child` and returns it
+    ///
+    /// # Errors
+    /// `NotAChild` if `child` isn't a child of this node
+    fn remove_child(&mut self, child: NodeId) -> Result<NodeId>;
+    /// links the orphan `child` right before `reference`, or at the end if there's no `reference`
+    fn insert_before(&mut self, child: NodeId, reference: Option<NodeId>) -> Result<NodeId>;
+    /// puts the orphan `new` where `old` was, and returns `old`
+    ///
+    /// # Errors
+    /// `NotAChild` if `old` isn't a child of this node, and the errors of `Dom::insert_before` for `new`
+    fn replace_child(&mut self, new: NodeId, old: NodeId) -> Result<NodeId>;
+    /// unlinks this node from its parent, if it has one
+    fn detach(&mut self);
+}
+
+impl Mutate for NodeMut<'_> {
+    fn remove_child(&mut self, child: NodeId) -> Result<NodeId> {
+        if self.dom[child].parent != Some(self.id) {
+            return Err(Error::NotAChild);
+        }
+        self.dom.node_mut(child).detach();
+        Ok(child)
+    }
+
+    fn insert_before(&mut self, child: NodeId, reference: Option<NodeId>) -> Result<NodeId> {
+        let Some(reference) = reference else {
+            return self.append_child(child);
+        };
+        self.dom.insert_before(self.id, child, Some(reference))?;
+        self.mark_inserted(child);
+        self.mark_siblings_style_dirty(Some(reference));
+        Ok(child)
+    }
+
+    fn replace_child(&mut self, new: NodeId, old: NodeId) -> Result<NodeId> {
+        if self.dom[old].parent != Some(self.id) {
+            return Err(Error::NotAChild);
+        }
+        // checked before anything changes, so that an error leaves the tree as it was
+        self.dom.check_insertable(self.id, new)?;
+        let next = self.dom[old].next_sibling;
+        self.remove_child(old)?;
+        self.insert_before(new, next)?;
+        Ok(old)
+    }
+
+    fn detach(&mut self) {
+        let Some(parent) = self.node().parent else {
+            return;
+        };
+        let next = self.node().next_sibling;
+        self.dom.detach(self.id);
+
+        let mut parent = self.dom.node_mut(parent);
+        parent.mark_layout_dirty();
+        parent.mark_siblings_style_dirty(next);
+        // its boxes were built for the parent it had
+        self.mark_layout_dirty();
+    }
+}
+
+impl NodeMut<'_> {
+    /// the sibling combinators (`+`, `~`) match differently for the children after one that changed,
+    /// so they're restyled starting from `first`
+    fn mark_siblings_style_dirty(&mut self, first: Option<NodeId>) {
+        let Some(first) = first else {
+            return;
+        };
+        // they have the same ancestors, which only have to be marked once
+        self.dom.node_mut(first).mark_style_dirty();
+        let mut sibling = self.dom[first].next_sibling;
+        while let Some(id) = sibling {
+            self.dom[id].dirty.style = true;
+            sibling = self.dom[id].next_sibling;
+        }
+    }
+}
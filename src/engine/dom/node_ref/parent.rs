@@ -0,0 +1,16 @@
+use super::NodeRef;
+
+pub trait Parent
+where
+    Self: Sized,
+{
+    /// `None` for the root, and for nodes that aren't in the tree
+    fn parent(&self) -> Option<Self>;
+}
+
+impl Parent for NodeRef<'_> {
+    fn parent(&self) -> Option<Self> {
+        let parent = self.node().parent?;
+        Some(self.dom.node(parent))
+    }
+}
@@ -0,0 +1,16 @@
+use super::NodeRef;
+
+pub trait PrettyPrintTree {
+    fn pretty_print_tree(&self, depth: usize);
+}
+
+impl PrettyPrintTree for NodeRef<'_> {
+    fn pretty_print_tree(&self, depth: usize) {
+        let indent = (0..depth).map(|_| "   ").collect::<String>();
+        println!("{}{}", indent, self.node());
+
+        for child in self.dom.children(self.id) {
+            self.dom.node(child).pretty_print_tree(depth + 1);
+        }
+    }
+}
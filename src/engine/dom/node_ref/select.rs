@@ -0,0 +1,262 @@
+use std::str::FromStr;
+
+use crate::engine::{
+    dom::{NodeId, NodeType},
+    stylesheet::{self, ComplexSelector},
+};
+
+use super::{Error, NodeRef, Result, UnreachableError};
+
+pub trait Select {
+    fn query_select(&self, query: &str) -> Result<Vec<NodeId>>;
+    /// the descendants of this node that match the selector, in document order
+    fn select(&self, selector: &stylesheet::ComplexSelector) -> Result<Vec<NodeId>>;
+
+    /// checks if this node matches the selector, going from right to left.
+    ///
+    /// like in `select`, a node without a parent (the document root) is never matched,
+    /// not even as an ancestor or sibling.
+    /// a selector with a pseudo-element matches its originating element (`p::before` matches the `p`).
+    fn matches(&self, selector: &stylesheet::ComplexSelector) -> Result<bool>;
+}
+
+impl Select for NodeRef<'_> {
+    fn query_select(&self, query: &str) -> Result<Vec<NodeId>> {
+        // FIX: this will lose information
+        self.select(&ComplexSelector::from_str(query).or(Err(Error::SelectorParsing))?)
+    }
+
+    fn select(&self, selector: &stylesheet::ComplexSelector) -> Result<Vec<NodeId>> {
+        let last = last_index(selector)?;
+        // the ancestors and siblings that the rest of the selector matches can be outside of this node
+        Ok(self
+            .dom
+            .pre_order(self.id)
+            .skip(1)
+            .filter(|&id| self.dom.node(id).matches_from(selector, last))
+            .collect())
+    }
+
+    fn matches(&self, selector: &stylesheet::ComplexSelector) -> Result<bool> {
+        Ok(self.matches_from(selector, last_index(selector)?))
+    }
+}
+
+/// the index of the rightmost simple selector, which is where matching starts
+fn last_index(selector: &stylesheet::ComplexSelector) -> Result<usize> {
+    if selector.inner.is_empty() {
+        return Err(UnreachableError::SelectorHasNoSimpleSelectors.into());
+    }
+    if selector.combinators.len() >= selector.inner.len() {
+        return Err(UnreachableError::SelectorHasMoreCombinatorsThanSelectors.into());
+    }
+    Ok(selector.inner.len() - 1)
+}
+
+impl NodeRef<'_> {
+    fn matches_simple(&self, simple: &stylesheet::Selector) -> bool {
+        match &self.node().node_type {
+            NodeType::Element(element) => element.matches_selector(simple),
+            _ => false,
+        }
+    }
+
+    /// matches `selector.inner[index]` against this node,
+    /// then the rest of the selector (to the left) against the relevant ancestors/siblings
+    fn matches_from(&self, selector: &stylesheet::ComplexSelector, index: usize) -> bool {
+        if !self.matches_simple(&selector.inner[index]) {
+            return false;
+        }
+        if index == 0 {
+            return true;
+        }
+
+        let rest = |id| self.dom.node(id).matches_from(selector, index - 1);
+        match selector.combinators[index - 1] {
+            stylesheet::Combinator::Child => self.matchable_ancestors().next().is_some_and(rest),
+            stylesheet::Combinator::Descendant => self.matchable_ancestors().any(rest),
+            stylesheet::Combinator::AdjacentSibling => {
+                self.previous_element_siblings().next().is_some_and(rest)
+            }
+            stylesheet::Combinator::GeneralSibling => self.previous_element_siblings().any(rest),
+        }
+    }
+
+    /// the ancestors from the parent on, without the document root (which has no parent itself)
+    fn matchable_ancestors(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.dom
+            .ancestors(self.id)
+            .take_while(|&id| self.dom[id].parent.is_some())
+    }
+
+    /// sibling combinators only count elements, so text and comment nodes are skipped entirely.
+    /// a node without a parent simply has no siblings.
+    fn previous_element_siblings(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.dom
+            .previous_siblings(self.id)
+            .filter(|&id| matches!(self.dom[id].node_type, NodeType::Element(_)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn test_selectors() {
+        let input = r#"
+        <!DOCTYPE html>
+
+        <head>
+            <title>Hello web</title>
+            <style>
+                .yellow {
+                    color: yellow;
+                }
+            </style>
+        </head>
+
+        <body>
+            <h1 id="title">Hello Web</h1>
+            <div class="lorem-blue">
+                <p>Lorem ipsum, dolor sit amet consectetur adipisicing elit. Error, consequuntur!</p>
+            </div>
+
+            <div id="second-paragraph">
+                <p>Lorem ipsum dolor sit amet consectetur adipisicing elit. Animi, sit.</p>
+            </div>
+
+            <p class="yellow">Lorem ipsum dolor sit amet consectetur adipisicing elit. Animi, sit.</p>
+        </body>
+        "#;
+        let dom = crate::engine::html::parse_from_str(input).unwrap();
+
+        let basic_class = dom.query_select(".yellow").unwrap().len();
+        assert_eq!(basic_class, 1);
+
+        let child_paragraphs = dom.query_select("body>p").unwrap().len();
+        assert_eq!(child_paragraphs, 1);
+
+        let div_child_paragraphs = dom.query_select("body>div>p").unwrap().len();
+        assert_eq!(div_child_paragraphs, 2);
+
+        let body_para_descendants = dom.query_select("body p").unwrap().len();
+        assert_eq!(
+            body_para_descendants,
+            child_paragraphs + div_child_paragraphs
+        );
+
+        assert_eq!(dom.query_select("h1 ~ div").unwrap().len(), 2);
+        assert_eq!(dom.query_select("h1 + div").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_sibling_last_child() {
+        let input = r#"
+        <body>
+            <div><h1>Title</h1></div>
+            <h1>Other title</h1>
+            <div class="after"></div>
+            <section><h1>Last</h1></section>
+        </body>
+        "#;
+        let dom = crate::engine::html::parse_from_str(input).unwrap();
+
+        // two of the three h1s are last children, which must not abort the query
+        assert_eq!(dom.query_select("h1 + div").unwrap().len(), 1);
+        assert_eq!(dom.query_select("h1 + .after").unwrap().len(), 1);
+        assert_eq!(dom.query_select("h1 ~ section").unwrap().len(), 1);
+        assert_eq!(dom.query_select("section + div").unwrap().len(), 0);
+        assert_eq!(dom.query_select("section ~ *").unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_sibling_skips_non_elements() {
+        use crate::engine::dom::{Append as _, Dom};
+
+        let mut dom = Dom::new("html");
+        let body = dom.node_mut(dom.root).append_element("body", None);
+        let mut body = dom.node_mut(body);
+        body.append_element("h1", None);
+        body.append_text("   ");
+        body.append_comment("a comment in between");
+        body.append_text("some text");
+        body.append_element("div", None);
+        body.append_element("p", None);
+        body.append_text("trailing text");
+
+        assert_eq!(dom.query_select("h1 + div").unwrap().len(), 1);
+        assert_eq!(dom.query_select("h1 + p").unwrap().len(), 0);
+        assert_eq!(dom.query_select("h1 ~ p").unwrap().len(), 1);
+        assert_eq!(dom.query_select("div + p").unwrap().len(), 1);
+        assert_eq!(dom.query_select("p + *").unwrap().len(), 0);
+        assert_eq!(dom.query_select("p ~ *").unwrap().len(), 0);
+
+        let p = dom.query_select("p").unwrap()[0];
+        let matches = |query: &str| {
+            use super::Select as _;
+            use std::str::FromStr as _;
+            let selector = crate::engine::stylesheet::ComplexSelector::from_str(query).unwrap();
+            dom.node(p).matches(&selector).unwrap()
+        };
+        assert!(matches("div + p"));
+        assert!(matches("h1 ~ p"));
+        assert!(!matches("h1 + p"));
+    }
+
+    #[test]
+    fn test_matches() {
+        use super::Select as _;
+        use crate::engine::stylesheet::ComplexSelector;
+        use std::str::FromStr as _;
+
+        let input = r#"
+        <body>
+            <h1 id="title">Hello Web</h1>
+            <div class="lorem-blue">
+                <p>Lorem ipsum</p>
+            </div>
+            <div id="second-paragraph">
+                <section><p class="yellow">Lorem ipsum</p></section>
+            </div>
+        </body>
+        "#;
+        let dom = crate::engine::html::parse_from_str(input).unwrap();
+        let yellow = dom.node(dom.query_select(".yellow").unwrap()[0]);
+
+        let matches = [
+            "p",
+            ".yellow",
+            "body p",
+            "body .yellow",
+            "div > section > p",
+            "#second-paragraph p",
+            "h1 ~ div p",
+            "h1 + div ~ div p.yellow",
+            "body section > .yellow",
+        ];
+        let not_matches = [
+            "div > p",
+            "h1 + div p",
+            ".lorem-blue p",
+            "root p",
+            "p p",
+            "section ~ p",
+        ];
+
+        for x in matches {
+            assert!(
+                yellow
+                    .matches(&ComplexSelector::from_str(x).unwrap())
+                    .unwrap(),
+                "{x}"
+            );
+        }
+        for x in not_matches {
+            assert!(
+                !yellow
+                    .matches(&ComplexSelector::from_str(x).unwrap())
+                    .unwrap(),
+                "{x}"
+            );
+        }
+    }
+}
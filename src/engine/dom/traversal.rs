@@ -0,0 +1,222 @@
+use std::iter;
+
+use super::{Dom, NodeId, NodeRef};
+
+impl Dom {
+    /// `id` and its descendants in document order: every node comes before its children
+    pub fn pre_order(&self, id: NodeId) -> PreOrder<'_> {
+        PreOrder {
+            dom: self,
+            root: id,
+            current: None,
+            next: Some(id),
+        }
+    }
+
+    /// the descendants of `id` and then `id`: every node comes after its children
+    pub fn post_order(&self, id: NodeId) -> PostOrder<'_> {
+        PostOrder {
+            dom: self,
+            root: id,
+            next: Some(self.first_leaf(id)),
+        }
+    }
+
+    /// the ancestors of `id`, from its parent up to the root of its tree
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        iter::successors(self[id].parent, |&id| self[id].parent)
+    }
+
+    /// the siblings before `id`, closest first
+    pub fn previous_siblings(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        iter::successors(self[id].previous_sibling, |&id| self[id].previous_sibling)
+    }
+
+    /// the siblings after `id`, closest first
+    pub fn next_siblings(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        iter::successors(self[id].next_sibling, |&id| self[id].next_sibling)
+    }
+
+    /// `id` and its descendants in document order, as long as `filter` lets them through, see `Filter`
+    pub fn walk<'a, F>(&'a self, id: NodeId, filter: F) -> TreeWalker<'a, F>
+    where
+        F: FnMut(NodeRef<'a>) -> Filter,
+    {
+        TreeWalker {
+            pre_order: self.pre_order(id),
+            filter,
+        }
+    }
+
+    /// the first node without children that `id` leads to through first children
+    fn first_leaf(&self, id: NodeId) -> NodeId {
+        let mut node = id;
+        while let Some(child) = self[node].first_child {
+            node = child;
+        }
+        node
+    }
+}
+
+/// A document-order traversal of a subtree, see `Dom::pre_order`
+#[derive(Debug, Clone)]
+pub struct PreOrder<'a> {
+    dom: &'a Dom,
+    root: NodeId,
+    /// the last node that was returned
+    current: Option<NodeId>,
+    next: Option<NodeId>,
+}
+
+impl PreOrder<'_> {
+    /// goes on after the descendants of the last node that was returned, without them
+    pub fn skip_children(&mut self) {
+        if let Some(current) = self.current {
+            self.next = self.after(current);
+        }
+    }
+
+    /// the node after the subtree of `id`, which is the next sibling of it or of its closest ancestor that has one
+    fn after(&self, id: NodeId) -> Option<NodeId> {
+        let mut node = id;
+        while node != self.root {
+            if let Some(sibling) = self.dom[node].next_sibling {
+                return Some(sibling);
+            }
+            node = self.dom[node].parent?;
+        }
+        None
+    }
+}
+
+impl Iterator for PreOrder<'_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.next?;
+        self.current = Some(id);
+        self.next = self.dom[id].first_child.or_else(|| self.after(id));
+        Some(id)
+    }
+}
+
+/// A traversal of a subtree that returns the children first, see `Dom::post_order`
+#[derive(Debug, Clone)]
+pub struct PostOrder<'a> {
+    dom: &'a Dom,
+    root: NodeId,
+    next: Option<NodeId>,
+}
+
+impl Iterator for PostOrder<'_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.next?;
+        self.next = match self.dom[id].next_sibling {
+            _ if id == self.root => None,
+            Some(sibling) => Some(self.dom.first_leaf(sibling)),
+            None => self.dom[id].parent,
+        };
+        Some(id)
+    }
+}
+
+/// What a `TreeWalker` does with a node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    /// returns it, and goes on with its children
+    Accept,
+    /// goes on with its children without returning it
+    Skip,
+    /// leaves out its whole subtree
+    Reject,
+}
+
+/// A document-order traversal of a subtree that leaves out what its filter doesn't accept, see `Dom::walk`
+#[derive(Debug, Clone)]
+pub struct TreeWalker<'a, F> {
+    pre_order: PreOrder<'a>,
+    filter: F,
+}
+
+impl<'a, F> Iterator for TreeWalker<'a, F>
+where
+    F: FnMut(NodeRef<'a>) -> Filter,
+{
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        loop {
+            let id = self.pre_order.next()?;
+            match (self.filter)(self.pre_order.dom.node(id)) {
+                Filter::Accept => return Some(id),
+                Filter::Skip => {}
+                Filter::Reject => self.pre_order.skip_children(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{dom::NodeType, html};
+
+    fn tags(dom: &Dom, nodes: impl Iterator<Item = NodeId>) -> Vec<String> {
+        nodes
+            .filter_map(|id| match &dom[id].node_type {
+                NodeType::Element(element) => Some(element.tag.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn orders() {
+        let dom = html::parse_from_str(
+            "<body><div><h1>a</h1><p>b</p></div><ul><li>c</li><li>d</li></ul></body>",
+        )
+        .unwrap();
+        let body = dom.query_select("body").unwrap()[0];
+        let li = dom.query_select("li").unwrap()[1];
+
+        let pre_order = tags(&dom, dom.pre_order(body));
+        assert_eq!(pre_order, ["body", "div", "h1", "p", "ul", "li", "li"]);
+        let post_order = tags(&dom, dom.post_order(body));
+        assert_eq!(post_order, ["h1", "p", "div", "li", "li", "ul", "body"]);
+        // a subtree ends at its root, even though there's more after it
+        let h1 = dom.query_select("h1").unwrap()[0];
+        assert_eq!(dom.pre_order(h1).count(), 2);
+        assert_eq!(dom.post_order(h1).count(), 2);
+
+        assert_eq!(tags(&dom, dom.ancestors(li)), ["ul", "body", "root"]);
+        let first = dom.previous_siblings(li).next().unwrap();
+        assert_eq!(dom.next_siblings(first).collect::<Vec<_>>(), [li]);
+        assert_eq!(dom.next_siblings(li).count(), 0);
+
+        let mut pre_order = dom.pre_order(body);
+        pre_order.next();
+        pre_order.next();
+        pre_order.skip_children();
+        assert_eq!(tags(&dom, pre_order), ["ul", "li", "li"]);
+    }
+
+    #[test]
+    fn tree_walker() {
+        let dom = html::parse_from_str(
+            "<body><div class='ad'><p>a</p></div><section><p>b</p></section><p>c</p></body>",
+        )
+        .unwrap();
+        let body = dom.query_select("body").unwrap()[0];
+        let walked = dom.walk(body, |node| match &node.node().node_type {
+            NodeType::Element(element) if element.classes().contains("ad") => Filter::Reject,
+            NodeType::Element(element) if element.tag == "p" => Filter::Accept,
+            _ => Filter::Skip,
+        });
+        let texts = walked
+            .map(|p| dom[dom[p].first_child().unwrap()].to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(texts, ["\"b\"", "\"c\""]);
+    }
+}
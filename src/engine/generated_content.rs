@@ -0,0 +1,264 @@
+use std::sync::Arc;
+
+use super::{
+    computed_style::{ComputedStyle, Content, ContentItem, Display},
+    counters::{CounterStyle, Counters},
+    dom::ElementData,
+    stylesheet::PseudoElement,
+};
+
+/// the counter that numbers list items
+pub const LIST_ITEM: &str = "list-item";
+
+/// A box created by CSS instead of the document, for `::marker`, `::before` and `::after`.
+/// it's laid out and drawn like a text run
+#[derive(Debug, Clone)]
+pub struct GeneratedBox {
+    pub pseudo_element: PseudoElement,
+    pub style: Arc<ComputedStyle>,
+    pub text: String,
+}
+
+impl PartialEq for GeneratedBox {
+    /// the styles of boxes that weren't restyled are shared, which saves comparing them
+    fn eq(&self, other: &Self) -> bool {
+        self.pseudo_element == other.pseudo_element
+            && self.text == other.text
+            && (Arc::ptr_eq(&self.style, &other.style) || self.style == other.style)
+    }
+}
+
+/// What `content` needs to be resolved, besides the element:
+/// counters and the quote nesting level, which both depend on everything before it in document order
+#[derive(Debug, Default)]
+pub struct ContentContext {
+    pub counters: Counters,
+    quote_depth: usize,
+}
+
+/// What the html attributes of lists and list items do to the `list-item` counter
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ListHints {
+    /// `ol`, `ul` and `menu` reset it, to `<ol start>` - 1 by default.
+    /// `<ol reversed>` counts down instead, from the number of items
+    pub reset: Option<(i32, bool)>,
+    /// `<li value>`
+    pub set: Option<i32>,
+}
+
+impl ListHints {
+    /// `items` is the number of `li` children, which reversed lists start from
+    pub fn new(element: &ElementData, items: usize) -> Self {
+        let integer = |name| {
+            let value = element.attrs.get(name)?;
+            value.trim().parse::<i32>().ok()
+        };
+        let reset = match element.tag.as_str() {
+            "ol" if element.attrs.contains_key("reversed") => {
+                let start = integer("start").unwrap_or(items as i32);
+                Some((start.saturating_add(1), true))
+            }
+            "ol" => Some((integer("start").unwrap_or(1).saturating_sub(1), false)),
+            "ul" | "menu" => Some((0, false)),
+            _ => None,
+        };
+        let set = match element.tag.as_str() {
+            "li" => integer("value"),
+            _ => None,
+        };
+        Self { reset, set }
+    }
+}
+
+impl ContentContext {
+    /// applies `counter-reset`, `counter-increment` and `counter-set` (in that order)
+    /// for a box at `depth`.
+    ///
+    /// the `list-item` counter is also updated like browsers do by default,
+    /// unless the styles mention it: `hints` resets or sets it,
+    /// and every `display: list-item` box increments it
+    pub fn update_counters(&mut self, style: &ComputedStyle, hints: ListHints, depth: usize) {
+        let mentions_list_item =
+            |counters: &[(String, i32)]| counters.iter().any(|(name, _)| name == LIST_ITEM);
+
+        match hints.reset {
+            Some((value, reversed)) if !mentions_list_item(&style.counter_reset) => {
+                self.counters.reset(LIST_ITEM, value, depth);
+                self.counters.set_reversed(LIST_ITEM, reversed);
+            }
+            _ => {}
+        }
+        for (name, value) in &style.counter_reset {
+            self.counters.reset(name, *value, depth);
+        }
+
+        if style.display == Display::ListItem && !mentions_list_item(&style.counter_increment) {
+            let by = if self.counters.is_reversed(LIST_ITEM) {
+                -1
+            } else {
+                1
+            };
+            self.counters.increment(LIST_ITEM, by, depth);
+        }
+        for (name, by) in &style.counter_increment {
+            self.counters.increment(name, *by, depth);
+        }
+
+        match hints.set {
+            Some(value) if !mentions_list_item(&style.counter_set) => {
+                self.counters.set(LIST_ITEM, value, depth);
+            }
+            _ => {}
+        }
+        for (name, value) in &style.counter_set {
+            self.counters.set(name, *value, depth);
+        }
+    }
+
+    /// the text of a `::marker` whose `content` is `normal`, from its `list-style-type`:
+    /// `1. ` for numbers and letters, `• ` for bullets
+    pub fn marker(&self, style: &ComputedStyle) -> Option<String> {
+        let marker = match style.list_style_type {
+            CounterStyle::None => return None,
+            bullet @ (CounterStyle::Disc | CounterStyle::Circle | CounterStyle::Square) => {
+                bullet.format(0)
+            }
+            numbered => format!("{}.", numbered.format(self.counters.value(LIST_ITEM))),
+        };
+        Some(marker + " ")
+    }
+
+    /// the text of a generated box with the `style` of its pseudo-element.
+    /// returns `None` if `content` doesn't generate a box
+    pub fn resolve(&mut self, style: &ComputedStyle, element: &ElementData) -> Option<String> {
+        let Content::Items(items) = &style.content else {
+            return None;
+        };
+
+        let mut text = String::new();
+        for item in items {
+            match item {
+                ContentItem::String(string) => text.push_str(string),
+                ContentItem::Attr(name) => {
+                    text.push_str(element.attrs.get(name).map_or("", String::as_str))
+                }
+                ContentItem::Counter(name, counter_style) => {
+                    text.push_str(&counter_style.format(self.counters.value(name)))
+                }
+                ContentItem::Counters(name, separator, counter_style) => {
+                    let values = self.counters.values(name);
+                    // like `counter()`, a missing counter shows up as 0
+                    let values = if values.is_empty() { vec![0] } else { values };
+                    let formatted: Vec<_> =
+                        values.iter().map(|v| counter_style.format(*v)).collect();
+                    text.push_str(&formatted.join(separator));
+                }
+                ContentItem::OpenQuote => {
+                    text.push_str(style.quotes.pair(self.quote_depth).0);
+                    self.quote_depth += 1;
+                }
+                ContentItem::CloseQuote => {
+                    // an unmatched close quote draws nothing
+                    if self.quote_depth > 0 {
+                        self.quote_depth -= 1;
+                        text.push_str(style.quotes.pair(self.quote_depth).1);
+                    }
+                }
+                ContentItem::NoOpenQuote => self.quote_depth += 1,
+                ContentItem::NoCloseQuote => self.quote_depth = self.quote_depth.saturating_sub(1),
+            }
+        }
+        Some(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{
+        dom::{Dom, NodeId},
+        layout::LayoutManager,
+        render::testing::dom_with,
+        stylesheet::Color,
+    };
+
+    #[test]
+    fn generated_content() {
+        let mut dom = dom_with(
+            r#"
+            <label id="required" title="name">x</label>
+            <ol id="list">
+                <li class="first">a</li>
+                <li>b<ol><li class="first">c</li><li>d</li></ol></li>
+            </ol>
+            <q id="outer">a<q id="inner">b</q></q>
+            <p id="none">x</p>
+            "#,
+            "q::before { content: open-quote } q::after { content: close-quote }",
+            r#"
+            #required::after { content: " (" attr(title) ")\2a"; color: red; }
+            #required:before { content: "> "; }
+            ol { counter-reset: item; }
+            li::before { counter-increment: item; content: counters(item, ".") ": "; }
+            li.first::after { content: counter(item, upper-roman); }
+            #outer { quotes: "«" "»" "<" ">"; }
+            #none::before { content: "x"; display: none; }
+            #none::after { content: none; }
+            "#,
+        );
+
+        let generated = |dom: &Dom, node: NodeId| -> Vec<(PseudoElement, String)> {
+            dom[node]
+                .generated
+                .iter()
+                .map(|g| (g.pseudo_element, g.text.clone()))
+                .collect()
+        };
+        let required = dom.query_select("#required").unwrap()[0];
+        assert_eq!(
+            generated(&dom, required),
+            [
+                (PseudoElement::Before, "> ".to_string()),
+                (PseudoElement::After, " (name)*".to_string())
+            ]
+        );
+        let after = dom[required].generated[1].style.clone();
+        assert_eq!(after.color, Color::from_name("red"));
+
+        let items: Vec<_> = dom
+            .query_select("li")
+            .unwrap()
+            .iter()
+            .map(|&li| {
+                let texts: Vec<_> = generated(&dom, li)
+                    .into_iter()
+                    .map(|(_, text)| text)
+                    .collect();
+                texts.join("|")
+            })
+            .collect();
+        assert_eq!(items, ["1: |I", "2: ", "2.1: |I", "2.2: "]);
+
+        let quotes: Vec<_> = dom
+            .query_select("q")
+            .unwrap()
+            .iter()
+            .map(|&q| {
+                generated(&dom, q)
+                    .into_iter()
+                    .map(|(_, text)| text)
+                    .collect::<String>()
+            })
+            .collect();
+        assert_eq!(quotes, ["«»", "<>"]);
+        assert!(generated(&dom, dom.query_select("#none").unwrap()[0]).is_empty());
+
+        // generated boxes are laid out as the first and last children
+        let mut layout = LayoutManager::new();
+        layout.build(&mut dom).unwrap();
+        layout.compute(taffy::Size::max_content()).unwrap();
+        assert!(layout
+            .get_generated(required, PseudoElement::Before)
+            .is_ok());
+    }
+}
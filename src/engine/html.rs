@@ -1,10 +1,10 @@
 use std::collections::HashMap;
 
-use crate::engine::dom::{self, ElementData, Node, NodeType, SharedNode};
+use crate::engine::dom::{self, ElementData, Node, NodeId, NodeType};
 use pest::{iterators::Pair, Parser};
 use pest_derive::Parser;
 
-use super::dom::shared_node::{Append as _, GetSetAttr as _};
+use super::dom::node_ref::{Append as _, GetSetAttr as _};
 
 #[derive(Parser)]
 #[grammar = "grammar/html.pest"]
@@ -16,7 +16,7 @@ pub fn parse_from_str(html: &str) -> Result<dom::Dom, pest::error::Error<Rule>>
 
     let first_token_peek = pairs.peek().unwrap();
 
-    let dom = dom::Dom::new(match first_token_peek.as_rule() {
+    let mut dom = dom::Dom::new(match first_token_peek.as_rule() {
         Rule::doctype => {
             pairs.next();
             first_token_peek.into_inner().as_str()
@@ -28,56 +28,54 @@ pub fn parse_from_str(html: &str) -> Result<dom::Dom, pest::error::Error<Rule>>
         if matches!(pair.as_rule(), Rule::EOI) {
             break;
         }
-        let node = parse_node(pair);
-        dom.root.append_shared_node(node).unwrap(); // TEMP:unwrap
+        let root = dom.root;
+        parse_node(&mut dom, root, pair);
     }
 
     Ok(dom)
 }
 
-fn parse_node(pair: Pair<Rule>) -> SharedNode {
+/// appends the node of `pair` to the children of `parent`, along with its subtree
+fn parse_node(dom: &mut dom::Dom, parent: NodeId, pair: Pair<Rule>) {
     match pair.as_rule() {
         Rule::element => {
             let mut inner = pair.into_inner();
             let tag_or_style = inner.next().unwrap();
 
-            let node = Node::new(NodeType::Element(ElementData::new(
-                tag_or_style.as_str(),
-                Some(HashMap::new()),
-            )))
-            .into_shared();
+            let node = dom
+                .node_mut(parent)
+                .append_node(Node::new(NodeType::Element(ElementData::new(
+                    tag_or_style.as_str(),
+                    Some(HashMap::new()),
+                ))));
 
-            // unwraps are to crash on poison error
             for child in inner {
                 match child.as_rule() {
-                    Rule::element => {
-                        node.append_shared_node(parse_node(child)).unwrap();
-                    }
+                    Rule::element => parse_node(dom, node, child),
                     Rule::attr_empty => {
-                        node.set_attr(child.as_str(), "").unwrap();
+                        dom.node_mut(node).set_attr(child.as_str(), "");
                     }
                     Rule::attr_with_value => {
                         let mut child_inner = child.into_inner();
-                        node.set_attr(
+                        dom.node_mut(node).set_attr(
                             child_inner.next().unwrap().as_str(),
                             child_inner
                                 .next()
                                 .unwrap()
                                 .as_str()
                                 .trim_matches(['\'', '"']),
-                        )
-                        .unwrap();
+                        );
                     }
                     Rule::text => {
-                        node.append_text(child.as_str()).unwrap();
+                        dom.node_mut(node).append_text(child.as_str());
                     }
                     _ => unreachable!(),
                 };
             }
-
-            node
         }
-        Rule::text => Node::new(NodeType::Text(String::from(pair.as_str()))).into_shared(),
+        Rule::text => {
+            dom.node_mut(parent).append_text(pair.as_str());
+        }
 
         _ => unreachable!(),
     }
@@ -0,0 +1,228 @@
+use std::{collections::HashMap, fmt::Write as _};
+
+use crate::engine::{
+    dom::{Dom, NodeType},
+    stylesheet,
+};
+
+use super::LayoutManager;
+
+/// A box of the layout tree, as `LayoutManager::dump` sees it: in cells, relative to the page
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoxDump {
+    /// `tag#id.class` for elements, with `::before` and the like for generated boxes,
+    /// the quoted text for text runs, and `viewport` or `anonymous` for the boxes without a node
+    pub label: String,
+    /// the top left corner of the border box, from the top left corner of the page
+    pub position: (f32, f32),
+    pub size: (f32, f32),
+    /// top, right, bottom and left, like in CSS
+    pub padding: [f32; 4],
+    pub border: [f32; 4],
+    pub margin: [f32; 4],
+    pub children: Vec<BoxDump>,
+}
+
+impl LayoutManager {
+    /// the box tree, from the viewport down, once `compute` has laid it out.
+    /// positions ignore scroll offsets, it's the layout and not what's drawn
+    pub fn dump(&self, dom: &Dom) -> super::super::Result<BoxDump> {
+        let root = self.root.ok_or(super::super::Error::LayoutRootNodeNone)?;
+        let pseudo_elements = self
+            .generated
+            .iter()
+            .map(|((_, pseudo), id)| (*id, *pseudo))
+            .collect();
+        self.dump_box(dom, root, (0.0, 0.0), &pseudo_elements)
+    }
+
+    /// `dump` as indented text, one box per line, eg.
+    /// `p#intro.lead (0, 1) 80x2 padding 0 1 0 1`.
+    /// padding, border and margin are left out when they're all 0
+    pub fn dump_text(&self, dom: &Dom) -> super::super::Result<String> {
+        let mut text = String::new();
+        write_text(&self.dump(dom)?, 0, &mut text);
+        Ok(text)
+    }
+
+    /// `dump` as JSON, with every field of every box, children last
+    pub fn dump_json(&self, dom: &Dom) -> super::super::Result<String> {
+        let mut json = String::new();
+        write_json(&self.dump(dom)?, &mut json);
+        Ok(json)
+    }
+
+    fn dump_box(
+        &self,
+        dom: &Dom,
+        id: taffy::NodeId,
+        origin: (f32, f32),
+        pseudo_elements: &HashMap<taffy::NodeId, stylesheet::PseudoElement>,
+    ) -> super::super::Result<BoxDump> {
+        let layout = self.tree.layout(id)?;
+        let position = (origin.0 + layout.location.x, origin.1 + layout.location.y);
+        let children = self
+            .tree
+            .children(id)?
+            .into_iter()
+            .map(|child| self.dump_box(dom, child, position, pseudo_elements))
+            .collect::<super::super::Result<_>>()?;
+        let sides = |rect: taffy::Rect<f32>| [rect.top, rect.right, rect.bottom, rect.left];
+        Ok(BoxDump {
+            label: self.label(dom, id, pseudo_elements.get(&id).copied()),
+            position,
+            size: (layout.size.width, layout.size.height),
+            padding: sides(layout.padding),
+            border: sides(layout.border),
+            margin: sides(layout.margin),
+            children,
+        })
+    }
+
+    fn label(
+        &self,
+        dom: &Dom,
+        id: taffy::NodeId,
+        pseudo_element: Option<stylesheet::PseudoElement>,
+    ) -> String {
+        if Some(id) == self.root {
+            return String::from("viewport");
+        }
+        let context = self.tree.get_node_context(id);
+        let Some(node) = context.and_then(|context| context.node) else {
+            return String::from("anonymous");
+        };
+        let mut label = match &dom[node].node_type {
+            NodeType::Element(element) => {
+                let mut label = element.tag.clone();
+                if let Some(id) = element.attrs.get("id") {
+                    write!(label, "#{id}").unwrap();
+                }
+                for class in element
+                    .attrs
+                    .get("class")
+                    .into_iter()
+                    .flat_map(|classes| classes.split_whitespace())
+                {
+                    write!(label, ".{class}").unwrap();
+                }
+                label
+            }
+            // the text as it's drawn
+            NodeType::Text(_) | NodeType::Comment(_) => {
+                let text = context.and_then(|context| context.text.as_deref());
+                format!("{:?}", text.unwrap_or_default())
+            }
+        };
+        if let Some(pseudo_element) = pseudo_element {
+            let text = context.and_then(|context| context.text.as_deref());
+            write!(label, "::{pseudo_element} {:?}", text.unwrap_or_default()).unwrap();
+        }
+        label
+    }
+}
+
+fn write_text(dump: &BoxDump, depth: usize, text: &mut String) {
+    let (x, y) = dump.position;
+    let (width, height) = dump.size;
+    write!(
+        text,
+        "{}{} ({x}, {y}) {width}x{height}",
+        "  ".repeat(depth),
+        dump.label
+    )
+    .unwrap();
+    for (name, sides) in [
+        ("padding", dump.padding),
+        ("border", dump.border),
+        ("margin", dump.margin),
+    ] {
+        if sides.iter().any(|side| *side != 0.0) {
+            let [top, right, bottom, left] = sides;
+            write!(text, " {name} {top} {right} {bottom} {left}").unwrap();
+        }
+    }
+    text.push('\n');
+    for child in &dump.children {
+        write_text(child, depth + 1, text);
+    }
+}
+
+fn write_json(dump: &BoxDump, json: &mut String) {
+    let (x, y) = dump.position;
+    let (width, height) = dump.size;
+    let sides = |[top, right, bottom, left]: [f32; 4]| format!("[{top},{right},{bottom},{left}]");
+    write!(
+        json,
+        r#"{{"label":{},"x":{x},"y":{y},"width":{width},"height":{height},"padding":{},"border":{},"margin":{},"children":["#,
+        json_string(&dump.label),
+        sides(dump.padding),
+        sides(dump.border),
+        sides(dump.margin),
+    )
+    .unwrap();
+    for (i, child) in dump.children.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        write_json(child, json);
+    }
+    json.push_str("]}");
+}
+
+fn json_string(string: &str) -> String {
+    let mut json = String::from('"');
+    for c in string.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if c.is_control() => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::render::testing::laid_out;
+
+    #[test]
+    fn dump() {
+        let (dom, layout) = laid_out(
+            r#"<div id="a" class="x y"><p>hi "you"</p></div>"#,
+            "#a { padding: 1tb; border: 1tb solid; margin-left: 1tb; } p::before { content: '>'; }",
+            (12, 6),
+        );
+        assert_eq!(
+            layout.dump_text(&dom).unwrap(),
+            [
+                "viewport (0, 0) 12x6",
+                "  root (0, 0) 12x7",
+                "    div#a.x.y (1, 0) 11x7 padding 1 1 1 1 border 1 1 1 1 margin 0 0 0 1",
+                "      p (3, 2) 7x3",
+                "        p::before \">\" (3, 2) 7x1",
+                "        \"hi \\\"you\\\"\" (3, 3) 7x2",
+                "",
+            ]
+            .join("\n")
+        );
+
+        let (dom, layout) = laid_out("<p>a\tb</p>", "", (12, 6));
+        assert_eq!(
+            layout.dump_json(&dom).unwrap(),
+            concat!(
+                r#"{"label":"viewport","x":0,"y":0,"width":12,"height":6,"#,
+                r#""padding":[0,0,0,0],"border":[0,0,0,0],"margin":[0,0,0,0],"children":["#,
+                r#"{"label":"root","x":0,"y":0,"width":12,"height":1,"#,
+                r#""padding":[0,0,0,0],"border":[0,0,0,0],"margin":[0,0,0,0],"children":["#,
+                r#"{"label":"p","x":0,"y":0,"width":12,"height":1,"#,
+                r#""padding":[0,0,0,0],"border":[0,0,0,0],"margin":[0,0,0,0],"children":["#,
+                r#"{"label":"\"a b\"","x":0,"y":0,"width":12,"height":1,"#,
+                r#""padding":[0,0,0,0],"border":[0,0,0,0],"margin":[0,0,0,0],"children":[]}]}]}]}"#,
+            )
+        );
+    }
+}
@@ -0,0 +1,1105 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use taffy::{
+    style_helpers::{auto, line, span},
+    AvailableSpace, Size,
+};
+
+mod dump;
+pub use dump::BoxDump;
+
+use super::{
+    computed_style::{BorderCollapse, CaptionSide, ComputedStyle, Display, Position, WhiteSpace},
+    dom::{Dom, NodeId, NodeType},
+    style_to_taffy,
+    stylesheet::PseudoElement,
+    table::{Slots, Span},
+    text,
+};
+
+/// What a box is drawn with, see `render`.
+/// boxes that only exist for layout, like the parts of a table that are left out of the grid, have none
+#[derive(Debug, Clone)]
+pub struct BoxContext {
+    pub style: Arc<ComputedStyle>,
+    /// the text of a text run or a generated box, after white-space processing and `text-transform`
+    pub text: Option<String>,
+    /// the node the box was generated by (the element, for `::before` and the like).
+    /// anonymous boxes have none
+    pub node: Option<NodeId>,
+}
+
+pub type LayoutTree = taffy::TaffyTree<BoxContext>;
+
+/// how many times `LayoutManager::compute` lays the boxes out again for their math functions at most,
+/// which is as deep as they can be nested in each other and still be right
+const MAX_CALC_PASSES: usize = 8;
+
+pub type LayoutMap = HashMap<NodeId, taffy::NodeId>;
+
+/// What a node was converted into, which is reused as long as it isn't dirty
+#[derive(Debug)]
+struct Converted {
+    /// the boxes it generates in its parent
+    boxes: Vec<taffy::NodeId>,
+    /// the boxes with `position: fixed` in its subtree
+    fixed: Vec<taffy::NodeId>,
+    /// the boxes it created besides its own, that nothing else refers to:
+    /// its generated boxes and the anonymous ones around its children
+    owned: Vec<taffy::NodeId>,
+    /// the nodes that were converted for its boxes, which are dropped once they aren't anymore,
+    /// eg. after they were removed, see `drop_converted`
+    children: Vec<NodeId>,
+    /// the `LayoutManager::pass` it was converted in
+    pass: usize,
+}
+
+pub struct LayoutManager {
+    pub tree: LayoutTree,
+    /// the viewport's box, which holds the boxes of the document and the fixed ones
+    pub root: Option<taffy::NodeId>,
+    map: LayoutMap,
+    /// the boxes with `position: fixed`, which are laid out in the viewport rather than in their parent
+    fixed: Vec<taffy::NodeId>,
+    /// the `::marker`/`::before`/`::after` boxes, which have no node of their own
+    generated: HashMap<(NodeId, PseudoElement), taffy::NodeId>,
+    /// the other way around from `generated`, see `pseudo_element_of`
+    generated_by: HashMap<taffy::NodeId, (NodeId, PseudoElement)>,
+    converted: HashMap<NodeId, Converted>,
+    /// the boxes created since the conversion of a node started, see `Converted::owned`
+    created: Vec<taffy::NodeId>,
+    /// the nodes converted since the conversion of a node started, see `Converted::children`
+    visited: Vec<NodeId>,
+    /// how many times `update` ran
+    pass: usize,
+}
+
+impl LayoutManager {
+    pub fn new() -> Self {
+        Self {
+            tree: taffy::TaffyTree::new(),
+            root: None,
+            map: LayoutMap::new(),
+            fixed: vec![],
+            generated: HashMap::new(),
+            generated_by: HashMap::new(),
+            converted: HashMap::new(),
+            created: vec![],
+            visited: vec![],
+            pass: 0,
+        }
+    }
+
+    /// converts the tree of `dom` into boxes, inside a box for the viewport that `compute` sizes
+    pub fn build(&mut self, dom: &mut Dom) -> super::Result<()> {
+        self.tree.clear();
+        self.root = None;
+        self.map.clear();
+        self.generated.clear();
+        self.generated_by.clear();
+        self.converted.clear();
+        self.update(dom)
+    }
+
+    /// converts the nodes of the tree of `dom` that are layout-dirty, and the ones they're in, again.
+    /// the boxes of the others are kept, and so is what taffy cached about them,
+    /// so `compute` only lays out what changed.
+    ///
+    /// the styles have to be up to date, see `Dom::update_styles`
+    pub fn update(&mut self, dom: &mut Dom) -> super::Result<()> {
+        self.fixed.clear();
+        self.pass += 1;
+        let mut children = self.convert_node_to_taffy(dom, dom.root)?;
+        self.visited.clear();
+        children.append(&mut self.fixed);
+        let root = match self.root {
+            Some(root) => root,
+            None => {
+                let viewport = taffy::Style {
+                    display: taffy::Display::Block,
+                    ..Default::default()
+                };
+                let root = self.tree.new_leaf(viewport)?;
+                self.root = Some(root);
+                root
+            }
+        };
+        if self.tree.children(root)? != children {
+            self.tree.set_children(root, &children)?;
+        }
+        Ok(())
+    }
+
+    /// the boxes that `node` generates in its parent:
+    /// none for `display: none` and collapsed whitespace,
+    /// its children's boxes for `display: contents`, and its own box otherwise.
+    ///
+    /// a node that isn't dirty gets the boxes it was converted into the last time.
+    /// otherwise its own box is reused, which tells taffy that it changed
+    fn convert_node_to_taffy(
+        &mut self,
+        dom: &mut Dom,
+        node: NodeId,
+    ) -> super::Result<Vec<taffy::NodeId>> {
+        let dirty = dom[node].dirty;
+        if !dirty.layout && !dirty.descendants {
+            if let Some(converted) = self.converted.get(&node) {
+                self.fixed.extend_from_slice(&converted.fixed);
+                self.visited.push(node);
+                return Ok(converted.boxes.clone());
+            }
+        }
+
+        let before = self
+            .converted
+            .get(&node)
+            .map(|converted| converted.children.clone())
+            .unwrap_or_default();
+        self.forget(node)?;
+        let old = self.map.remove(&node);
+        let (fixed, created, visited) = (self.fixed.len(), self.created.len(), self.visited.len());
+        let boxes = self.convert_node(dom, node, old)?;
+        if let Some(old) = old.filter(|old| self.map.get(&node) != Some(old)) {
+            self.tree.remove(old)?;
+        }
+        let children = self.visited.split_off(visited);
+        if !before.is_empty() {
+            let kept: HashSet<_> = children.iter().collect();
+            for gone in before.into_iter().filter(|child| !kept.contains(child)) {
+                self.drop_converted(gone)?;
+            }
+        }
+        let converted = Converted {
+            boxes: boxes.clone(),
+            fixed: self.fixed[fixed..].to_vec(),
+            owned: self.created.split_off(created),
+            children,
+            pass: self.pass,
+        };
+        self.converted.insert(node, converted);
+        self.visited.push(node);
+
+        clean(dom, node);
+        Ok(boxes)
+    }
+
+    /// drops what `node` was converted into, except for its own box
+    fn forget(&mut self, node: NodeId) -> super::Result<()> {
+        if let Some(converted) = self.converted.remove(&node) {
+            for id in converted.owned {
+                self.tree.remove(id)?;
+            }
+        }
+        for pseudo in [
+            PseudoElement::Marker,
+            PseudoElement::Before,
+            PseudoElement::After,
+        ] {
+            if let Some(id) = self.generated.remove(&(node, pseudo)) {
+                self.generated_by.remove(&id);
+            }
+        }
+        Ok(())
+    }
+
+    /// drops the boxes of a node that isn't converted anymore, and the ones of the nodes it converted,
+    /// so that a node that's removed leaves nothing behind in the tree.
+    /// it's left alone if it was just converted somewhere else, after it moved
+    fn drop_converted(&mut self, node: NodeId) -> super::Result<()> {
+        let children = match self.converted.get(&node) {
+            Some(converted) if converted.pass == self.pass => return Ok(()),
+            Some(converted) => converted.children.clone(),
+            None => vec![],
+        };
+        self.forget(node)?;
+        if let Some(id) = self.map.remove(&node) {
+            self.tree.remove(id)?;
+        }
+        for child in children {
+            self.drop_converted(child)?;
+        }
+        Ok(())
+    }
+
+    /// drops the dirty nodes of a tree that isn't converted, eg. under `display: none`,
+    /// so that they're converted again once it is
+    fn forget_dirty(&mut self, dom: &mut Dom, node: NodeId) -> super::Result<()> {
+        let dirty = dom[node].dirty;
+        if !dirty.layout && !dirty.descendants {
+            return Ok(());
+        }
+        clean(dom, node);
+        self.drop_converted(node)?;
+        for child in children_of(dom, node) {
+            self.forget_dirty(dom, child)?;
+        }
+        Ok(())
+    }
+
+    /// `convert_node_to_taffy`, reusing the `old` box of `node`
+    fn convert_node(
+        &mut self,
+        dom: &mut Dom,
+        node: NodeId,
+        old: Option<taffy::NodeId>,
+    ) -> super::Result<Vec<taffy::NodeId>> {
+        let r = &dom[node];
+        if let NodeType::Comment(_) = r.node_type {
+            return Ok(vec![]);
+        }
+        let computed_style = r.computed_style.clone();
+        let children = children_of(dom, node);
+        if computed_style
+            .as_ref()
+            .is_some_and(|style| style.display == Display::None)
+        {
+            for child in children {
+                self.forget_dirty(dom, child)?;
+            }
+            return Ok(vec![]);
+        }
+
+        // text runs are leaves, measured by `text::measure`
+        if let NodeType::Text(text) = &r.node_type {
+            let style = computed_style.unwrap_or_default();
+            let text = text::collapse(text, style.white_space);
+            let leaf = self.text_leaf(old, &style, &text, node)?;
+            self.map.insert(node, leaf);
+            return Ok(vec![leaf]);
+        }
+        let generated = r.generated.clone();
+
+        let display = computed_style.as_ref().map(|style| style.display);
+        let (mut children, columns) = match (display, &computed_style) {
+            (Some(display), Some(style)) if display.is_table() => {
+                let (children, columns) = self.convert_table(dom, &children, style)?;
+                (children, Some(columns))
+            }
+            _ => (
+                self.convert_children(dom, &children, computed_style.as_deref())?,
+                None,
+            ),
+        };
+
+        // generated boxes are leaves, like text.
+        // the `::marker` and `::before` boxes come first, in that order
+        let mut first = 0;
+        for generated in generated {
+            let leaf = self.text_leaf(None, &generated.style, &generated.text, node)?;
+            self.created.push(leaf);
+            match generated.pseudo_element {
+                PseudoElement::Marker | PseudoElement::Before => {
+                    children.insert(first, leaf);
+                    first += 1;
+                }
+                PseudoElement::After => children.push(leaf),
+            }
+            self.generated
+                .insert((node, generated.pseudo_element), leaf);
+            self.generated_by
+                .insert(leaf, (node, generated.pseudo_element));
+        }
+
+        if display == Some(Display::Contents) {
+            return Ok(children);
+        }
+
+        // nodes that haven't gone through the cascade get the default taffy style
+        let mut style = match &computed_style {
+            Some(computed_style) => style_to_taffy::computed_to_taffy(computed_style),
+            None => taffy::Style::DEFAULT,
+        };
+        if let Some(columns) = columns {
+            style.grid_template_columns = vec![auto(); columns];
+        }
+        let taffy_node = match old {
+            Some(id) => {
+                self.tree.set_style(id, style)?;
+                self.tree.set_children(id, &children)?;
+                id
+            }
+            None => self.new_box(style, &children)?,
+        };
+        let fixed = computed_style
+            .as_ref()
+            .is_some_and(|style| style.position == Position::Fixed);
+        let context = computed_style.map(|style| BoxContext {
+            style,
+            text: None,
+            node: Some(node),
+        });
+        self.tree.set_node_context(taffy_node, context)?;
+
+        self.map.insert(node, taffy_node);
+
+        if fixed {
+            self.fixed.push(taffy_node);
+            return Ok(vec![]);
+        }
+        Ok(vec![taffy_node])
+    }
+
+    /// a leaf for a text run, which is `id` if there's one to reuse
+    fn text_leaf(
+        &mut self,
+        id: Option<taffy::NodeId>,
+        style: &Arc<ComputedStyle>,
+        text: &str,
+        node: NodeId,
+    ) -> super::Result<taffy::NodeId> {
+        let context = BoxContext {
+            style: Arc::clone(style),
+            text: Some(style.transform_text(text).into_owned()),
+            node: Some(node),
+        };
+        let taffy_style = style_to_taffy::computed_to_taffy(style);
+        match id {
+            Some(id) => {
+                self.tree.set_style(id, taffy_style)?;
+                self.tree.set_node_context(id, Some(context))?;
+                Ok(id)
+            }
+            None => Ok(self.tree.new_leaf_with_context(taffy_style, context)?),
+        }
+    }
+
+    /// unlike `new_with_children`, takes the children away from the box they were in before
+    fn new_box(
+        &mut self,
+        style: taffy::Style,
+        children: &[taffy::NodeId],
+    ) -> super::Result<taffy::NodeId> {
+        let id = self.tree.new_leaf(style)?;
+        self.tree.set_children(id, children)?;
+        Ok(id)
+    }
+
+    /// converts the children of a box whose style is `parent_style`.
+    /// table parts outside of a table are wrapped in an anonymous one
+    fn convert_children(
+        &mut self,
+        dom: &mut Dom,
+        children: &[NodeId],
+        parent_style: Option<&ComputedStyle>,
+    ) -> super::Result<Vec<taffy::NodeId>> {
+        let is_table_part =
+            |dom: &Dom, node| display_of(dom, node).is_some_and(Display::is_table_internal);
+
+        let mut converted = vec![];
+        let mut i = 0;
+        while i < children.len() {
+            if is_collapsed_whitespace(dom, children, i) {
+                self.forget_dirty(dom, children[i])?;
+                i += 1;
+                continue;
+            }
+            if !is_table_part(dom, children[i]) {
+                converted.extend(self.convert_node_to_taffy(dom, children[i])?);
+                i += 1;
+                continue;
+            }
+
+            let start = i;
+            while i < children.len()
+                && (is_table_part(dom, children[i]) || is_collapsed_whitespace(dom, children, i))
+            {
+                i += 1;
+            }
+            let mut style = ComputedStyle::anonymous(parent_style.unwrap_or(&Default::default()));
+            style.display = Display::Table;
+            let (cells, columns) = self.convert_table(dom, &children[start..i], &style)?;
+
+            let mut taffy_style = style_to_taffy::computed_to_taffy(&style);
+            taffy_style.grid_template_columns = vec![auto(); columns];
+            let table = self.new_box(taffy_style, &cells)?;
+            self.created.push(table);
+            let context = BoxContext {
+                style: Arc::new(style),
+                text: None,
+                node: None,
+            };
+            self.tree.set_node_context(table, Some(context))?;
+            converted.push(table);
+        }
+        Ok(converted)
+    }
+
+    /// lays the `parts` of a table out as the items of a grid, whose box is the table's.
+    /// rows and row groups have no box of their own, their cells are placed in the grid
+    /// according to `colspan` and `rowspan`, and captions span every column.
+    ///
+    /// returns the items and the number of columns
+    fn convert_table(
+        &mut self,
+        dom: &mut Dom,
+        parts: &[NodeId],
+        table_style: &ComputedStyle,
+    ) -> super::Result<(Vec<taffy::NodeId>, usize)> {
+        let mut structure = TableStructure::default();
+        structure.collect(dom, parts, table_style);
+        let TableStructure {
+            captions,
+            head,
+            body,
+            foot,
+            ..
+        } = structure;
+
+        // the cells of the header groups go first, and the ones of the footer groups last
+        let groups: Vec<_> = head.into_iter().chain(body).chain(foot).collect();
+        let mut slots = Slots::default();
+        let mut placed = vec![];
+        for group in &groups {
+            let spans: Vec<Vec<_>> = group
+                .iter()
+                .map(|row| row.iter().map(|cell| cell.span(dom)).collect())
+                .collect();
+            placed.push(slots.place_group(&spans));
+        }
+        let columns = slots.columns().max(1);
+
+        let top_captions = captions
+            .iter()
+            .filter(|(side, _)| *side == CaptionSide::Top)
+            .count();
+        let collapse = table_style.border_collapse == BorderCollapse::Collapse;
+
+        let mut items = vec![];
+        for (group, placed) in groups.into_iter().zip(placed) {
+            for (row, placed) in group.into_iter().zip(placed) {
+                for (cell, placement) in row.into_iter().zip(placed) {
+                    let ids = match cell {
+                        TableCell::Node(node) => self.convert_node_to_taffy(dom, node)?,
+                        TableCell::Anonymous(nodes) => {
+                            let children = self.convert_children(dom, &nodes, Some(table_style))?;
+                            let cell = self.new_box(taffy::Style::DEFAULT, &children)?;
+                            self.created.push(cell);
+                            vec![cell]
+                        }
+                    };
+                    for id in ids {
+                        let mut style = self.tree.style(id)?.clone();
+                        style.grid_row = taffy::Line {
+                            start: line((placement.row + top_captions + 1) as i16),
+                            end: span(placement.rows as u16),
+                        };
+                        style.grid_column = taffy::Line {
+                            start: line((placement.column + 1) as i16),
+                            end: span(placement.columns as u16),
+                        };
+                        // the cells above and to the left already draw the borders they share
+                        if collapse && placement.row > 0 {
+                            style.border.top = taffy::LengthPercentage::length(0.0);
+                        }
+                        if collapse && placement.column > 0 {
+                            style.border.left = taffy::LengthPercentage::length(0.0);
+                        }
+                        // the boxes of cells that weren't converted again are already placed
+                        if *self.tree.style(id)? != style {
+                            self.tree.set_style(id, style)?;
+                        }
+                        items.push(id);
+                    }
+                }
+            }
+        }
+
+        let mut top = 0;
+        let mut bottom = 0;
+        for (side, node) in captions {
+            let row = match side {
+                CaptionSide::Top => {
+                    top += 1;
+                    top
+                }
+                CaptionSide::Bottom => {
+                    bottom += 1;
+                    top_captions + slots.rows() + bottom
+                }
+            };
+            for id in self.convert_node_to_taffy(dom, node)? {
+                let mut style = self.tree.style(id)?.clone();
+                style.grid_row = line(row as i16);
+                style.grid_column = taffy::Line {
+                    start: line(1),
+                    end: line(-1),
+                };
+                if *self.tree.style(id)? != style {
+                    self.tree.set_style(id, style)?;
+                }
+                items.push(id);
+            }
+        }
+
+        Ok((items, columns))
+    }
+
+    pub fn get_node_id(&self, node: NodeId) -> Option<taffy::NodeId> {
+        self.map.get(&node).copied()
+    }
+
+    pub fn get(&self, node: NodeId) -> super::Result<&taffy::Layout> {
+        let id = self
+            .get_node_id(node)
+            .ok_or(super::Error::LayoutNodeNotFound)?;
+        Ok(self.tree.layout(id)?)
+    }
+
+    /// the layout of the `pseudo` box of `node`, if it has one
+    pub fn get_generated(
+        &self,
+        node: NodeId,
+        pseudo: PseudoElement,
+    ) -> super::Result<&taffy::Layout> {
+        let id = self
+            .generated
+            .get(&(node, pseudo))
+            .ok_or(super::Error::LayoutNodeNotFound)?;
+        Ok(self.tree.layout(*id)?)
+    }
+
+    /// the pseudo-element that the box `id` was generated for, if it's a `::marker`/`::before`/`::after` box
+    pub fn pseudo_element_of(&self, id: taffy::NodeId) -> Option<PseudoElement> {
+        self.generated_by.get(&id).map(|&(_, pseudo)| pseudo)
+    }
+
+    /// lays the boxes out in a viewport of `available_space`.
+    /// a definite size is the size of the viewport, which fixed boxes are positioned in.
+    ///
+    /// taffy can't evaluate math functions, so the boxes with some are laid out again
+    /// once their containing block is, see `resolve_calc`
+    pub fn compute(&mut self, available_space: Size<AvailableSpace>) -> super::Result<()> {
+        let root = self.root.ok_or(super::Error::LayoutRootNodeNone)?;
+        let size = |space| match space {
+            AvailableSpace::Definite(size) => taffy::Dimension::length(size),
+            _ => taffy::Dimension::auto(),
+        };
+        let mut viewport = self.tree.style(root)?.clone();
+        viewport.size = Size {
+            width: size(available_space.width),
+            height: size(available_space.height),
+        };
+        if *self.tree.style(root)? != viewport {
+            self.tree.set_style(root, viewport)?;
+        }
+
+        self.lay_out(root, available_space)?;
+        // each pass settles the math functions of the boxes inside the ones that changed in the pass before
+        for _ in 0..MAX_CALC_PASSES {
+            if !self.resolve_calc(root)? {
+                break;
+            }
+            self.lay_out(root, available_space)?;
+        }
+        Ok(())
+    }
+
+    fn lay_out(
+        &mut self,
+        root: taffy::NodeId,
+        available_space: Size<AvailableSpace>,
+    ) -> super::Result<()> {
+        Ok(self.tree.compute_layout_with_measure(
+            root,
+            available_space,
+            |known_dimensions, available_space, _, context, _| match context {
+                Some(BoxContext {
+                    style,
+                    text: Some(text),
+                    ..
+                }) => text::measure(text, style.white_space, known_dimensions, available_space),
+                _ => Size::ZERO,
+            },
+        )?)
+    }
+
+    /// resolves the math functions of the boxes against the size their containing block was just laid out with:
+    /// the padding box of their parent if they're positioned absolutely, and its content box otherwise.
+    /// returns whether any box changed
+    fn resolve_calc(&mut self, root: taffy::NodeId) -> super::Result<bool> {
+        let mut changed = false;
+        let mut parents = vec![root];
+        while let Some(parent) = parents.pop() {
+            let layout = self.tree.layout(parent)?;
+            let padding_box = Size {
+                width: layout.size.width - layout.border.left - layout.border.right,
+                height: layout.size.height - layout.border.top - layout.border.bottom,
+            };
+            let content_box = Size {
+                width: padding_box.width
+                    - layout.padding.left
+                    - layout.padding.right
+                    - layout.scrollbar_size.width,
+                height: padding_box.height
+                    - layout.padding.top
+                    - layout.padding.bottom
+                    - layout.scrollbar_size.height,
+            };
+
+            for child in self.tree.children(parent)? {
+                parents.push(child);
+                let style = match self.tree.get_node_context(child) {
+                    Some(context) if context.style.has_calc() => Arc::clone(&context.style),
+                    _ => continue,
+                };
+                let mut taffy_style = self.tree.style(child)?.clone();
+                let containing_block = match taffy_style.position {
+                    taffy::Position::Absolute => padding_box,
+                    taffy::Position::Relative => content_box,
+                };
+                style_to_taffy::resolve_calc(&style, &mut taffy_style, containing_block);
+                if *self.tree.style(child)? != taffy_style {
+                    self.tree.set_style(child, taffy_style)?;
+                    changed = true;
+                }
+            }
+        }
+        Ok(changed)
+    }
+}
+
+/// clears the dirty bits of a node whose boxes are up to date.
+/// it's also used on the parts of a table that have no box of their own:
+/// their cells are converted again, since the table is
+fn clean(dom: &mut Dom, node: NodeId) {
+    let dirty = &mut dom[node].dirty;
+    dirty.layout = false;
+    dirty.descendants = false;
+}
+
+fn children_of(dom: &Dom, node: NodeId) -> Vec<NodeId> {
+    dom.children(node).collect()
+}
+
+fn display_of(dom: &Dom, node: NodeId) -> Option<Display> {
+    dom[node].computed_style.as_ref().map(|style| style.display)
+}
+
+/// a cell of a table, or the content that's wrapped in an anonymous one
+#[derive(Debug)]
+enum TableCell {
+    Node(NodeId),
+    Anonymous(Vec<NodeId>),
+}
+
+impl TableCell {
+    fn span(&self, dom: &Dom) -> Span {
+        match self {
+            TableCell::Node(node) => match &dom[*node].node_type {
+                NodeType::Element(element) => Span::new(Some(element)),
+                _ => Span::new(None),
+            },
+            TableCell::Anonymous(_) => Span::new(None),
+        }
+    }
+}
+
+type TableRow = Vec<TableCell>;
+
+/// The parts of a table, after the anonymous rows and cells are added where they're missing
+#[derive(Debug, Default)]
+struct TableStructure {
+    captions: Vec<(CaptionSide, NodeId)>,
+    head: Vec<Vec<TableRow>>,
+    body: Vec<Vec<TableRow>>,
+    foot: Vec<Vec<TableRow>>,
+    /// rows that are directly in the table, which make up an anonymous row group
+    loose: Vec<TableRow>,
+    /// whether the last loose row is anonymous, and still takes the cells that follow it
+    open_row: bool,
+}
+
+impl TableStructure {
+    fn collect(&mut self, dom: &mut Dom, parts: &[NodeId], table_style: &ComputedStyle) {
+        for (i, &part) in parts.iter().enumerate() {
+            if is_collapsed_whitespace(dom, parts, i) {
+                continue;
+            }
+            let r = &dom[part];
+            if let NodeType::Comment(_) = r.node_type {
+                continue;
+            }
+            let style = r.computed_style.as_ref();
+            let display = style.map(|style| style.display);
+            let caption_side = style.map_or(table_style.caption_side, |style| style.caption_side);
+            let group = match display {
+                Some(Display::None | Display::TableColumn | Display::TableColumnGroup) => continue,
+                Some(Display::TableCaption) => {
+                    self.captions.push((caption_side, part));
+                    continue;
+                }
+                Some(Display::TableHeaderGroup) => &mut self.head,
+                Some(Display::TableRowGroup) => &mut self.body,
+                Some(Display::TableFooterGroup) => &mut self.foot,
+                _ => {
+                    Self::add_to_rows(dom, &mut self.loose, &mut self.open_row, part, display);
+                    continue;
+                }
+            };
+            clean(dom, part);
+            let children = children_of(dom, part);
+            let mut rows = vec![];
+            let mut open_row = false;
+            for (i, &child) in children.iter().enumerate() {
+                if !is_collapsed_whitespace(dom, &children, i) {
+                    let display = display_of(dom, child);
+                    Self::add_to_rows(dom, &mut rows, &mut open_row, child, display);
+                }
+            }
+            group.push(rows);
+            self.flush_loose();
+        }
+        self.flush_loose();
+    }
+
+    fn flush_loose(&mut self) {
+        if !self.loose.is_empty() {
+            self.body.push(std::mem::take(&mut self.loose));
+        }
+        self.open_row = false;
+    }
+
+    /// adds a row to `rows`, or a cell (or content for an anonymous one) to the last row
+    fn add_to_rows(
+        dom: &mut Dom,
+        rows: &mut Vec<TableRow>,
+        open_row: &mut bool,
+        part: NodeId,
+        display: Option<Display>,
+    ) {
+        if display == Some(Display::TableRow) {
+            clean(dom, part);
+            let children = children_of(dom, part);
+            let mut cells = vec![];
+            for (i, &child) in children.iter().enumerate() {
+                if !is_collapsed_whitespace(dom, &children, i) {
+                    Self::add_to_row(&mut cells, child, display_of(dom, child));
+                }
+            }
+            rows.push(cells);
+            *open_row = false;
+            return;
+        }
+
+        if !*open_row {
+            rows.push(vec![]);
+            *open_row = true;
+        }
+        let row = rows.last_mut().expect("a row was just added");
+        Self::add_to_row(row, part, display);
+    }
+
+    fn add_to_row(row: &mut TableRow, part: NodeId, display: Option<Display>) {
+        match (display, row.last_mut()) {
+            (Some(Display::None), _) => {}
+            (Some(Display::TableCell), _) => row.push(TableCell::Node(part)),
+            (_, Some(TableCell::Anonymous(nodes))) => nodes.push(part),
+            _ => row.push(TableCell::Anonymous(vec![part])),
+        }
+    }
+}
+
+/// whether `siblings[i]` is whitespace-only text that white-space processing would remove entirely:
+/// collapsible whitespace next to a block (or at the start or end of its parent)
+/// ends up at the start or end of a line
+fn is_collapsed_whitespace(dom: &Dom, siblings: &[NodeId], i: usize) -> bool {
+    let r = &dom[siblings[i]];
+    let NodeType::Text(text) = &r.node_type else {
+        return false;
+    };
+    let collapsible = r
+        .computed_style
+        .as_ref()
+        .is_none_or(|style| matches!(style.white_space, WhiteSpace::Normal | WhiteSpace::Nowrap));
+    if !collapsible || !text.chars().all(char::is_whitespace) {
+        return false;
+    }
+
+    // the nearest siblings that generate a box
+    let is_inline = |&sibling: &NodeId| -> Option<bool> {
+        let r = &dom[sibling];
+        let display = r.computed_style.as_ref().map(|style| style.display);
+        match (&r.node_type, display) {
+            (NodeType::Comment(_), _) | (_, Some(Display::None)) => None,
+            (NodeType::Text(_), _) => Some(true),
+            (_, display) => Some(display.is_none_or(|display| display.is_inline_level())),
+        }
+    };
+    let before = siblings[..i].iter().rev().find_map(is_inline);
+    let after = siblings[i + 1..].iter().find_map(is_inline);
+    before != Some(true) || after != Some(true)
+}
+
+impl Default for LayoutManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{
+        computed_style::{Length, LengthAuto},
+        css,
+        dom::{Append as _, ElementData, GetSetAttr as _, Mutate as _, Node},
+        html,
+        render::{
+            testing::{computed, dom_with, draw, laid_out, layout_of},
+            Glyphs,
+        },
+        stylesheet::Origin,
+    };
+
+    #[test]
+    fn incremental() {
+        let css = "#side { width: 50vw; border: 1tb solid; } .wide { width: 20tb; } li::before { content: '-'; } li + li::before { content: '+'; }";
+        let page = |items: &str| {
+            format!(r#"<div id="side">side</div><p id="text">text</p><ul>{items}</ul>"#)
+        };
+        // what a full restyle and rebuild draws
+        let fresh = |html_input: &str, size: (u16, u16)| {
+            let mut dom = html::parse_from_str(html_input).unwrap();
+            dom.stylesheets
+                .push(css::parse_from_str(css, Origin::Author).unwrap());
+            dom.set_viewport(size).unwrap();
+            dom.refresh_styles().unwrap();
+            let layout = layout_of(&mut dom, size);
+            draw(&dom, &layout, size, Glyphs::Unicode)
+        };
+        let relayout = |dom: &mut Dom, layout: &mut LayoutManager, size: (u16, u16)| {
+            dom.update_styles().unwrap();
+            layout.update(dom).unwrap();
+            let space = taffy::AvailableSpace::Definite;
+            let size = taffy::Size {
+                width: space(size.0.into()),
+                height: space(size.1.into()),
+            };
+            layout.compute(size).unwrap();
+        };
+
+        let (mut dom, mut layout) = laid_out(&page("<li>a</li>"), css, (20, 8));
+        dom.set_viewport((20, 8)).unwrap();
+        relayout(&mut dom, &mut layout, (20, 8));
+        let text = dom.query_select("#text").unwrap().remove(0);
+        let text_box = layout.get_node_id(text).unwrap();
+        assert_eq!(dom[dom.root].dirty, Default::default());
+
+        // the box of `#side` follows the viewport, the others are kept as they are
+        dom.set_viewport((30, 8)).unwrap();
+        layout.update(&mut dom).unwrap();
+        assert!(!layout.tree.dirty(text_box).unwrap());
+        relayout(&mut dom, &mut layout, (30, 8));
+        let expected = fresh(&page("<li>a</li>"), (30, 8));
+        assert_eq!(expected[0], "┌─────────────┐");
+        assert_eq!(draw(&dom, &layout, (30, 8), Glyphs::Unicode), expected);
+        assert_eq!(layout.get_node_id(text), Some(text_box));
+
+        dom.node_mut(text).set_attr("class", "wide");
+        relayout(&mut dom, &mut layout, (30, 8));
+        let html_input = page("<li>a</li>").replace(r#"id="text""#, r#"id="text" class="wide""#);
+        assert_eq!(
+            draw(&dom, &layout, (30, 8), Glyphs::Unicode),
+            fresh(&html_input, (30, 8))
+        );
+
+        let list = dom.query_select("ul").unwrap().remove(0);
+        let item = dom.node_mut(list).append_element("li", None);
+        dom.node_mut(item).append_text("b");
+        relayout(&mut dom, &mut layout, (30, 8));
+        let html_input =
+            page("<li>a</li><li>b</li>").replace(r#"id="text""#, r#"id="text" class="wide""#);
+        assert_eq!(
+            draw(&dom, &layout, (30, 8), Glyphs::Unicode),
+            fresh(&html_input, (30, 8))
+        );
+
+        // "a" isn't the first item anymore, so it gets the `li + li` marker
+        let first = dom[list].first_child().unwrap();
+        let inserted = dom.create_node(Node::new(NodeType::Element(ElementData::new("li", None))));
+        dom.node_mut(inserted).append_text("c");
+        dom.node_mut(list)
+            .insert_before(inserted, Some(first))
+            .unwrap();
+        relayout(&mut dom, &mut layout, (30, 8));
+        let html_input = page("<li>c</li><li>a</li><li>b</li>")
+            .replace(r#"id="text""#, r#"id="text" class="wide""#);
+        assert_eq!(
+            draw(&dom, &layout, (30, 8), Glyphs::Unicode),
+            fresh(&html_input, (30, 8))
+        );
+
+        // and it's the first again once "c" is gone
+        dom.node_mut(list).remove_child(inserted).unwrap();
+        relayout(&mut dom, &mut layout, (30, 8));
+        let html_input =
+            page("<li>a</li><li>b</li>").replace(r#"id="text""#, r#"id="text" class="wide""#);
+        assert_eq!(
+            draw(&dom, &layout, (30, 8), Glyphs::Unicode),
+            fresh(&html_input, (30, 8))
+        );
+    }
+
+    #[test]
+    fn removed_boxes_are_dropped() {
+        let css = "li::before { content: '-'; } td::after { content: '|'; }";
+        let html_input = r#"<div id="page"><ul id="list"><li>a</li><li>b <b>c</b></li></ul>
+            <div id="table"><td>1</td><td>2</td></div></div>"#;
+        let (mut dom, mut layout) = laid_out(html_input, css, (20, 8));
+        let boxes = layout.tree.total_node_count();
+
+        let page = dom.query_select("#page").unwrap()[0];
+        for query in ["#list", "#table", "#list"] {
+            let removed = dom.query_select(query).unwrap()[0];
+            let next = dom[removed].next_sibling();
+            dom.node_mut(page).remove_child(removed).unwrap();
+            dom.update_styles().unwrap();
+            layout.update(&mut dom).unwrap();
+            assert!(layout.tree.total_node_count() < boxes);
+
+            dom.node_mut(page).insert_before(removed, next).unwrap();
+            dom.update_styles().unwrap();
+            layout.update(&mut dom).unwrap();
+            assert_eq!(layout.tree.total_node_count(), boxes);
+        }
+    }
+
+    #[test]
+    fn layout_reads_computed_style() {
+        let mut dom = dom_with(
+            r#"<div id="box"><p id="inner">text</p></div>"#,
+            "",
+            "#box { width: 20tb; padding: 1tb; } #inner { height: 3tb; }",
+        );
+
+        let layout = layout_of(&mut dom, (80, 24));
+
+        let outer = layout.get(dom.query_select("#box").unwrap()[0]).unwrap();
+        assert_eq!(outer.size.width, 20.0);
+        assert_eq!(outer.size.height, 5.0);
+
+        let inner = layout.get(dom.query_select("#inner").unwrap()[0]).unwrap();
+        assert_eq!(inner.location.x, 1.0);
+        assert_eq!(inner.size.width, 18.0);
+    }
+
+    #[test]
+    fn percentages_use_the_containing_block() {
+        let mut dom = dom_with(
+            r#"<div id="auto"><div id="outer"><p id="half">text</p><p id="pos"></p></div></div>"#,
+            "",
+            "#outer { height: 10tb; position: relative; }
+             #half { width: 50%; padding-left: 10%; }
+             #pos { position: absolute; top: 50%; left: 25%; }",
+        );
+        let style = computed(&dom, "#half");
+        assert_eq!(style.width, LengthAuto::Percent(0.5));
+        assert_eq!(style.padding.left, Length::Percent(0.1));
+
+        let layout = layout_of(&mut dom, (80, 24));
+
+        // the parent's width is `auto`, but its used width is known
+        let half = layout.get(dom.query_select("#half").unwrap()[0]).unwrap();
+        assert_eq!(half.size.width, 40.0);
+        // padding is relative to the width, even on the vertical axis
+        assert_eq!(half.padding.left, 8.0);
+
+        let pos = layout.get(dom.query_select("#pos").unwrap()[0]).unwrap();
+        assert_eq!(pos.location.y, 5.0);
+        assert_eq!(pos.location.x, 20.0);
+    }
+
+    #[test]
+    fn math_functions_in_layout() {
+        use crate::engine::stylesheet::PseudoElement;
+
+        let mut dom = dom_with(
+            r#"<html><body><div id="parent"><p id="child"><span id="inner">a</span></p></div></body></html>"#,
+            "",
+            r#"
+            html::before { content: 'x'; width: calc(50% + 1tb); }
+            #parent { padding-left: 2tb; padding-right: 2tb; }
+            #child { width: calc(100% - 2tb); }
+            #inner { display: block; width: calc(50% + 1tb); }
+            "#,
+        );
+        let mut layout = LayoutManager::new();
+        layout.build(&mut dom).unwrap();
+        let width = |dom: &Dom, layout: &LayoutManager, query: &str| {
+            layout
+                .get(dom.query_select(query).unwrap()[0])
+                .unwrap()
+                .size
+                .width
+        };
+
+        for viewport in [80.0, 60.0] {
+            layout
+                .compute(taffy::Size {
+                    width: taffy::AvailableSpace::Definite(viewport),
+                    height: taffy::AvailableSpace::Definite(24.0),
+                })
+                .unwrap();
+            // `#parent` is as wide as the viewport, without its padding
+            assert_eq!(width(&dom, &layout, "#child"), viewport - 6.0);
+            assert_eq!(width(&dom, &layout, "#inner"), (viewport - 6.0) / 2.0 + 1.0);
+            let html = dom.query_select("html").unwrap()[0];
+            let before = layout.get_generated(html, PseudoElement::Before).unwrap();
+            assert_eq!(before.size.width, viewport / 2.0 + 1.0);
+        }
+    }
+
+    #[test]
+    fn boxes_left_out_of_layout() {
+        let mut dom = dom_with(
+            r#"<div id="page">
+                <script>let x;</script>
+                <p id="gone">a</p>
+                <p hidden>b</p>
+                <p id="invisible">c</p>
+                <div id="contents"><p id="spliced">d</p></div>
+                <p id="words"></p>
+            </div>"#,
+            "",
+            "#gone { display: none; }
+             #invisible { visibility: hidden; height: 2tb; }
+             #contents { display: contents; }
+             p { display: block; height: 1tb; }",
+        );
+        // the parser drops comments and whitespace-only text, but scripts can add them
+        let page = dom.query_select("#page").unwrap()[0];
+        dom.node_mut(page).append_comment("comment");
+        dom.node_mut(page).append_text("\n    ");
+        let words = dom.query_select("#words").unwrap()[0];
+        let b = dom.node_mut(words).append_element("b", None);
+        dom.node_mut(b).append_text("e");
+        dom.node_mut(words).append_text(" ");
+        let i = dom.node_mut(words).append_element("i", None);
+        dom.node_mut(i).append_text("f");
+        dom.refresh_styles().unwrap();
+
+        let mut layout = LayoutManager::new();
+        layout.build(&mut dom).unwrap();
+        layout.compute(taffy::Size::max_content()).unwrap();
+
+        let node = |query| dom.query_select(query).unwrap()[0];
+        assert_eq!(computed(&dom, "script").display, Display::None);
+        assert!(layout.get(node("script")).is_err());
+        assert!(layout.get(node("#gone")).is_err());
+        assert!(layout.get(node("#contents")).is_err());
+
+        // the page only has the invisible paragraph, the spliced one and the words
+        let page = layout.get_node_id(page).unwrap();
+        assert_eq!(layout.tree.children(page).unwrap().len(), 3);
+        assert_eq!(layout.get(node("#invisible")).unwrap().size.height, 2.0);
+        assert_eq!(layout.get(node("#spliced")).unwrap().location.y, 2.0);
+        assert_eq!(layout.get(node("#page")).unwrap().size.height, 4.0);
+
+        // whitespace between inline boxes is kept
+        let words = layout.get_node_id(words).unwrap();
+        assert_eq!(layout.tree.children(words).unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+/// Fetches the resources a page refers to, like the stylesheets in `@import`
+pub trait ResourceLoader {
+    /// returns `None` if `url` can't be loaded
+    fn load(&self, url: &str) -> Option<String>;
+}
+
+/// loads `file://` urls and plain paths from the filesystem
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FileLoader;
+
+impl ResourceLoader for FileLoader {
+    fn load(&self, url: &str) -> Option<String> {
+        let path = url.strip_prefix("file://").unwrap_or(url);
+        std::fs::read_to_string(path).ok()
+    }
+}
+
+/// resources kept in memory, by url
+impl ResourceLoader for HashMap<String, String> {
+    fn load(&self, url: &str) -> Option<String> {
+        self.get(url).cloned()
+    }
+}
+
+/// resolves `href` against the url of the resource that refers to it
+pub fn resolve_url(base: &str, href: &str) -> String {
+    if href.contains("://") {
+        return href.to_string();
+    }
+
+    // `scheme://host`, which absolute paths are resolved against
+    let origin_len = base
+        .find("://")
+        .map(|i| i + 3 + base[i + 3..].find('/').unwrap_or(base.len() - i - 3))
+        .unwrap_or(0);
+    let (origin, path) = base.split_at(origin_len);
+
+    let joined = match href.strip_prefix('/') {
+        Some(_) => href.to_string(),
+        None => match path.rfind('/') {
+            Some(slash) => format!("{}{href}", &path[..=slash]),
+            None => href.to_string(),
+        },
+    };
+
+    let mut segments: Vec<&str> = vec![];
+    for segment in joined.split('/') {
+        match segment {
+            "." => {}
+            ".." if segments.last().is_some_and(|s| !s.is_empty() && *s != "..") => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+
+    format!("{origin}{}", segments.join("/"))
+}
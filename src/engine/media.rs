@@ -0,0 +1,351 @@
+use strum_macros::{Display, EnumString};
+
+use super::stylesheet::{FontMetrics, Unit, Value};
+
+/// What `@media` queries are evaluated against, apart from the viewport
+/// (which lives on the `Dom`, since lengths need it too).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Device {
+    pub color_scheme: ColorScheme,
+    /// terminals can't animate anything anyway, so this defaults to `true`
+    pub reduced_motion: bool,
+    /// bits per color component, 0 on monochrome terminals
+    pub color_bits: u8,
+    /// bits per pixel on monochrome terminals, 0 on color ones
+    pub monochrome_bits: u8,
+}
+
+impl Default for Device {
+    fn default() -> Self {
+        Self {
+            color_scheme: ColorScheme::Dark,
+            reduced_motion: true,
+            color_bits: 8,
+            monochrome_bits: 0,
+        }
+    }
+}
+
+impl Device {
+    /// guesses the capabilities of the terminal from the usual environment variables
+    /// (`NO_COLOR`, `COLORTERM`, `TERM` and `COLORFGBG`)
+    pub fn from_env() -> Self {
+        let var = |name| std::env::var(name).unwrap_or_default();
+        let mut device = Self::default();
+
+        let colorterm = var("COLORTERM");
+        let term = var("TERM");
+        if !var("NO_COLOR").is_empty() || term == "dumb" {
+            device.color_bits = 0;
+            device.monochrome_bits = 1;
+        } else if colorterm != "truecolor" && colorterm != "24bit" {
+            // 256 colors are roughly 2-3 bits per component
+            device.color_bits = if term.contains("256color") { 2 } else { 1 };
+        }
+
+        // eg. `15;0`: light text on a dark background
+        if let Some(bg) = var("COLORFGBG").rsplit(';').next() {
+            if let Ok(bg) = bg.parse::<u8>() {
+                device.color_scheme = match bg {
+                    7 | 9..=15 => ColorScheme::Light,
+                    _ => ColorScheme::Dark,
+                };
+            }
+        }
+
+        device
+    }
+}
+
+#[derive(Debug, Display, EnumString, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "kebab-case", ascii_case_insensitive)]
+pub enum ColorScheme {
+    Light,
+    Dark,
+}
+
+/// The prelude of a `@media` rule: matches if any of the queries match.
+/// An empty list matches everything.
+#[derive(Debug, Default)]
+pub struct MediaQueryList(pub Vec<MediaQuery>);
+
+#[derive(Debug)]
+pub struct MediaQuery {
+    /// `not screen and (color)`
+    pub negated: bool,
+    /// `None` is the same as `all`
+    pub media_type: Option<String>,
+    pub condition: Option<MediaCondition>,
+}
+
+impl MediaQuery {
+    /// what a query that failed to parse turns into
+    pub fn not_all() -> Self {
+        Self {
+            negated: true,
+            media_type: None,
+            condition: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum MediaCondition {
+    Feature(MediaFeature),
+    Not(Box<MediaCondition>),
+    And(Vec<MediaCondition>),
+    Or(Vec<MediaCondition>),
+}
+
+/// `(min-width: 40em)`, `(400px <= width < 700px)` or `(color)`.
+///
+/// `min-`/`max-` prefixes and ranges are normalized into `comparisons`,
+/// always as `<name> <comparison> <value>`.
+/// a boolean feature like `(color)` has no comparisons.
+#[derive(Debug)]
+pub struct MediaFeature {
+    pub name: String,
+    pub comparisons: Vec<(Comparison, Value)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+impl Comparison {
+    /// `a < b` is `b > a`
+    pub fn flip(self) -> Self {
+        match self {
+            Comparison::Lt => Comparison::Gt,
+            Comparison::Le => Comparison::Ge,
+            Comparison::Eq => Comparison::Eq,
+            Comparison::Ge => Comparison::Le,
+            Comparison::Gt => Comparison::Lt,
+        }
+    }
+
+    fn test(self, lhs: f64, rhs: f64) -> bool {
+        match self {
+            Comparison::Lt => lhs < rhs,
+            Comparison::Le => lhs <= rhs,
+            Comparison::Eq => lhs == rhs,
+            Comparison::Ge => lhs >= rhs,
+            Comparison::Gt => lhs > rhs,
+        }
+    }
+}
+
+impl MediaQueryList {
+    pub fn matches(&self, device: &Device, viewport: (u16, u16)) -> bool {
+        self.0.is_empty() || self.0.iter().any(|q| q.matches(device, viewport))
+    }
+}
+
+impl MediaQuery {
+    pub fn matches(&self, device: &Device, viewport: (u16, u16)) -> bool {
+        let type_matches = match self.media_type.as_deref().map(str::to_ascii_lowercase) {
+            None => true,
+            // `screen` matches so that responsive sites work, `tb` can target us specifically
+            Some(t) => matches!(t.as_str(), "all" | "screen" | "tb"),
+        };
+        let condition_matches = self
+            .condition
+            .as_ref()
+            .is_none_or(|c| c.matches(device, viewport));
+
+        (type_matches && condition_matches) != self.negated
+    }
+}
+
+impl MediaCondition {
+    pub fn matches(&self, device: &Device, viewport: (u16, u16)) -> bool {
+        match self {
+            MediaCondition::Feature(feature) => feature.matches(device, viewport),
+            MediaCondition::Not(condition) => !condition.matches(device, viewport),
+            MediaCondition::And(conditions) => {
+                conditions.iter().all(|c| c.matches(device, viewport))
+            }
+            MediaCondition::Or(conditions) => {
+                conditions.iter().any(|c| c.matches(device, viewport))
+            }
+        }
+    }
+}
+
+impl MediaFeature {
+    /// unknown features, and known ones with values of the wrong type, never match
+    pub fn matches(&self, device: &Device, viewport: (u16, u16)) -> bool {
+        let (width, height) = (f64::from(viewport.0), f64::from(viewport.1));
+
+        match self.name.to_ascii_lowercase().as_str() {
+            "width" => self.compare(width, |v| length(v, viewport)),
+            "height" => self.compare(height, |v| length(v, viewport)),
+            "color" => self.compare(device.color_bits.into(), integer),
+            "monochrome" => self.compare(device.monochrome_bits.into(), integer),
+            "orientation" => self.keyword(if height >= width {
+                "portrait"
+            } else {
+                "landscape"
+            }),
+            "prefers-color-scheme" => match &self.comparisons[..] {
+                [] => true,
+                _ => self.keyword(&device.color_scheme.to_string()),
+            },
+            "prefers-reduced-motion" => match (&self.comparisons[..], device.reduced_motion) {
+                ([], reduced) => reduced,
+                (_, true) => self.keyword("reduce"),
+                (_, false) => self.keyword("no-preference"),
+            },
+            _ => false,
+        }
+    }
+
+    /// a boolean test is true for anything but zero
+    fn compare(&self, actual: f64, convert: impl Fn(&Value) -> Option<f64>) -> bool {
+        if self.comparisons.is_empty() {
+            return actual != 0.0;
+        }
+        self.comparisons.iter().all(|(comparison, value)| {
+            convert(value).is_some_and(|expected| comparison.test(actual, expected))
+        })
+    }
+
+    /// for discrete features like `(orientation: portrait)`, which only take `:`
+    fn keyword(&self, actual: &str) -> bool {
+        match &self.comparisons[..] {
+            [(Comparison::Eq, Value::Keyword(k))] => k.eq_ignore_ascii_case(actual),
+            _ => false,
+        }
+    }
+}
+
+/// lengths in media queries are compared in cells, before rounding.
+/// font-relative units use the default font size
+fn length(value: &Value, viewport: (u16, u16)) -> Option<f64> {
+    let font = FontMetrics::default();
+    match value {
+        Value::Dimension(d) if matches!(d.unit, Unit::Unitless) && d.value == 0.0 => Some(0.0),
+        Value::Dimension(d) => d.tb_value(None, viewport, font).ok(),
+        Value::Math(expr) => expr.as_tb(None, viewport, font).ok().map(|d| d.value),
+        _ => None,
+    }
+}
+
+fn integer(value: &Value) -> Option<f64> {
+    match value {
+        Value::Dimension(d) if matches!(d.unit, Unit::Unitless) => Some(d.value),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{
+        computed_style::{Display, LengthAuto},
+        css,
+        render::testing::{computed, dom_with},
+    };
+
+    fn matches(query: &str, device: &Device, viewport: (u16, u16)) -> bool {
+        css::parse_media_query_list(query).matches(device, viewport)
+    }
+
+    #[test]
+    fn evaluation() {
+        let device = Device::default();
+        let monochrome = Device {
+            color_bits: 0,
+            monochrome_bits: 1,
+            color_scheme: ColorScheme::Light,
+            reduced_motion: false,
+        };
+        // 600px is 75 cells
+        let narrow = (60, 40);
+        let wide = (100, 30);
+
+        assert!(matches("", &device, wide));
+        assert!(matches("screen", &device, wide));
+        assert!(matches("only tb", &device, wide));
+        assert!(!matches("print", &device, wide));
+        assert!(matches("not print", &device, wide));
+        assert!(matches("print, tb", &device, wide));
+
+        assert!(matches("(max-width: 600px)", &device, narrow));
+        assert!(!matches("(max-width: 600px)", &device, wide));
+        assert!(matches("screen and (min-width: 75tb)", &device, wide));
+        assert!(matches("(width > 600px)", &device, wide));
+        assert!(matches("(50tb <= width < 75tb)", &device, narrow));
+        assert!(!matches("(50tb <= width < 75tb)", &device, wide));
+        assert!(matches(
+            "(min-width: 40em) and (max-height: 30tb)",
+            &device,
+            wide
+        ));
+        assert!(matches("not (width < 10tb)", &device, wide));
+        assert!(matches("(width < 10tb) or (height > 10tb)", &device, wide));
+
+        assert!(matches("(orientation: landscape)", &device, wide));
+        assert!(!matches("(orientation: portrait)", &device, wide));
+        assert!(matches("(prefers-color-scheme: dark)", &device, wide));
+        assert!(matches("(prefers-color-scheme: light)", &monochrome, wide));
+        assert!(matches("(prefers-reduced-motion: reduce)", &device, wide));
+        assert!(matches(
+            "(prefers-reduced-motion: no-preference)",
+            &monochrome,
+            wide
+        ));
+        assert!(matches("(color)", &device, wide));
+        assert!(matches("(min-color: 8)", &device, wide));
+        assert!(!matches("(color)", &monochrome, wide));
+        assert!(matches("(monochrome)", &monochrome, wide));
+
+        // unknown features and invalid queries never match, but only invalidate their own query
+        assert!(!matches("(hover: hover)", &device, wide));
+        assert!(!matches(
+            "(width < 10tb) and (height > 10tb) or (color)",
+            &device,
+            wide
+        ));
+        assert!(matches("(width: ), tb", &device, wide));
+    }
+
+    #[test]
+    fn media_queries_on_resize() {
+        let mut dom = dom_with(
+            r#"<nav id="sidebar">a</nav>"#,
+            "",
+            r#"
+            #sidebar { display: block; width: 20tb; }
+            @media screen and (max-width: 600px) {
+                #sidebar { display: none; }
+                @media (orientation: portrait) {
+                    #sidebar { width: 100vw; }
+                }
+            }
+            @font-face { font-family: x; }
+            @media print { #sidebar { width: 1tb; } }
+            "#,
+        );
+        assert_eq!(computed(&dom, "#sidebar").display, Display::Block);
+
+        // 600px is 75 cells
+        dom.set_viewport((60, 24)).unwrap();
+        let style = computed(&dom, "#sidebar");
+        assert_eq!(style.display, Display::None);
+        assert_eq!(style.width, LengthAuto::Tb(20.0));
+
+        dom.set_viewport((40, 50)).unwrap();
+        assert_eq!(computed(&dom, "#sidebar").width, LengthAuto::Tb(40.0));
+
+        dom.set_viewport((100, 50)).unwrap();
+        let style = computed(&dom, "#sidebar");
+        assert_eq!(style.display, Display::Block);
+        assert_eq!(style.width, LengthAuto::Tb(20.0));
+    }
+}
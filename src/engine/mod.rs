@@ -1,17 +1,32 @@
 use thiserror::Error;
 
+pub mod bloom;
+pub mod calc;
+pub mod cascade;
+pub mod computed_style;
+pub mod counters;
 pub mod css;
+pub mod custom_properties;
 pub mod dom;
+pub mod generated_content;
 pub mod html;
 pub mod layout;
+pub mod loader;
+pub mod media;
+pub mod render;
+pub mod rule_hash;
+pub mod scroll;
+pub mod shorthands;
 pub mod simply;
 mod style_to_taffy;
 pub mod stylesheet;
+mod table;
+pub mod text;
 
 #[derive(Debug, Error)]
 pub enum Error {
-    #[error("SharedNode error: {0}")]
-    SharedNodeError(#[from] dom::shared_node::Error),
+    #[error("DOM error: {0}")]
+    DomError(#[from] dom::node_ref::Error),
     #[error("HTML parsing error: {0}")]
     HtmlParsingError(#[from] Box<pest::error::Error<html::Rule>>),
     #[error("CSS parsing error: {0}")]
@@ -0,0 +1,204 @@
+use crate::engine::computed_style::BorderStyle;
+
+/// Which characters borders are drawn with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Glyphs {
+    /// box-drawing characters
+    #[default]
+    Unicode,
+    /// `+`, `-` and `|`, for terminals that can't show box-drawing characters
+    Ascii,
+}
+
+impl Glyphs {
+    /// from the locale in `LC_ALL`, `LC_CTYPE` or `LANG`, like most terminal programs do
+    pub fn detect() -> Self {
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match locale.contains("utf-8") || locale.contains("utf8") {
+            true => Glyphs::Unicode,
+            false => Glyphs::Ascii,
+        }
+    }
+
+    /// the track and the thumb of a scrollbar
+    pub fn scrollbar(self, vertical: bool) -> [&'static str; 2] {
+        match (self, vertical) {
+            (Glyphs::Unicode, _) => ["░", "█"],
+            (Glyphs::Ascii, true) => ["|", "#"],
+            (Glyphs::Ascii, false) => ["-", "#"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Weight {
+    Light,
+    Heavy,
+    Double,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dash {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+/// What a side of a border is drawn with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Line {
+    pub weight: Weight,
+    pub dash: Dash,
+}
+
+impl Line {
+    /// `None` if the side has no border.
+    /// borders of 2 cells and more are heavy, the rest of their width is left empty
+    pub fn new(style: BorderStyle, width: f32) -> Option<Self> {
+        if !style.is_drawn() || width < 1.0 {
+            return None;
+        }
+        let weight = match style {
+            BorderStyle::Double => Weight::Double,
+            _ if width >= 2.0 => Weight::Heavy,
+            _ => Weight::Light,
+        };
+        let dash = match style {
+            BorderStyle::Dashed => Dash::Dashed,
+            BorderStyle::Dotted => Dash::Dotted,
+            _ => Dash::Solid,
+        };
+        Some(Self { weight, dash })
+    }
+}
+
+pub const UP: usize = 0;
+pub const DOWN: usize = 1;
+pub const LEFT: usize = 2;
+pub const RIGHT: usize = 3;
+
+/// A cell of a border, and the lines that leave it in each direction (`UP`, `DOWN`, `LEFT` and `RIGHT`).
+///
+/// the lines of neighbouring borders are joined,
+/// so a cell can end up with more of them than the border it was drawn for
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Junction {
+    pub arms: [Option<Weight>; 4],
+    /// only used by straight lines
+    pub dash: Dash,
+    /// only used by light corners
+    pub rounded: bool,
+}
+
+impl Junction {
+    /// adds the lines of `other`, which is drawn over this one
+    pub fn merge(&mut self, other: Junction) {
+        let was_empty = self.arms.iter().all(Option::is_none);
+        for (arm, other) in self.arms.iter_mut().zip(other.arms) {
+            *arm = (*arm).max(other);
+        }
+        self.dash = other.dash;
+        self.rounded = other.rounded && (was_empty || self.rounded);
+    }
+
+    pub fn glyph(&self, glyphs: Glyphs) -> &'static str {
+        let [up, down, left, right] = self.arms.map(|arm| arm.is_some());
+        let vertical = up || down;
+        let horizontal = left || right;
+        if glyphs == Glyphs::Ascii {
+            return match (vertical, horizontal) {
+                (true, true) => "+",
+                (true, false) => "|",
+                (false, true) => "-",
+                (false, false) => " ",
+            };
+        }
+
+        // lines of different weights meet with the heaviest one
+        let weight = self.arms.iter().flatten().max().copied();
+        let weight = weight.unwrap_or(Weight::Light);
+        if !vertical || !horizontal {
+            let straight = match (weight, self.dash) {
+                (Weight::Light, Dash::Solid) => ["─", "│"],
+                (Weight::Light, Dash::Dashed) => ["╌", "╎"],
+                (Weight::Light, Dash::Dotted) => ["┈", "┊"],
+                (Weight::Heavy, Dash::Solid) => ["━", "┃"],
+                (Weight::Heavy, Dash::Dashed) => ["╍", "╏"],
+                (Weight::Heavy, Dash::Dotted) => ["┉", "┋"],
+                (Weight::Double, _) => ["═", "║"],
+            };
+            return straight[vertical as usize];
+        }
+
+        let index = match (up, down, left, right) {
+            (false, true, false, true) => 0,
+            (false, true, true, false) => 1,
+            (true, false, false, true) => 2,
+            (true, false, true, false) => 3,
+            (true, true, false, true) => 4,
+            (true, true, true, false) => 5,
+            (false, true, true, true) => 6,
+            (true, false, true, true) => 7,
+            _ => 8,
+        };
+        if self.rounded && weight == Weight::Light && index < 4 {
+            return ["╭", "╮", "╰", "╯"][index];
+        }
+        let set = match weight {
+            Weight::Light => ["┌", "┐", "└", "┘", "├", "┤", "┬", "┴", "┼"],
+            Weight::Heavy => ["┏", "┓", "┗", "┛", "┣", "┫", "┳", "┻", "╋"],
+            Weight::Double => ["╔", "╗", "╚", "╝", "╠", "╣", "╦", "╩", "╬"],
+        };
+        set[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::render::testing::rendered;
+
+    #[test]
+    fn border_styles() {
+        let boxes = r#"<div id="double">a</div>
+            <div id="rounded">b</div>
+            <div id="dashed">c</div>
+            <div id="heavy">d</div>
+            <div id="mixed">e</div>"#;
+        let css = "div { width: 3tb; }
+            #double { border: double 1tb; }
+            #rounded { border: 1tb solid; border-radius: 1tb; }
+            #dashed { border: 1tb dashed; }
+            #heavy { border: 2tb solid; height: 5tb; }
+            #mixed { border: 1tb solid; border-bottom-style: none; border-left: 1tb dotted; }";
+        assert_eq!(
+            rendered(boxes, css, (8, 19)),
+            [
+                "╔═╗",
+                "║a║",
+                "╚═╝",
+                "╭─╮",
+                "│b│",
+                "╰─╯",
+                "┌╌┐",
+                "╎c╎",
+                "└╌┘",
+                "┏━━┓",
+                "┃  ┃",
+                "┃ d┃",
+                "┃  ┃",
+                "┗━━┛",
+                "┌─┐",
+                "┊e│",
+                "",
+                "",
+                "",
+            ]
+        );
+    }
+}
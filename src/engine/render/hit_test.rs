@@ -0,0 +1,233 @@
+use textwrap::core::display_width;
+
+use crate::engine::{
+    computed_style::Visibility,
+    dom::{Dom, NodeId},
+    layout::{BoxContext, LayoutManager},
+    stylesheet::PseudoElement,
+    text,
+};
+
+use super::{line_offset, paint_order, Paint, Viewport};
+
+/// What's under a cell of the terminal
+#[derive(Debug, Clone)]
+pub struct Hit {
+    /// the node of the box on top: a text node over its text,
+    /// and the element for its own box and for the boxes it generates
+    pub node: NodeId,
+    /// set for the `::marker`, `::before` and `::after` boxes of `node`
+    pub pseudo_element: Option<PseudoElement>,
+    /// where the cell is in the text of the box, if it has some and the cell is on one of its lines.
+    ///
+    /// inline boxes are laid out as blocks, so the offset is in a single text run and not in its paragraph:
+    /// in `<p>a <b>bold</b> c</p>`, `a`, `bold` and `c` are on lines of their own, and each starts at 0.
+    /// the lines are looked up in the text of the run with `text[start..].find(line)`, which falls back
+    /// to the start of the run when the line isn't in it as is, so the offset is wrong
+    /// when `text-transform` or white-space collapsing make a line differ from the text
+    pub text: Option<TextPosition>,
+}
+
+/// A character of a text run, in the text as it's drawn: after white-space processing and `text-transform`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextPosition {
+    /// the line it's on, from the top of the box
+    pub line: usize,
+    /// how many characters come before it in the run.
+    /// past the end of a line, it's the offset right after the line
+    pub offset: usize,
+}
+
+impl LayoutManager {
+    /// The node whose box is on top at the cell (`x`, `y`) of the terminal,
+    /// when the page is drawn in `viewport` (see `render`).
+    ///
+    /// the boxes are tried in the reverse of the painting order, so they stack, scroll and clip
+    /// like they're drawn. boxes that aren't visible, and anonymous ones, let the boxes under them be hit
+    pub fn hit_test(
+        &self,
+        dom: &Dom,
+        viewport: Viewport,
+        x: u16,
+        y: u16,
+    ) -> super::super::Result<Option<Hit>> {
+        if !viewport.area.contains((x, y).into()) {
+            return Ok(None);
+        }
+        let (x, y) = (i32::from(x), i32::from(y));
+        for paint in paint_order(dom, self, viewport, viewport.area)?
+            .into_iter()
+            .rev()
+        {
+            // scrollbars are drawn in the box of their container, which gets hit anyway
+            let Paint::Box(placed) = paint else {
+                continue;
+            };
+            let Some(context) = self.tree.get_node_context(placed.id) else {
+                continue;
+            };
+            let Some(node) = context.node else {
+                continue;
+            };
+            if context.style.visibility != Visibility::Visible
+                || !placed.clip.contains((x as u16, y as u16).into())
+            {
+                continue;
+            }
+
+            let layout = self.tree.layout(placed.id)?;
+            let left = placed.position.0.round() as i32;
+            let top = placed.position.1.round() as i32;
+            let width = layout.size.width.round() as i32;
+            let height = layout.size.height.round() as i32;
+            if !(left..left + width).contains(&x) || !(top..top + height).contains(&y) {
+                continue;
+            }
+
+            let content_left = left + (layout.border.left + layout.padding.left).round() as i32;
+            let content_top = top + (layout.border.top + layout.padding.top).round() as i32;
+            let text = context.text.as_deref().and_then(|text| {
+                text_position(context, text, layout, x - content_left, y - content_top)
+            });
+            return Ok(Some(Hit {
+                node,
+                pseudo_element: self.pseudo_element_of(placed.id),
+                text,
+            }));
+        }
+        Ok(None)
+    }
+}
+
+/// the character of `text` at `column` and `row` of the content box, broken into lines like `paint_box` does
+fn text_position(
+    context: &BoxContext,
+    text: &str,
+    layout: &taffy::Layout,
+    column: i32,
+    row: i32,
+) -> Option<TextPosition> {
+    let content_width = layout.content_box_width().round().max(0.0) as usize;
+    let lines = text::lines(text, Some(content_width), context.style.white_space);
+    let line = usize::try_from(row).ok()?;
+    lines.get(line)?;
+
+    // the lines are the text in order, less the spaces they were broken at.
+    // a line that isn't found is taken to start where the last one did, see `Hit::text`
+    let mut start = 0;
+    for (i, current) in lines.iter().enumerate() {
+        start += text[start..].find(current.as_ref()).unwrap_or(0);
+        if i == line {
+            break;
+        }
+        start += current.len();
+    }
+
+    let current = &lines[line];
+    let column = column - line_offset(context.style.text_align, content_width, current);
+    let mut covered = 0;
+    let mut in_line = current.chars().count();
+    for (i, c) in current.chars().enumerate() {
+        let width = display_width(c.encode_utf8(&mut [0; 4])) as i32;
+        if width > 0 && column < covered + width {
+            in_line = i;
+            break;
+        }
+        covered += width;
+    }
+    Some(TextPosition {
+        line,
+        offset: text[..start].chars().count() + in_line,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::layout::Rect;
+
+    use crate::engine::{dom::NodeType, render::testing::laid_out};
+
+    #[test]
+    fn hit_testing() {
+        let html_input = r#"<p id="text">hello world</p>
+            <div id="box"><span id="on-top">top</span></div>
+            <div id="scroller"><p>a</p><p>b</p><p>c</p></div>
+            <p id="quote">q</p>"#;
+        let css = "#text { width: 5tb; } \
+            #box { padding: 1tb; width: 9tb; } \
+            #on-top { position: relative; z-index: 1; left: -1tb; } \
+            #scroller { height: 2tb; overflow: auto; } \
+            #quote::before { content: '> '; }";
+        let (mut dom, layout) = laid_out(html_input, css, (12, 12));
+        let area = Rect::new(0, 0, 12, 12);
+        let hit =
+            |dom: &Dom, viewport, x, y| layout.hit_test(dom, viewport, x, y).unwrap().unwrap();
+        let id = |dom: &Dom, hit: &Hit| match &dom[hit.node].node_type {
+            NodeType::Element(element) => element.attrs.get("id").cloned().unwrap_or_default(),
+            NodeType::Text(text) => text.clone(),
+            NodeType::Comment(_) => unreachable!(),
+        };
+
+        // "hello" and "world" are on two lines, the `o` of "world" comes after "hello w"
+        let on_text = hit(&dom, Viewport::new(area), 1, 1);
+        assert_eq!(id(&dom, &on_text), "hello world");
+        let offset = TextPosition { line: 1, offset: 7 };
+        assert_eq!(on_text.text, Some(offset));
+        // past the end of the line
+        assert_eq!(hit(&dom, Viewport::new(area), 4, 0).text.unwrap().offset, 4);
+
+        // the padding of `#box`, then the span over it, moved left onto the padding
+        assert_eq!(id(&dom, &hit(&dom, Viewport::new(area), 5, 2)), "box");
+        assert_eq!(hit(&dom, Viewport::new(area), 0, 3).text.unwrap().offset, 0);
+        assert_eq!(id(&dom, &hit(&dom, Viewport::new(area), 0, 3)), "top");
+
+        // `#scroller` shows "a" and "b", and "c" once it's scrolled by a line.
+        // "c" is clipped before that, so the row under it is `#quote`'s
+        let scroller = dom.query_select("#scroller").unwrap().remove(0);
+        assert_eq!(id(&dom, &hit(&dom, Viewport::new(area), 0, 6)), "b");
+        assert_eq!(id(&dom, &hit(&dom, Viewport::new(area), 0, 7)), "quote");
+        dom[scroller].scroll.y = 1.0;
+        assert_eq!(id(&dom, &hit(&dom, Viewport::new(area), 0, 6)), "c");
+
+        // the page scrolled by a line moves everything up
+        let scrolled = Viewport {
+            area,
+            scroll: taffy::Point { x: 0.0, y: 1.0 },
+        };
+        assert_eq!(id(&dom, &hit(&dom, scrolled, 0, 6)), "quote");
+
+        // the generated box belongs to its element
+        let marker = hit(&dom, Viewport::new(area), 0, 7);
+        assert_eq!(marker.pseudo_element, Some(PseudoElement::Before));
+        assert_eq!(marker.text, Some(TextPosition { line: 0, offset: 0 }));
+        assert!(layout
+            .hit_test(&dom, Viewport::new(area), 12, 0)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn offsets_in_inline_content() {
+        let (dom, layout) = laid_out("<p>a <b>bold</b> c</p>", "", (12, 4));
+        let area = Rect::new(0, 0, 12, 4);
+        let hit = |x, y| {
+            layout
+                .hit_test(&dom, Viewport::new(area), x, y)
+                .unwrap()
+                .unwrap()
+        };
+        let text = |hit: &Hit| match &dom[hit.node].node_type {
+            NodeType::Text(text) => text.clone(),
+            _ => unreachable!(),
+        };
+
+        // each text run is on a line of its own, with offsets from its own start
+        assert_eq!(text(&hit(0, 0)), "a ");
+        assert_eq!(hit(1, 0).text, Some(TextPosition { line: 0, offset: 1 }));
+        assert_eq!(text(&hit(2, 1)), "bold");
+        assert_eq!(hit(2, 1).text, Some(TextPosition { line: 0, offset: 2 }));
+        assert_eq!(text(&hit(0, 2)), "c");
+        assert_eq!(hit(0, 2).text, Some(TextPosition { line: 0, offset: 0 }));
+    }
+}
@@ -0,0 +1,375 @@
+use std::collections::HashMap;
+
+use ratatui::{buffer::Buffer, layout::Rect, style::Style};
+use textwrap::core::display_width;
+
+use super::{
+    computed_style::{Length, Overflow, TextAlign, Visibility},
+    dom::Dom,
+    layout::{BoxContext, LayoutManager},
+    scroll, text,
+};
+
+mod borders;
+mod hit_test;
+mod stacking;
+/// helpers for the tests that style, lay out or draw a page, in the modules that take part in it
+#[cfg(test)]
+pub(crate) mod testing;
+
+pub use borders::Glyphs;
+use borders::{Junction, Line, DOWN, LEFT, RIGHT, UP};
+pub use hit_test::{Hit, TextPosition};
+pub use stacking::{paint_order, Paint, Placed};
+
+/// The part of the page that's shown: `area` of the buffer, with the page scrolled by `scroll` cells
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub area: Rect,
+    pub scroll: taffy::Point<f32>,
+}
+
+impl Viewport {
+    pub fn new(area: Rect) -> Self {
+        Self {
+            area,
+            scroll: taffy::Point::ZERO,
+        }
+    }
+}
+
+/// Draws the boxes of a computed layout into `buffer`, in the CSS painting order (see `paint_order`),
+/// with the top left corner of the page at the top left corner of the viewport's area.
+/// nothing is drawn outside of the area, and borders and scrollbars are drawn with `glyphs`.
+///
+/// the content of scroll containers is shifted by the scroll offset of their node,
+/// and fixed boxes stay in place when the page is scrolled
+pub fn render(
+    dom: &Dom,
+    layout: &LayoutManager,
+    viewport: Viewport,
+    glyphs: Glyphs,
+    buffer: &mut Buffer,
+) -> super::Result<()> {
+    let clip = viewport.area.intersection(buffer.area);
+    let mut painter = Painter {
+        clip,
+        buffer,
+        glyphs,
+        junctions: HashMap::new(),
+    };
+    for paint in paint_order(dom, layout, viewport, clip)? {
+        let (Paint::Box(placed) | Paint::Scrollbars(placed)) = paint;
+        let Some(context) = layout.tree.get_node_context(placed.id) else {
+            continue;
+        };
+        // `visibility: hidden` boxes still take up room, and their children can be visible again
+        if context.style.visibility != Visibility::Visible {
+            continue;
+        }
+        let box_layout = layout.tree.layout(placed.id)?;
+        painter.clip = placed.clip;
+        match paint {
+            Paint::Box(_) => paint_box(context, box_layout, placed.position, &mut painter),
+            Paint::Scrollbars(_) => paint_scrollbars(
+                context,
+                box_layout,
+                placed.position,
+                placed.scroll,
+                &mut painter,
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// Draws the scrollbars in the cells that taffy keeps for them, inside the border.
+/// `overflow: scroll` always has them, and `overflow-y: auto` only when the content overflows
+fn paint_scrollbars(
+    context: &BoxContext,
+    layout: &taffy::Layout,
+    position: (f32, f32),
+    offset: taffy::Point<f32>,
+    painter: &mut Painter,
+) {
+    let style = &context.style;
+    let max = scroll::max_offset(layout);
+    let shown = |overflow, max: f32| match overflow {
+        Overflow::Scroll => true,
+        Overflow::Auto => max > 0.0,
+        _ => false,
+    };
+    let bar_style = style.to_ratatui_border_style(None);
+    let x = (position.0 + layout.border.left).round() as i32;
+    let y = (position.1 + layout.border.top).round() as i32;
+    let x_end = (position.0 + layout.size.width - layout.border.right).round() as i32;
+    let y_end = (position.1 + layout.size.height - layout.border.bottom).round() as i32;
+    let bar_width = layout.scrollbar_size.width.round() as i32;
+    let bar_height = layout.scrollbar_size.height.round() as i32;
+
+    if bar_width > 0 && shown(style.overflow_y, max.y) {
+        let track = y_end - bar_height - y;
+        let [track_glyph, thumb_glyph] = painter.glyphs.scrollbar(true);
+        let thumb = thumb(track, max.y, offset.y);
+        for i in 0..track.max(0) {
+            let glyph = if thumb.contains(&i) {
+                thumb_glyph
+            } else {
+                track_glyph
+            };
+            for column in x_end - bar_width..x_end {
+                painter.put(column, y + i, glyph, bar_style);
+            }
+        }
+    }
+    if bar_height > 0 && shown(style.overflow_x, max.x) {
+        let track = x_end - bar_width - x;
+        let [track_glyph, thumb_glyph] = painter.glyphs.scrollbar(false);
+        let thumb = thumb(track, max.x, offset.x);
+        for i in 0..track.max(0) {
+            let glyph = if thumb.contains(&i) {
+                thumb_glyph
+            } else {
+                track_glyph
+            };
+            for row in y_end - bar_height..y_end {
+                painter.put(x + i, row, glyph, bar_style);
+            }
+        }
+    }
+}
+
+/// the cells of a `track` cells long scrollbar that its thumb covers,
+/// when the content is scrolled by `offset` out of `max`
+fn thumb(track: i32, max: f32, offset: f32) -> std::ops::Range<i32> {
+    if track <= 0 {
+        return 0..0;
+    }
+    let visible = track as f32;
+    let length = (visible * visible / (visible + max)).round().max(1.0) as i32;
+    let start = match max > 0.0 {
+        true => ((track - length) as f32 * offset / max).round() as i32,
+        false => 0,
+    };
+    start..start + length
+}
+
+fn paint_box(
+    context: &BoxContext,
+    layout: &taffy::Layout,
+    position: (f32, f32),
+    painter: &mut Painter,
+) {
+    let x = position.0.round() as i32;
+    let y = position.1.round() as i32;
+    let width = layout.size.width.round() as i32;
+    let height = layout.size.height.round() as i32;
+    let style = context.style.to_ratatui_style();
+
+    if context.style.background_color.is_some() {
+        for row in y..y + height {
+            for column in x..x + width {
+                painter.set_style(column, row, style);
+            }
+        }
+    }
+
+    if width > 0 && height > 0 {
+        paint_border(context, layout, (x, y, width, height), painter);
+    }
+
+    if let Some(text) = &context.text {
+        let content_x = x + (layout.border.left + layout.padding.left).round() as i32;
+        let content_y = y + (layout.border.top + layout.padding.top).round() as i32;
+        let content_width = layout.content_box_width().round().max(0.0) as usize;
+
+        let lines = text::lines(text, Some(content_width), context.style.white_space);
+        for (i, line) in lines.iter().enumerate() {
+            let offset = line_offset(context.style.text_align, content_width, line);
+            painter.put_str(content_x + offset, content_y + i as i32, line, style);
+        }
+    }
+}
+
+/// how far from the start of the content box a `line` of text starts
+fn line_offset(text_align: TextAlign, content_width: usize, line: &str) -> i32 {
+    let free = content_width.saturating_sub(display_width(line)) as i32;
+    match text_align {
+        TextAlign::Center => free / 2,
+        TextAlign::Right | TextAlign::End => free,
+        TextAlign::Start | TextAlign::Left | TextAlign::Justify => 0,
+    }
+}
+
+/// Draws the sides of a border that are there. a corner where only one side is drawn
+/// carries that side's line on, so it joins the border of a neighbour that has the other side
+fn paint_border(
+    context: &BoxContext,
+    layout: &taffy::Layout,
+    (x, y, width, height): (i32, i32, i32, i32),
+    painter: &mut Painter,
+) {
+    let style = &context.style;
+    let line = |border_style, width| Line::new(border_style, width);
+    let top = line(style.border_style.top, layout.border.top);
+    let right = line(style.border_style.right, layout.border.right);
+    let bottom = line(style.border_style.bottom, layout.border.bottom);
+    let left = line(style.border_style.left, layout.border.left);
+    let rounded = match &style.border_radius {
+        Length::Tb(radius) => *radius > 0.0,
+        Length::Percent(fraction) => *fraction > 0.0,
+        Length::Calc(calc) => calc.resolve(layout.size.width.min(layout.size.height)) > 0.0,
+    };
+
+    let (x_end, y_end) = (x + width - 1, y + height - 1);
+    let straight = |line: Option<Line>, arms: [usize; 2]| {
+        let mut junction = Junction::default();
+        if let Some(line) = line {
+            junction.dash = line.dash;
+            for arm in arms {
+                junction.arms[arm] = Some(line.weight);
+            }
+        }
+        junction
+    };
+    let corner = |horizontal: Option<Line>, vertical: Option<Line>, arms: [usize; 2]| match (
+        horizontal, vertical,
+    ) {
+        (Some(_), None) => straight(horizontal, [LEFT, RIGHT]),
+        (None, Some(_)) => straight(vertical, [UP, DOWN]),
+        _ => Junction {
+            arms: [UP, DOWN, LEFT, RIGHT].map(|arm| match arm {
+                UP | DOWN if arms.contains(&arm) => vertical.map(|line| line.weight),
+                LEFT | RIGHT if arms.contains(&arm) => horizontal.map(|line| line.weight),
+                _ => None,
+            }),
+            rounded,
+            ..Default::default()
+        },
+    };
+
+    let sides = [
+        (top, style.border_color.top),
+        (right, style.border_color.right),
+        (bottom, style.border_color.bottom),
+        (left, style.border_color.left),
+    ]
+    .map(|(line, color)| (line, style.to_ratatui_border_style(color)));
+    let [top, right, bottom, left] = sides;
+    for column in x + 1..x_end {
+        painter.put_border(column, y, straight(top.0, [LEFT, RIGHT]), top.1);
+        painter.put_border(column, y_end, straight(bottom.0, [LEFT, RIGHT]), bottom.1);
+    }
+    for row in y + 1..y_end {
+        painter.put_border(x, row, straight(left.0, [UP, DOWN]), left.1);
+        painter.put_border(x_end, row, straight(right.0, [UP, DOWN]), right.1);
+    }
+    // the corners take the color of the horizontal side
+    let corners = [
+        (x, y, corner(top.0, left.0, [DOWN, RIGHT]), top.1),
+        (x_end, y, corner(top.0, right.0, [DOWN, LEFT]), top.1),
+        (x, y_end, corner(bottom.0, left.0, [UP, RIGHT]), bottom.1),
+        (
+            x_end,
+            y_end,
+            corner(bottom.0, right.0, [UP, LEFT]),
+            bottom.1,
+        ),
+    ];
+    for (column, row, junction, style) in corners {
+        painter.put_border(column, row, junction, style);
+    }
+}
+
+/// Draws into the cells of a buffer that are inside `clip`, and ignores the others
+struct Painter<'a> {
+    buffer: &'a mut Buffer,
+    clip: Rect,
+    glyphs: Glyphs,
+    /// the border lines drawn so far, so that the borders of neighbouring boxes join
+    junctions: HashMap<(i32, i32), Junction>,
+}
+
+impl Painter<'_> {
+    fn cell(&mut self, x: i32, y: i32) -> Option<&mut ratatui::buffer::Cell> {
+        let (x, y) = (u16::try_from(x).ok()?, u16::try_from(y).ok()?);
+        if !self.clip.contains((x, y).into()) {
+            return None;
+        }
+        self.buffer.cell_mut((x, y))
+    }
+
+    fn set_style(&mut self, x: i32, y: i32, style: Style) {
+        if let Some(cell) = self.cell(x, y) {
+            cell.set_style(style);
+        }
+    }
+
+    fn put(&mut self, x: i32, y: i32, symbol: &str, style: Style) {
+        if let Some(cell) = self.cell(x, y) {
+            cell.set_symbol(symbol).set_style(style);
+            self.junctions.remove(&(x, y));
+        }
+    }
+
+    /// draws the lines of `junction` over the border already in the cell, if there's one.
+    /// lines that run into a neighbouring border are joined to it
+    fn put_border(&mut self, x: i32, y: i32, mut junction: Junction, style: Style) {
+        if junction.arms.iter().all(Option::is_none) || self.cell(x, y).is_none() {
+            return;
+        }
+        if let Some(existing) = self.junctions.get(&(x, y)) {
+            let mut existing = *existing;
+            existing.merge(junction);
+            junction = existing;
+        }
+
+        let neighbours = [
+            (UP, 0, -1, DOWN),
+            (DOWN, 0, 1, UP),
+            (LEFT, -1, 0, RIGHT),
+            (RIGHT, 1, 0, LEFT),
+        ];
+        for (arm, dx, dy, opposite) in neighbours {
+            let position = (x + dx, y + dy);
+            let Some(neighbour) = self.junctions.get_mut(&position) else {
+                continue;
+            };
+            match (junction.arms[arm], neighbour.arms[opposite]) {
+                (Some(weight), None) => {
+                    neighbour.arms[opposite] = Some(weight);
+                    let glyph = neighbour.glyph(self.glyphs);
+                    if let Some(cell) = self.cell(position.0, position.1) {
+                        cell.set_symbol(glyph);
+                    }
+                }
+                (None, Some(weight)) => junction.arms[arm] = Some(weight),
+                _ => {}
+            }
+        }
+
+        let glyph = junction.glyph(self.glyphs);
+        if let Some(cell) = self.cell(x, y) {
+            cell.set_symbol(glyph).set_style(style);
+        }
+        self.junctions.insert((x, y), junction);
+    }
+
+    fn put_str(&mut self, x: i32, y: i32, text: &str, style: Style) {
+        let mut x = x;
+        let mut symbol = [0; 4];
+        for c in text.chars() {
+            let symbol = c.encode_utf8(&mut symbol);
+            let width = display_width(symbol) as i32;
+            if width == 0 {
+                continue;
+            }
+            self.put(x, y, symbol, style);
+            // the cells that a wide character covers are left empty
+            for covered in x + 1..x + width {
+                self.put(covered, y, "", style);
+            }
+            x += width;
+        }
+    }
+}
@@ -0,0 +1,409 @@
+use ratatui::layout::Rect;
+
+use crate::engine::{
+    computed_style::{ComputedStyle, LengthAuto, Overflow, Position},
+    dom::Dom,
+    layout::{BoxContext, LayoutManager, LayoutTree},
+    scroll,
+};
+
+use super::Viewport;
+
+/// A box, and where it ends up in the buffer
+#[derive(Debug, Clone, Copy)]
+pub struct Placed {
+    pub id: taffy::NodeId,
+    /// the top left corner of its border box
+    pub position: (f32, f32),
+    /// the cells it can be drawn in: the viewport, less what the boxes around it clip
+    pub clip: Rect,
+    /// how far its content is scrolled, if it's a scroll container
+    pub scroll: taffy::Point<f32>,
+}
+
+/// What's painted of a box at a step of the painting order
+#[derive(Debug, Clone, Copy)]
+pub enum Paint {
+    /// its background, border and text
+    Box(Placed),
+    /// the scrollbars of a scroll container, over its content
+    Scrollbars(Placed),
+}
+
+/// The boxes in the order they're painted in, the last one being on top.
+///
+/// the order is the one of CSS 2 appendix E: for each stacking context, its own box,
+/// then the stacking contexts inside it with a negative `z-index`, its block-level boxes,
+/// its inline-level boxes and text, the positioned boxes with `z-index: auto` or `0` in tree order,
+/// and last the stacking contexts with a positive `z-index`
+pub fn paint_order(
+    dom: &Dom,
+    layout: &LayoutManager,
+    viewport: Viewport,
+    clip: Rect,
+) -> super::super::Result<Vec<Paint>> {
+    let root = layout.root.ok_or(super::super::Error::LayoutRootNodeNone)?;
+    let mut walker = Walker {
+        dom,
+        tree: &layout.tree,
+        layers: vec![Layer::default()],
+    };
+    let area = taffy::Rect {
+        left: viewport.area.x as f32,
+        right: viewport.area.right() as f32,
+        top: viewport.area.y as f32,
+        bottom: viewport.area.bottom() as f32,
+    };
+    for child in layout.tree.children(root)? {
+        // fixed boxes stay where they are when the page is scrolled
+        let fixed = layout
+            .tree
+            .get_node_context(child)
+            .is_some_and(|context| context.style.position == Position::Fixed);
+        let scroll = match fixed {
+            true => taffy::Point::ZERO,
+            false => viewport.scroll,
+        };
+        let around = Around {
+            origin: (area.left - scroll.x, area.top - scroll.y),
+            clip,
+            scrollport: area,
+            containing: area,
+        };
+        walker.walk(child, around, 0, 0)?;
+    }
+
+    let mut order = vec![];
+    walker.flatten(0, &mut order);
+    Ok(order)
+}
+
+/// What a box is positioned and clipped by, in the coordinates of the buffer
+#[derive(Debug, Clone, Copy)]
+struct Around {
+    /// the position of the parent's box, which taffy's locations are relative to,
+    /// shifted by how far the parent is scrolled
+    origin: (f32, f32),
+    clip: Rect,
+    /// the padding box of the nearest scroll container, or the viewport, which sticky boxes stick to
+    scrollport: taffy::Rect<f32>,
+    /// the content box of the parent, which sticky boxes don't leave
+    containing: taffy::Rect<f32>,
+}
+
+/// A stacking context, or a positioned box with `z-index: auto`,
+/// which is painted like one but whose positioned descendants belong to the stacking context around it.
+/// the other layers are indices into `Walker::layers`
+#[derive(Debug, Default)]
+struct Layer {
+    /// `None` for the root stacking context
+    root: Option<Placed>,
+    negative: Vec<(i32, usize)>,
+    blocks: Vec<Placed>,
+    inlines: Vec<Placed>,
+    scrollbars: Vec<Placed>,
+    /// the layers with `z-index: auto` or `0`, in tree order
+    positioned: Vec<usize>,
+    positive: Vec<(i32, usize)>,
+}
+
+enum Stacking {
+    /// painted in the layer of its parent
+    None,
+    /// a positioned box with `z-index: auto`
+    Pseudo,
+    Context(i32),
+}
+
+impl Stacking {
+    fn of(style: &ComputedStyle) -> Self {
+        match (style.position, style.z_index) {
+            (Position::Static, _) => Stacking::None,
+            (Position::Fixed | Position::Sticky, z_index) => {
+                Stacking::Context(z_index.unwrap_or(0))
+            }
+            (_, Some(z_index)) => Stacking::Context(z_index),
+            (_, None) => Stacking::Pseudo,
+        }
+    }
+}
+
+struct Walker<'a> {
+    dom: &'a Dom,
+    tree: &'a LayoutTree,
+    layers: Vec<Layer>,
+}
+
+impl Walker<'_> {
+    /// places the box `id` and its descendants in the layers:
+    /// positioned ones in the stacking context `context`, the others in `layer`
+    fn walk(
+        &mut self,
+        id: taffy::NodeId,
+        around: Around,
+        context: usize,
+        layer: usize,
+    ) -> super::super::Result<()> {
+        let layout = self.tree.layout(id)?;
+        let mut position = (
+            around.origin.0 + layout.location.x,
+            around.origin.1 + layout.location.y,
+        );
+
+        // boxes that only exist for layout aren't painted, but their children are
+        let Some(box_context) = self.tree.get_node_context(id) else {
+            let inner = Around {
+                origin: position,
+                containing: content_box(layout, position),
+                ..around
+            };
+            for child in self.tree.children(id)? {
+                self.walk(child, inner, context, layer)?;
+            }
+            return Ok(());
+        };
+        let style = &box_context.style;
+        if style.position == Position::Sticky {
+            position = stick(position, layout, style, &around);
+        }
+
+        let clips = style.overflow_x != Overflow::Visible || style.overflow_y != Overflow::Visible;
+        let scroll = match (box_context.node, clips) {
+            (Some(node), true) => scroll::clamp(self.dom[node].scroll, style, layout),
+            _ => taffy::Point::ZERO,
+        };
+        let placed = Placed {
+            id,
+            position,
+            clip: around.clip,
+            scroll,
+        };
+
+        let (context, layer) = match Stacking::of(style) {
+            Stacking::None => {
+                let painted = &mut self.layers[layer];
+                match box_context.text.is_some() || style.display.is_inline_level() {
+                    true => painted.inlines.push(placed),
+                    false => painted.blocks.push(placed),
+                }
+                (context, layer)
+            }
+            Stacking::Pseudo => {
+                let own = self.new_layer(placed);
+                self.layers[context].positioned.push(own);
+                (context, own)
+            }
+            Stacking::Context(z_index) => {
+                let own = self.new_layer(placed);
+                let parent = &mut self.layers[context];
+                match z_index {
+                    ..0 => parent.negative.push((z_index, own)),
+                    0 => parent.positioned.push(own),
+                    1.. => parent.positive.push((z_index, own)),
+                }
+                (own, own)
+            }
+        };
+
+        let scrolled = (position.0 - scroll.x, position.1 - scroll.y);
+        let inner = match clips {
+            true => {
+                let padding = padding_box(layout, position);
+                Around {
+                    origin: scrolled,
+                    clip: content_clip(box_context, padding, around.clip),
+                    scrollport: padding,
+                    containing: content_box(layout, scrolled),
+                }
+            }
+            false => Around {
+                origin: scrolled,
+                containing: content_box(layout, scrolled),
+                ..around
+            },
+        };
+        for child in self.tree.children(id)? {
+            self.walk(child, inner, context, layer)?;
+        }
+        if clips {
+            self.layers[layer].scrollbars.push(placed);
+        }
+        Ok(())
+    }
+
+    fn new_layer(&mut self, root: Placed) -> usize {
+        self.layers.push(Layer {
+            root: Some(root),
+            ..Default::default()
+        });
+        self.layers.len() - 1
+    }
+
+    fn flatten(&self, layer: usize, order: &mut Vec<Paint>) {
+        let layer = &self.layers[layer];
+        let by_z_index = |layers: &[(i32, usize)]| {
+            let mut layers = layers.to_vec();
+            // stable, so equal `z-index`es stay in tree order
+            layers.sort_by_key(|(z_index, _)| *z_index);
+            layers.into_iter().map(|(_, layer)| layer)
+        };
+
+        order.extend(layer.root.map(Paint::Box));
+        for negative in by_z_index(&layer.negative) {
+            self.flatten(negative, order);
+        }
+        order.extend(layer.blocks.iter().copied().map(Paint::Box));
+        order.extend(layer.inlines.iter().copied().map(Paint::Box));
+        order.extend(layer.scrollbars.iter().copied().map(Paint::Scrollbars));
+        for &positioned in &layer.positioned {
+            self.flatten(positioned, order);
+        }
+        for positive in by_z_index(&layer.positive) {
+            self.flatten(positive, order);
+        }
+    }
+}
+
+/// moves a sticky box as far as it takes to keep it inside the scrollport by its insets,
+/// without leaving its containing block
+fn stick(
+    position: (f32, f32),
+    layout: &taffy::Layout,
+    style: &ComputedStyle,
+    around: &Around,
+) -> (f32, f32) {
+    let (port, containing) = (around.scrollport, around.containing);
+    let inset = |inset: &LengthAuto, basis: f32| match inset {
+        LengthAuto::Auto => None,
+        LengthAuto::Tb(inset) => Some(*inset),
+        LengthAuto::Percent(fraction) => Some(fraction * basis),
+        LengthAuto::Calc(calc) => Some(calc.resolve(basis)),
+    };
+    let (width, height) = (layout.size.width, layout.size.height);
+    let (port_width, port_height) = (port.right - port.left, port.bottom - port.top);
+
+    let (mut x, mut y) = position;
+    if let Some(top) = inset(&style.inset.top, port_height) {
+        y = y.max((port.top + top).min(containing.bottom - height));
+    }
+    if let Some(bottom) = inset(&style.inset.bottom, port_height) {
+        y = y.min((port.bottom - bottom - height).max(containing.top));
+    }
+    if let Some(left) = inset(&style.inset.left, port_width) {
+        x = x.max((port.left + left).min(containing.right - width));
+    }
+    if let Some(right) = inset(&style.inset.right, port_width) {
+        x = x.min((port.right - right - width).max(containing.left));
+    }
+    (x, y)
+}
+
+fn padding_box(layout: &taffy::Layout, position: (f32, f32)) -> taffy::Rect<f32> {
+    taffy::Rect {
+        left: position.0 + layout.border.left,
+        right: position.0 + layout.size.width - layout.border.right - layout.scrollbar_size.width,
+        top: position.1 + layout.border.top,
+        bottom: position.1 + layout.size.height
+            - layout.border.bottom
+            - layout.scrollbar_size.height,
+    }
+}
+
+fn content_box(layout: &taffy::Layout, position: (f32, f32)) -> taffy::Rect<f32> {
+    let padding = padding_box(layout, position);
+    taffy::Rect {
+        left: padding.left + layout.padding.left,
+        right: padding.right - layout.padding.right,
+        top: padding.top + layout.padding.top,
+        bottom: padding.bottom - layout.padding.bottom,
+    }
+}
+
+/// the part of `clip` that the content of a box that doesn't let it overflow can be drawn in:
+/// its padding box, less the scrollbars, on the axes that aren't `overflow: visible`
+fn content_clip(context: &BoxContext, padding: taffy::Rect<f32>, clip: Rect) -> Rect {
+    let axis = |overflow, start: f32, end: f32, clip: (u16, u16)| {
+        if overflow == Overflow::Visible {
+            return clip;
+        }
+        let (clip_start, clip_end) = (i32::from(clip.0), i32::from(clip.1));
+        let start = (start.round() as i32).clamp(clip_start, clip_end);
+        let end = (end.round() as i32).clamp(start, clip_end);
+        (start as u16, end as u16)
+    };
+    let style = &context.style;
+    let (x, x_end) = axis(
+        style.overflow_x,
+        padding.left,
+        padding.right,
+        (clip.x, clip.right()),
+    );
+    let (y, y_end) = axis(
+        style.overflow_y,
+        padding.top,
+        padding.bottom,
+        (clip.y, clip.bottom()),
+    );
+    Rect::new(x, y, x_end - x, y_end - y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::buffer::Buffer;
+
+    use crate::engine::{
+        css,
+        render::{
+            render,
+            testing::{draw, laid_out, layout_of},
+            Glyphs,
+        },
+        stylesheet::Origin,
+    };
+
+    #[test]
+    fn positioning() {
+        let page = r#"<div id="banner">cookies</div>
+            <p id="behind">xxxxx</p><p>one</p><p>two</p><p>three</p>"#;
+        let css = "#banner { position: fixed; bottom: 0; left: 0; z-index: 1; }
+            #behind { position: relative; top: 1tb; z-index: -1; }";
+        let (mut dom, layout) = laid_out(page, css, (8, 3));
+        let draw_scrolled = |dom: &Dom, layout: &LayoutManager, y| {
+            let mut viewport = Viewport::new(Rect::new(0, 0, 8, 3));
+            viewport.scroll.y = y;
+            let mut buffer = Buffer::empty(viewport.area);
+            render(dom, layout, viewport, Glyphs::Unicode, &mut buffer).unwrap();
+            let rows = (0..3).map(|y| (0..8).map(|x| buffer[(x, y)].symbol()).collect());
+            rows.map(|row: String| row.trim_end().to_string())
+                .collect::<Vec<_>>()
+        };
+        // the text of the page covers a negative `z-index`, and the fixed banner covers the page
+        assert_eq!(draw_scrolled(&dom, &layout, 0.0), ["", "onexx", "cookies"]);
+        assert_eq!(
+            draw_scrolled(&dom, &layout, 2.0),
+            ["two", "three", "cookies"]
+        );
+
+        // the reader can hide it
+        dom.stylesheets.push(
+            css::parse_from_str("#banner { display: none !important; }", Origin::User).unwrap(),
+        );
+        dom.refresh_styles().unwrap();
+        let layout = layout_of(&mut dom, (8, 3));
+        assert_eq!(draw_scrolled(&dom, &layout, 2.0), ["two", "three", ""]);
+
+        // sticky headers stay at the top of what's scrolled, as long as their table is there
+        let table = r#"<div id="scroller"><table>
+            <tr><th>name</th></tr><tr><td>a</td></tr><tr><td>b</td></tr><tr><td>c</td></tr>
+        </table></div>"#;
+        let css = "#scroller { height: 2tb; overflow-y: scroll; scrollbar-width: none; }
+            th { position: sticky; top: 0; }";
+        let (mut dom, layout) = laid_out(table, css, (8, 2));
+        assert_eq!(draw(&dom, &layout, (8, 2), Glyphs::Unicode), ["name", "a"]);
+        let scroller = dom.query_select("#scroller").unwrap().remove(0);
+        let delta = taffy::Point { x: 0.0, y: 5.0 };
+        scroll::scroll_by(&mut dom, &layout, scroller, delta).unwrap();
+        assert_eq!(draw(&dom, &layout, (8, 2), Glyphs::Unicode), ["name", "c"]);
+    }
+}
@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use ratatui::{buffer::Buffer, layout::Rect};
+
+use crate::engine::{
+    computed_style::ComputedStyle,
+    css,
+    dom::{Computed as _, Dom},
+    html,
+    layout::LayoutManager,
+    stylesheet::Origin,
+};
+
+use super::{render, Glyphs, Viewport};
+
+/// the rows of the buffer that `html` is drawn into, without trailing spaces
+pub(crate) fn rendered(html_input: &str, author_css: &str, size: (u16, u16)) -> Vec<String> {
+    rendered_with(html_input, author_css, size, Glyphs::Unicode)
+}
+
+pub(crate) fn rendered_with(
+    html_input: &str,
+    author_css: &str,
+    size: (u16, u16),
+    glyphs: Glyphs,
+) -> Vec<String> {
+    let (dom, layout) = laid_out(html_input, author_css, size);
+    draw(&dom, &layout, size, glyphs)
+}
+
+pub(crate) fn laid_out(
+    html_input: &str,
+    author_css: &str,
+    size: (u16, u16),
+) -> (Dom, LayoutManager) {
+    let mut dom = dom_with(html_input, "", author_css);
+    let layout = layout_of(&mut dom, size);
+    (dom, layout)
+}
+
+/// `html` with its styles computed from the agent and author stylesheets
+pub(crate) fn dom_with(html_input: &str, agent_css: &str, author_css: &str) -> Dom {
+    let mut dom = html::parse_from_str(html_input).unwrap();
+    dom.stylesheets
+        .push(css::parse_from_str(agent_css, Origin::Agent).unwrap());
+    dom.stylesheets
+        .push(css::parse_from_str(author_css, Origin::Author).unwrap());
+    dom.refresh_styles().unwrap();
+    dom
+}
+
+/// the computed style of the first node that matches `query`
+pub(crate) fn computed(dom: &Dom, query: &str) -> Arc<ComputedStyle> {
+    dom.node(dom.query_select(query).unwrap()[0])
+        .computed_style()
+}
+
+pub(crate) fn layout_of(dom: &mut Dom, size: (u16, u16)) -> LayoutManager {
+    let mut layout = LayoutManager::new();
+    layout.build(dom).unwrap();
+    layout
+        .compute(taffy::Size {
+            width: taffy::AvailableSpace::Definite(size.0.into()),
+            height: taffy::AvailableSpace::Definite(size.1.into()),
+        })
+        .unwrap();
+    layout
+}
+
+pub(crate) fn draw(
+    dom: &Dom,
+    layout: &LayoutManager,
+    size: (u16, u16),
+    glyphs: Glyphs,
+) -> Vec<String> {
+    let area = Rect::new(0, 0, size.0, size.1);
+    let mut buffer = Buffer::empty(area);
+    render(dom, layout, Viewport::new(area), glyphs, &mut buffer).unwrap();
+    (0..size.1)
+        .map(|y| {
+            let row: String = (0..size.0).map(|x| buffer[(x, y)].symbol()).collect();
+            row.trim_end().to_string()
+        })
+        .collect()
+}
@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use super::{
+    bloom::{self, AncestorBloom},
+    dom::ElementData,
+    media::Device,
+    stylesheet::{self, Combinator, ComplexSelector, Origin, Rule, Stylesheet},
+};
+
+/// A rule from one of the stylesheets, along with what's needed to apply it
+#[derive(Debug)]
+pub struct IndexedRule<'a> {
+    pub rule: &'a Rule,
+    pub origin: Origin,
+    /// position of the rule across all stylesheets, see `AppliedStyle::source_order`
+    pub source_order: usize,
+    /// see `AppliedStyle::layer_order`
+    pub layer_order: usize,
+    /// hashes of the ids, classes and tags that must be present on some ancestor
+    /// for this rule to be able to match
+    pub ancestor_hashes: Vec<u32>,
+}
+
+/// Buckets every rule by the rightmost compound selector's id, class or tag,
+/// so that finding the rules that could apply to an element doesn't need
+/// to look at every rule.
+///
+/// Each rule is put in exactly one bucket, picking the most selective one: id > class > tag.
+/// Rules that have none of those (eg. `*`) end up in `universal`.
+#[derive(Debug, Default)]
+pub struct RuleHash<'a> {
+    by_id: HashMap<&'a str, Vec<IndexedRule<'a>>>,
+    by_class: HashMap<&'a str, Vec<IndexedRule<'a>>>,
+    by_tag: HashMap<&'a str, Vec<IndexedRule<'a>>>,
+    universal: Vec<IndexedRule<'a>>,
+}
+
+impl<'a> RuleHash<'a> {
+    /// indexes the rules whose `@media` conditions match `device` and `viewport`
+    pub fn new(stylesheets: &'a [Stylesheet], device: &Device, viewport: (u16, u16)) -> Self {
+        let mut rule_hash = Self::default();
+        let layer_orders = stylesheet::layer_orders(stylesheets);
+        let rules = stylesheets
+            .iter()
+            .zip(&layer_orders)
+            .flat_map(|(sheet, layers)| {
+                sheet
+                    .rules
+                    .iter()
+                    .map(move |rule| (sheet.origin, layers[rule.layer], rule))
+            });
+        for (source_order, (origin, layer_order, rule)) in rules.enumerate() {
+            // skipped rules still count for the `source_order` of the others
+            if !rule.media_matches(device, viewport) {
+                continue;
+            }
+            rule_hash.insert(IndexedRule {
+                rule,
+                origin,
+                source_order,
+                layer_order,
+                ancestor_hashes: ancestor_hashes(&rule.selector),
+            });
+        }
+        rule_hash
+    }
+
+    fn insert(&mut self, indexed: IndexedRule<'a>) {
+        let Some(rightmost) = indexed.rule.selector.inner.last() else {
+            return;
+        };
+
+        if let Some(id) = &rightmost.id {
+            self.by_id.entry(id).or_default().push(indexed);
+        } else if let Some(class) = rightmost.classes.first() {
+            self.by_class.entry(class).or_default().push(indexed);
+        } else if let Some(tag) = &rightmost.tag_name {
+            self.by_tag.entry(tag).or_default().push(indexed);
+        } else {
+            self.universal.push(indexed);
+        }
+    }
+
+    /// returns the rules that *could* match `element`, in declaration order.
+    /// the ones that certainly can't match given the ancestors in `bloom` are already filtered out.
+    ///
+    /// the full selector still needs to be checked against the node.
+    pub fn candidates(
+        &self,
+        element: &ElementData,
+        bloom: &AncestorBloom,
+    ) -> Vec<&IndexedRule<'a>> {
+        let mut candidates = vec![];
+
+        if let Some(rules) = element.id().and_then(|id| self.by_id.get(id.as_str())) {
+            candidates.extend(rules);
+        }
+        for class in element.classes() {
+            if let Some(rules) = self.by_class.get(class) {
+                candidates.extend(rules);
+            }
+        }
+        if let Some(rules) = self.by_tag.get(element.tag.as_str()) {
+            candidates.extend(rules);
+        }
+        candidates.extend(&self.universal);
+
+        candidates.retain(|rule| {
+            rule.ancestor_hashes
+                .iter()
+                .all(|hash| bloom.might_contain_hash(*hash))
+        });
+        candidates.sort_by_key(|rule| rule.source_order);
+        candidates
+    }
+}
+
+/// collects the hashes of every compound selector that has to match an ancestor.
+///
+/// walking right to left, a compound on the left of a descendant or child combinator
+/// is an ancestor of the compound on its right.
+/// compounds on the left of a sibling combinator are not, so they are skipped
+/// (though their own ancestors still are ancestors of the subject)
+fn ancestor_hashes(selector: &ComplexSelector) -> Vec<u32> {
+    let mut hashes = vec![];
+
+    for (compound, combinator) in selector.inner.iter().zip(&selector.combinators) {
+        if !matches!(combinator, Combinator::Descendant | Combinator::Child) {
+            continue;
+        }
+
+        if let Some(id) = &compound.id {
+            hashes.push(bloom::hash_id(id));
+        }
+        hashes.extend(
+            compound
+                .classes
+                .iter()
+                .map(|class| bloom::hash_class(class)),
+        );
+        if let Some(tag) = &compound.tag_name {
+            hashes.push(bloom::hash_tag(tag));
+        }
+    }
+
+    hashes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::engine::css;
+
+    #[test]
+    fn bucketing_and_order() {
+        let sheet = css::parse_from_str(
+            r#"
+            p { color: red; }
+            #title { color: red; }
+            .a.b { color: red; }
+            * { color: red; }
+            div p.a { color: red; }
+            h1 + .b { color: red; }
+            "#,
+            Origin::Author,
+        )
+        .unwrap();
+        let sheets = [sheet];
+        let rule_hash = RuleHash::new(&sheets, &Device::default(), (80, 24));
+
+        let p = ElementData::new("p", Some(HashMap::from([("class".into(), "a b".into())])));
+
+        // no ancestors yet, so `div p.a` must be rejected by the bloom filter
+        let mut bloom = AncestorBloom::new();
+        let orders: Vec<_> = rule_hash
+            .candidates(&p, &bloom)
+            .iter()
+            .map(|r| r.source_order)
+            .collect();
+        assert_eq!(orders, vec![0, 2, 3, 5]);
+
+        bloom.push_element(&ElementData::new("div", None));
+        let orders: Vec<_> = rule_hash
+            .candidates(&p, &bloom)
+            .iter()
+            .map(|r| r.source_order)
+            .collect();
+        assert_eq!(orders, vec![0, 2, 3, 4, 5]);
+    }
+}
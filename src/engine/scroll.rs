@@ -0,0 +1,193 @@
+use taffy::Point;
+
+use super::{
+    computed_style::{ComputedStyle, Overflow},
+    dom::{Dom, NodeId},
+    layout::LayoutManager,
+};
+
+/// how far the content of a box can be scrolled on each axis, in cells
+pub fn max_offset(layout: &taffy::Layout) -> Point<f32> {
+    Point {
+        x: layout.scroll_width().round(),
+        y: layout.scroll_height().round(),
+    }
+}
+
+/// the scroll offset of a box with the `style` and `layout`, in whole cells:
+/// none on the axes that don't scroll, and no further than the end of the content
+pub fn clamp(offset: Point<f32>, style: &ComputedStyle, layout: &taffy::Layout) -> Point<f32> {
+    let max = max_offset(layout);
+    let axis = |offset: f32, max: f32, overflow: Overflow| match overflow.is_scroll_container() {
+        true => offset.round().clamp(0.0, max),
+        false => 0.0,
+    };
+    Point {
+        x: axis(offset.x, max.x, style.overflow_x),
+        y: axis(offset.y, max.y, style.overflow_y),
+    }
+}
+
+/// whether the user can scroll on an axis with this `overflow`, rather than only programmatically
+fn user_scrollable(overflow: Overflow, max: f32) -> bool {
+    matches!(overflow, Overflow::Scroll | Overflow::Auto) && max > 0.0
+}
+
+/// The boxes whose content overflows and that the user can scroll, in tree order.
+/// the page itself isn't one of them: it's scrolled by whoever draws it
+pub fn scroll_containers(layout: &LayoutManager) -> super::Result<Vec<NodeId>> {
+    let mut containers = vec![];
+    let mut stack = Vec::from_iter(layout.root);
+    while let Some(id) = stack.pop() {
+        if let Some(context) = layout.tree.get_node_context(id) {
+            let max = max_offset(layout.tree.layout(id)?);
+            let style = &context.style;
+            if user_scrollable(style.overflow_x, max.x) || user_scrollable(style.overflow_y, max.y)
+            {
+                containers.extend(context.node);
+            }
+        }
+        stack.extend(layout.tree.children(id)?.into_iter().rev());
+    }
+    Ok(containers)
+}
+
+/// Scrolls the box of `node` by `delta` cells, within what it can scroll.
+///
+/// returns the part of `delta` that's left, so that it can scroll whatever is around the box,
+/// like the page does when a code block is scrolled to its end
+pub fn scroll_by(
+    dom: &mut Dom,
+    layout: &LayoutManager,
+    node: NodeId,
+    delta: Point<f32>,
+) -> super::Result<Point<f32>> {
+    let id = layout
+        .get_node_id(node)
+        .ok_or(super::Error::LayoutNodeNotFound)?;
+    let context = layout.tree.get_node_context(id);
+    let Some(style) = context.map(|context| &context.style) else {
+        return Ok(delta);
+    };
+    let box_layout = layout.tree.layout(id)?;
+
+    let w = &mut dom[node];
+    let current = clamp(w.scroll, style, box_layout);
+    let wanted = Point {
+        x: current.x + delta.x,
+        y: current.y + delta.y,
+    };
+    w.scroll = clamp(wanted, style, box_layout);
+    Ok(Point {
+        x: wanted.x - w.scroll.x,
+        y: wanted.y - w.scroll.y,
+    })
+}
+
+/// Which scroll container the keyboard scrolls, independently of the page.
+/// with none focused, every key scrolls the page
+#[derive(Debug, Default)]
+pub struct ScrollFocus {
+    node: Option<NodeId>,
+}
+
+impl ScrollFocus {
+    pub fn node(&self) -> Option<NodeId> {
+        self.node
+    }
+
+    /// focuses the next scroll container, or the page after the last one, like Tab
+    pub fn next(&mut self, layout: &LayoutManager) -> super::Result<()> {
+        let containers = scroll_containers(layout)?;
+        let next = match self.position(&containers) {
+            Some(i) => containers.get(i + 1),
+            None => containers.first(),
+        };
+        self.node = next.copied();
+        Ok(())
+    }
+
+    /// focuses the previous scroll container, or the page before the first one, like Shift+Tab
+    pub fn previous(&mut self, layout: &LayoutManager) -> super::Result<()> {
+        let containers = scroll_containers(layout)?;
+        let previous = match self.position(&containers) {
+            Some(i) => i.checked_sub(1).map(|i| &containers[i]),
+            None => containers.last(),
+        };
+        self.node = previous.copied();
+        Ok(())
+    }
+
+    /// scrolls the focused container, see `scroll_by`.
+    /// returns what's left to scroll the page with, which is all of `delta` when the page has the focus
+    pub fn scroll(
+        &self,
+        dom: &mut Dom,
+        layout: &LayoutManager,
+        delta: Point<f32>,
+    ) -> super::Result<Point<f32>> {
+        match self.node {
+            Some(node) => scroll_by(dom, layout, node, delta),
+            None => Ok(delta),
+        }
+    }
+
+    /// where the focused container is in `containers`.
+    /// `None` when it isn't there anymore, eg. after a layout where it doesn't overflow
+    fn position(&self, containers: &[NodeId]) -> Option<usize> {
+        let node = self.node?;
+        containers.iter().position(|&container| container == node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::render::{
+        testing::{draw, laid_out},
+        Glyphs,
+    };
+
+    #[test]
+    fn scrolling() {
+        let page = r#"<div id="box"><p>one</p><p>two</p><p>three</p><p>four</p></div>
+            <p id="after">after</p>"#;
+        let css = "#box { width: 6tb; height: 2tb; overflow: auto; } #after { overflow: hidden; }";
+        let (mut dom, layout) = laid_out(page, css, (8, 3));
+        assert_eq!(
+            draw(&dom, &layout, (8, 3), Glyphs::Unicode),
+            ["one  █", "two  ░", "after"]
+        );
+
+        // the page keeps what the box can't scroll
+        let mut focus = ScrollFocus::default();
+        focus.next(&layout).unwrap();
+        let node = dom.query_select("#box").unwrap().remove(0);
+        assert_eq!(focus.node(), Some(node));
+        let delta = |y| taffy::Point { x: 0.0, y };
+        assert_eq!(
+            focus.scroll(&mut dom, &layout, delta(1.0)).unwrap(),
+            delta(0.0)
+        );
+        assert_eq!(
+            draw(&dom, &layout, (8, 3), Glyphs::Ascii),
+            ["two  |", "three#", "after"]
+        );
+        assert_eq!(
+            focus.scroll(&mut dom, &layout, delta(5.0)).unwrap(),
+            delta(4.0)
+        );
+        assert_eq!(
+            draw(&dom, &layout, (8, 3), Glyphs::Unicode),
+            ["three░", "four █", "after"]
+        );
+
+        // `#after` doesn't overflow, and `hidden` can't be scrolled by the user anyway
+        focus.next(&layout).unwrap();
+        assert!(focus.node().is_none());
+        assert_eq!(
+            focus.scroll(&mut dom, &layout, delta(1.0)).unwrap(),
+            delta(1.0)
+        );
+    }
+}
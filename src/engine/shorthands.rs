@@ -0,0 +1,130 @@
+use std::str::FromStr;
+
+use super::{computed_style::BorderStyle, stylesheet::Value};
+
+const SIDES: [&str; 4] = ["top", "right", "bottom", "left"];
+
+/// whether `key` is a shorthand that's expanded into its longhands, see `expand`
+pub fn is_shorthand(key: &str) -> bool {
+    key == "gap" || box_longhands(key).is_some() || border_sides(key).is_some()
+}
+
+/// the longhands that the `key` shorthand sets to `values`, in the order they're declared in.
+/// shorthands are expanded when they're parsed, so that every longhand cascades on its own,
+/// or once their `var()`s are substituted.
+/// returns `None` if `key` isn't a shorthand or `values` aren't valid for it
+pub fn expand(key: &str, values: &[Value]) -> Option<Vec<(String, Value)>> {
+    let longhands = longhands(key)?;
+    if let [value] = values {
+        if value.is_css_wide_keyword() {
+            return Some(longhands.into_iter().map(|l| (l, value.clone())).collect());
+        }
+    }
+    if values.iter().any(Value::is_css_wide_keyword) {
+        return None;
+    }
+
+    if let Some(longhands) = box_longhands(key) {
+        return Some(longhands.into_iter().zip(box_sides(values)?).collect());
+    }
+    if key == "gap" {
+        // the row gap, then the column gap if it's different
+        let (row, column) = match values {
+            [both] => (both, both),
+            [row, column] => (row, column),
+            _ => return None,
+        };
+        return Some(vec![
+            ("row-gap".into(), row.clone()),
+            ("column-gap".into(), column.clone()),
+        ]);
+    }
+    // `border` and `border-top` and the like: a width, a style and a color in any order,
+    // the missing ones being reset to their initial value
+    let mut parts = [None, None, None];
+    for value in values {
+        let part = border_part(value)?;
+        if parts[part].replace(value.clone()).is_some() {
+            return None;
+        }
+    }
+    let [width, style, color] = parts;
+    let parts = [
+        width.unwrap_or_else(|| Value::Keyword("medium".into())),
+        style.unwrap_or_else(|| Value::Keyword("none".into())),
+        color.unwrap_or_else(|| Value::Keyword("currentcolor".into())),
+    ];
+    let values = border_sides(key)?.into_iter().flat_map(|_| parts.clone());
+    Some(longhands.into_iter().zip(values).collect())
+}
+
+/// every longhand of `key`, if it's a shorthand
+fn longhands(key: &str) -> Option<Vec<String>> {
+    if key == "gap" {
+        return Some(vec!["row-gap".into(), "column-gap".into()]);
+    }
+    if let Some(longhands) = box_longhands(key) {
+        return Some(longhands.to_vec());
+    }
+    let sides = border_sides(key)?;
+    let parts = ["width", "style", "color"];
+    let longhands = sides.into_iter().flat_map(|side| {
+        parts
+            .into_iter()
+            .map(move |part| format!("border-{side}-{part}"))
+    });
+    Some(longhands.collect())
+}
+
+/// the longhands of the shorthands with one value per side: top, right, bottom and left
+fn box_longhands(key: &str) -> Option<[String; 4]> {
+    match key {
+        "margin" | "padding" => Some(SIDES.map(|side| format!("{key}-{side}"))),
+        "inset" => Some(SIDES.map(String::from)),
+        "border-width" | "border-style" | "border-color" => {
+            let part = &key["border-".len()..];
+            Some(SIDES.map(|side| format!("border-{side}-{part}")))
+        }
+        _ => None,
+    }
+}
+
+/// the sides that `border` or `border-top` and the like set
+fn border_sides(key: &str) -> Option<Vec<&'static str>> {
+    match key {
+        "border" => Some(SIDES.to_vec()),
+        _ => {
+            let side = key.strip_prefix("border-")?;
+            SIDES.into_iter().find(|&s| s == side).map(|s| vec![s])
+        }
+    }
+}
+
+/// the value of each side, from 1 to 4 values: all the sides, then vertical and horizontal,
+/// then top, horizontal and bottom, then top, right, bottom and left
+fn box_sides(values: &[Value]) -> Option<[Value; 4]> {
+    let sides = match values {
+        [all] => [all, all, all, all],
+        [vertical, horizontal] => [vertical, horizontal, vertical, horizontal],
+        [top, horizontal, bottom] => [top, horizontal, bottom, horizontal],
+        [top, right, bottom, left] => [top, right, bottom, left],
+        _ => return None,
+    };
+    Some(sides.map(Clone::clone))
+}
+
+/// which part of a border `value` is: 0 for the width, 1 for the style and 2 for the color.
+/// whether it's actually valid is only checked once it's the value of the longhand
+fn border_part(value: &Value) -> Option<usize> {
+    match value {
+        Value::Keyword(k)
+            if ["thin", "medium", "thick"].contains(&k.to_ascii_lowercase().as_str()) =>
+        {
+            Some(0)
+        }
+        Value::Keyword(k) if BorderStyle::from_str(k).is_ok() => Some(1),
+        Value::Dimension(_) | Value::Math(_) => Some(0),
+        Value::Keyword(_) | Value::Color(_) | Value::Function(..) => Some(2),
+        _ => None,
+    }
+}
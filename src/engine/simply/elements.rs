@@ -1,8 +1,20 @@
+use crate::engine::{
+    computed_style::{FontEmphasis, FontStyle},
+    dom::{Computed as _, NodeRef, NodeType},
+    stylesheet::PseudoElement,
+};
+
 pub struct Heading {
     pub level: u8,
     pub content: Paragraph,
 }
 
+impl Heading {
+    pub fn emphasis(&self) -> FontEmphasis {
+        FontEmphasis::from_heading_level(self.level)
+    }
+}
+
 pub struct Paragraph {
     pub content: Vec<Span>,
 }
@@ -28,10 +40,74 @@ pub struct List {
 
 pub enum ListKind {
     Unordered,
+    Ordered,
 }
 
 pub struct ListItem {
-    content: Paragraph,
+    /// the text of the item's `::marker`, eg. `3. ` or `• `
+    pub marker: String,
+    pub content: Paragraph,
+}
+
+impl List {
+    /// builds a list from an `ol` or `ul` node that went through `Dom::compute_styles`,
+    /// so that the items are numbered like in the layout
+    pub fn from_node(node: NodeRef) -> Self {
+        let kind = match &node.node().node_type {
+            NodeType::Element(element) if element.tag == "ol" => ListKind::Ordered,
+            _ => ListKind::Unordered,
+        };
+
+        let mut items = vec![];
+        for child in node.dom.children(node.id) {
+            let child = node.dom.node(child);
+            let c = child.node();
+            if !matches!(&c.node_type, NodeType::Element(e) if e.tag == "li") {
+                continue;
+            }
+            let marker = c
+                .generated
+                .iter()
+                .find(|g| g.pseudo_element == PseudoElement::Marker)
+                .map(|g| g.text.clone())
+                .unwrap_or_default();
+
+            let style = child.computed_style();
+            let span = Span {
+                color: style.to_ratatui_style().fg.unwrap_or_default(),
+                bold: style.is_bold(),
+                italic: style.font_style != FontStyle::Normal,
+                content: text_content(child),
+            };
+            items.push(ListItem {
+                marker,
+                content: Paragraph {
+                    content: vec![span],
+                },
+            });
+        }
+
+        Self { kind, items }
+    }
+}
+
+/// the text of every text node under `node`, with the whitespace collapsed
+fn text_content(node: NodeRef) -> String {
+    fn collect(node: NodeRef, text: &mut String) {
+        match &node.node().node_type {
+            NodeType::Text(t) => text.push_str(t),
+            NodeType::Element(_) => {
+                for child in node.dom.children(node.id) {
+                    collect(node.dom.node(child), text);
+                }
+            }
+            NodeType::Comment(_) => {}
+        }
+    }
+
+    let mut text = String::new();
+    collect(node, &mut text);
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
 }
 
 pub enum TbElementKind {
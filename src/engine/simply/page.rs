@@ -1,4 +1,5 @@
-use crate::engine::dom::SharedNode;
+use super::elements::TbElementKind;
+use crate::engine::dom::NodeId;
 
 pub struct Page {
     pub content: Vec<TbElement>,
@@ -6,5 +7,5 @@ pub struct Page {
 
 pub struct TbElement {
     pub kind: TbElementKind,
-    pub real_node: SharedNode,
+    pub real_node: NodeId,
 }
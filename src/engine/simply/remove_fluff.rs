@@ -1,6 +1,9 @@
-use std::sync::Arc;
+use crate::engine::dom::{Dom, Mutate as _, NodeId, Select};
 
-use crate::engine::dom::{Select, SharedNode};
+/// what is never part of the main content, even inside of it
+const FLUFF: [&str; 8] = [
+    "nav", "aside", "footer", "form", "iframe", ".ad", ".ads", ".advert",
+];
 
 /// this function will try to extract the main part of a html tree.
 ///
@@ -9,20 +12,57 @@ use crate::engine::dom::{Select, SharedNode};
 /// then it will try to pick out the <main> section
 /// lastly it will try to pick out the <body> section
 /// if even that is not present, what kind of website are you even viewing?
-pub fn remove_fluff(tree: SharedNode) -> super::Result<SharedNode> {
+///
+/// the `FLUFF` in the picked section, like navigation and ads, is removed from the tree
+pub fn remove_fluff(dom: &mut Dom, tree: NodeId) -> super::Result<NodeId> {
     // TODO: it will first try to check the provided ruleset
 
-    let main = tree.query_select("main")?;
-    let main = main.get(0);
-    if let Some(res) = main {
-        return Ok(Arc::clone(res));
+    let main = pick(dom, tree)?;
+    for query in FLUFF {
+        for fluff in dom.node(main).query_select(query)? {
+            // it might already be gone with an ancestor
+            if dom.is_inclusive_ancestor(main, fluff) {
+                dom.node_mut(fluff).detach();
+            }
+        }
     }
+    Ok(main)
+}
 
-    let body = tree.query_select("body")?;
-    let body = body.get(0);
-    if let Some(res) = body {
-        return Ok(Arc::clone(res));
+fn pick(dom: &Dom, tree: NodeId) -> super::Result<NodeId> {
+    let main = dom.node(tree).query_select("main")?;
+    if let Some(&res) = main.first() {
+        return Ok(res);
+    }
+
+    let body = dom.node(tree).query_select("body")?;
+    if let Some(&res) = body.first() {
+        return Ok(res);
     }
 
     Err(super::Error::NoMainSectionFound)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::html;
+
+    #[test]
+    fn prunes_the_main_section() {
+        let mut dom = html::parse_from_str(
+            r#"<body><nav>menu</nav><main><h1>title</h1><aside>related</aside><p>text</p><div class="ad"><p>buy</p></div></main></body>"#,
+        )
+        .unwrap();
+        let root = dom.root;
+        let main = remove_fluff(&mut dom, root).unwrap();
+
+        let kept = dom
+            .children(main)
+            .map(|child| dom[child].to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(kept, ["h1", "p"]);
+        // outside of the picked section nothing changes
+        assert_eq!(dom.query_select("nav").unwrap().len(), 1);
+    }
+}
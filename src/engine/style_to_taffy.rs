@@ -1,71 +1,95 @@
-use thiserror::Error;
-
-use super::{
-    dom::{AskStyle, SharedNode},
-    stylesheet::Value,
-};
-
-#[derive(Error, Debug)]
-pub enum Error {
-    #[error("inline is not yet allowed")]
-    InlineNotYetAllowed, // this will be added in future (?)
-}
+use super::computed_style::{self, ComputedStyle, Length, LengthAuto, Position};
 
 // NOTE: commented properties are either irrelevant or will be added in the future
-fn get_taffy_from_node(node: SharedNode) -> Result<taffy::Style, Error> {
+pub fn computed_to_taffy(style: &ComputedStyle) -> taffy::Style {
     taffy::Style {
-        display: match node.get_style("display") {
-            Value::Keyword(str) => match str.as_str() {
-                "inline" => return Err(Error::InlineNotYetAllowed),
-                "block" => taffy::Display::Block,
-                "flex" => taffy::Display::Flex,
-                "grid" => taffy::Display::Grid,
-                _ => taffy::Display::Block,
-            },
+        display: match style.display {
+            computed_style::Display::None => taffy::Display::None,
+            computed_style::Display::Flex | computed_style::Display::InlineFlex => {
+                taffy::Display::Flex
+            }
+            // the cells of a table are laid out as the items of a grid, see `LayoutManager::convert_table`
+            computed_style::Display::Grid
+            | computed_style::Display::InlineGrid
+            | computed_style::Display::Table
+            | computed_style::Display::InlineTable => taffy::Display::Grid,
+            // TODO: inline formatting contexts don't exist yet, so inline boxes are laid out as blocks
             _ => taffy::Display::Block,
         },
-        // item_is_table: todo!(),
+        item_is_table: style.display.is_table(),
         // item_is_replaced: todo!(),
         // box_sizing: todo!(),
-        // overflow: todo!(),
-        // scrollbar_width: todo!(),
-        position: match node.get_style("position") {
-            Value::Keyword(str) => match str.as_str() {
-                "relative" => taffy::Position::Relative,
-                "absolute" => taffy::Position::Absolute,
-                _ => taffy::Position::Relative,
+        overflow: taffy::Point {
+            // a whole row is too much room to keep for a scrollbar that may not be needed,
+            // so `overflow-x: auto` can be scrolled but doesn't get one
+            x: match style.overflow_x {
+                computed_style::Overflow::Auto => taffy::Overflow::Hidden,
+                overflow => overflow.to_taffy(),
             },
+            y: style.overflow_y.to_taffy(),
+        },
+        scrollbar_width: style.scrollbar_width.cells(),
+        // fixed boxes are laid out in the viewport, see `LayoutManager::build`
+        position: match style.position {
+            computed_style::Position::Absolute | computed_style::Position::Fixed => {
+                taffy::Position::Absolute
+            }
             _ => taffy::Position::Relative,
         },
-        // TODO: make a helper function / method for this
-        inset: taffy::Rect {
-            left: match node.get_style_with_fallback("inset", "left") {
-                _ => taffy::LengthPercentageAuto::auto(),
+        // a sticky box is only moved when it's drawn, see `render`
+        inset: match style.position {
+            computed_style::Position::Static | computed_style::Position::Sticky => {
+                computed_style::Edges::all(computed_style::LengthAuto::Auto).map(|l| l.to_taffy())
+            }
+            _ => style.inset.map(|l| l.to_taffy()),
+        },
+        size: taffy::Size {
+            width: style.width.to_taffy_dimension(),
+            height: style.height.to_taffy_dimension(),
+        },
+        min_size: taffy::Size {
+            width: style.min_width.to_taffy_dimension(),
+            height: style.min_height.to_taffy_dimension(),
+        },
+        max_size: taffy::Size {
+            width: style.max_width.to_taffy_dimension(),
+            height: style.max_height.to_taffy_dimension(),
+        },
+        // aspect_ratio: todo!(),
+        margin: style.margin.map(|l| l.to_taffy()),
+        padding: style.padding.map(|l| l.to_taffy()),
+        border: style
+            .border_width
+            .map(|&width| taffy::LengthPercentage::length(width)),
+        align_items: style.align_items,
+        // align_self: todo!(),
+        // justify_items: todo!(),
+        // justify_self: todo!(),
+        // align_content: todo!(),
+        justify_content: style.justify_content,
+        gap: match style.display {
+            // `border-spacing` is the room between the cells, but collapsed borders are shared
+            computed_style::Display::Table | computed_style::Display::InlineTable => {
+                let (horizontal, vertical) = match style.border_collapse {
+                    computed_style::BorderCollapse::Separate => style.border_spacing,
+                    computed_style::BorderCollapse::Collapse => (0.0, 0.0),
+                };
+                taffy::Size {
+                    width: taffy::LengthPercentage::length(horizontal),
+                    height: taffy::LengthPercentage::length(vertical),
+                }
+            }
+            _ => taffy::Size {
+                width: style.column_gap.to_taffy(),
+                height: style.row_gap.to_taffy(),
             },
-            right: todo!(),
-            top: todo!(),
-            bottom: todo!(),
         },
-        size: todo!(),
-        min_size: todo!(),
-        max_size: todo!(),
-        aspect_ratio: todo!(),
-        margin: todo!(),
-        padding: todo!(),
-        border: todo!(),
-        align_items: todo!(),
-        align_self: todo!(),
-        justify_items: todo!(),
-        justify_self: todo!(),
-        align_content: todo!(),
-        justify_content: todo!(),
-        gap: todo!(),
-        text_align: todo!(),
-        flex_direction: todo!(),
-        flex_wrap: todo!(),
-        flex_basis: todo!(),
-        flex_grow: todo!(),
-        flex_shrink: todo!(),
+        // text_align: todo!(),
+        flex_direction: style.flex_direction,
+        flex_wrap: style.flex_wrap,
+        flex_basis: style.flex_basis.to_taffy_dimension(),
+        flex_grow: style.flex_grow,
+        flex_shrink: style.flex_shrink,
         // grid_template_rows: todo!(),
         // grid_template_columns: todo!(),
         // grid_auto_rows: todo!(),
@@ -74,5 +98,66 @@ fn get_taffy_from_node(node: SharedNode) -> Result<taffy::Style, Error> {
         // grid_row: todo!(),
         // grid_column: todo!(),
         ..Default::default()
+    }
+}
+
+/// puts the math functions with percentages of `style` into `taffy_style`, which `computed_to_taffy` left `auto` or 0,
+/// with `containing_block` as the size of the containing block, see `LayoutManager::compute`.
+///
+/// like percentages, margins and padding are relative to its width on every side.
+/// sizes, padding and gaps can't be negative, so they're clamped
+pub fn resolve_calc(
+    style: &ComputedStyle,
+    taffy_style: &mut taffy::Style,
+    containing_block: taffy::Size<f32>,
+) {
+    let (width, height) = (containing_block.width, containing_block.height);
+    let size = |length: &LengthAuto, basis: f32, dimension: &mut taffy::Dimension| {
+        if let Some(calc) = length.calc() {
+            *dimension = taffy::Dimension::length(calc.resolve(basis).max(0.0));
+        }
     };
+    let offset = |length: &LengthAuto, basis: f32, offset: &mut taffy::LengthPercentageAuto| {
+        if let Some(calc) = length.calc() {
+            *offset = taffy::LengthPercentageAuto::length(calc.resolve(basis));
+        }
+    };
+    let spacing = |length: &Length, basis: f32, spacing: &mut taffy::LengthPercentage| {
+        if let Some(calc) = length.calc() {
+            *spacing = taffy::LengthPercentage::length(calc.resolve(basis).max(0.0));
+        }
+    };
+
+    size(&style.width, width, &mut taffy_style.size.width);
+    size(&style.height, height, &mut taffy_style.size.height);
+    size(&style.min_width, width, &mut taffy_style.min_size.width);
+    size(&style.min_height, height, &mut taffy_style.min_size.height);
+    size(&style.max_width, width, &mut taffy_style.max_size.width);
+    size(&style.max_height, height, &mut taffy_style.max_size.height);
+    size(&style.flex_basis, width, &mut taffy_style.flex_basis);
+    if matches!(
+        style.position,
+        Position::Relative | Position::Absolute | Position::Fixed
+    ) {
+        offset(&style.inset.top, height, &mut taffy_style.inset.top);
+        offset(&style.inset.right, width, &mut taffy_style.inset.right);
+        offset(&style.inset.bottom, height, &mut taffy_style.inset.bottom);
+        offset(&style.inset.left, width, &mut taffy_style.inset.left);
+    }
+    offset(&style.margin.top, width, &mut taffy_style.margin.top);
+    offset(&style.margin.right, width, &mut taffy_style.margin.right);
+    offset(&style.margin.bottom, width, &mut taffy_style.margin.bottom);
+    offset(&style.margin.left, width, &mut taffy_style.margin.left);
+    spacing(&style.padding.top, width, &mut taffy_style.padding.top);
+    spacing(&style.padding.right, width, &mut taffy_style.padding.right);
+    spacing(
+        &style.padding.bottom,
+        width,
+        &mut taffy_style.padding.bottom,
+    );
+    spacing(&style.padding.left, width, &mut taffy_style.padding.left);
+    if !style.display.is_table() {
+        spacing(&style.column_gap, width, &mut taffy_style.gap.width);
+        spacing(&style.row_gap, height, &mut taffy_style.gap.height);
+    }
 }
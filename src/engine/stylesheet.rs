@@ -1,11 +1,12 @@
-use std::{collections::HashMap, fmt::Display, rc::Rc, str::FromStr};
+use std::{collections::HashMap, fmt::Display, str::FromStr, sync::Arc};
 
 use pest::Parser as _;
-use strum_macros::Display;
+use strum_macros::{Display, EnumString};
 
 use super::{
+    calc::MathExpr,
     css::{self},
-    dom::{shared_node, AskStyle, Parent, SharedNode},
+    media::{Device, MediaQueryList},
     Error, Result,
 };
 
@@ -13,6 +14,11 @@ use super::{
 pub struct Stylesheet {
     pub rules: Vec<Rule>,
     pub origin: Origin,
+    /// everything that was dropped while parsing, the rest of the stylesheet still applies
+    pub warnings: Vec<ParseWarning>,
+    /// the cascade layers, in the order they were first declared.
+    /// the first one is the implicit layer of the unlayered rules
+    pub layers: Vec<Layer>,
 }
 
 impl Stylesheet {
@@ -20,8 +26,170 @@ impl Stylesheet {
         Stylesheet {
             rules: rules.unwrap_or_default(),
             origin,
+            warnings: vec![],
+            layers: vec![Layer {
+                name: None,
+                parent: None,
+            }],
         }
     }
+
+    /// returns the index of the `name` layer inside the `parent` layer, declaring it if needed.
+    /// `name` can be dotted (`a.b`), and anonymous layers (`None`) are always new
+    pub fn declare_layer(&mut self, parent: usize, name: Option<&str>) -> usize {
+        let Some(name) = name else {
+            self.layers.push(Layer {
+                name: None,
+                parent: Some(parent),
+            });
+            return self.layers.len() - 1;
+        };
+
+        name.split('.').fold(parent, |parent, part| {
+            let existing = self
+                .layers
+                .iter()
+                .position(|l| l.parent == Some(parent) && l.name.as_deref() == Some(part));
+            existing.unwrap_or_else(|| {
+                self.layers.push(Layer {
+                    name: Some(part.to_string()),
+                    parent: Some(parent),
+                });
+                self.layers.len() - 1
+            })
+        })
+    }
+}
+
+/// A cascade layer (`@layer`).
+///
+/// Normal declarations in later layers win over earlier ones regardless of specificity,
+/// and the rules directly in a layer win over the ones in its sublayers.
+/// `!important` declarations go the other way around.
+#[derive(Debug, Clone)]
+pub struct Layer {
+    /// the name inside the parent layer, eg. `base` for `framework.base`. `None` for anonymous layers
+    pub name: Option<String>,
+    /// `None` only for the implicit layer of the unlayered rules
+    pub parent: Option<usize>,
+}
+
+/// the `layer_order` of the declarations in `style` attributes, which win over every layer
+pub const STYLE_ATTRIBUTE_LAYER: usize = usize::MAX;
+
+/// computes the position of every layer of every stylesheet in the cascade, as
+/// `orders[sheet index][layer index]`. a higher order wins for normal declarations.
+///
+/// layers with the same name in different stylesheets of the same origin are the same layer,
+/// ordered by where they were first declared.
+pub fn layer_orders(stylesheets: &[Stylesheet]) -> Vec<Vec<usize>> {
+    struct Merged {
+        children: Vec<usize>,
+        order: usize,
+    }
+
+    fn assign_orders(merged: &mut [Merged], node: usize, next: &mut usize) {
+        for child in merged[node].children.clone() {
+            assign_orders(merged, child, next);
+        }
+        merged[node].order = *next;
+        *next += 1;
+    }
+
+    let mut mappings: Vec<Vec<usize>> = vec![];
+    let mut merged: Vec<Merged> = vec![];
+    let mut roots: Vec<(Origin, usize)> = vec![];
+    // (parent, name) -> layer
+    let mut by_name: HashMap<(usize, &str), usize> = HashMap::new();
+
+    for sheet in stylesheets {
+        let root = match roots.iter().find(|(origin, _)| *origin == sheet.origin) {
+            Some((_, root)) => *root,
+            None => {
+                merged.push(Merged {
+                    children: vec![],
+                    order: 0,
+                });
+                roots.push((sheet.origin, merged.len() - 1));
+                merged.len() - 1
+            }
+        };
+
+        let mut mapping = vec![root];
+        for layer in &sheet.layers[1..] {
+            let parent = mapping[layer.parent.unwrap_or(0)];
+            let existing = layer
+                .name
+                .as_deref()
+                .and_then(|name| by_name.get(&(parent, name)));
+            let node = match existing {
+                Some(node) => *node,
+                None => {
+                    merged.push(Merged {
+                        children: vec![],
+                        order: 0,
+                    });
+                    let node = merged.len() - 1;
+                    merged[parent].children.push(node);
+                    if let Some(name) = &layer.name {
+                        by_name.insert((parent, name), node);
+                    }
+                    node
+                }
+            };
+            mapping.push(node);
+        }
+        mappings.push(mapping);
+    }
+
+    for (_, root) in roots {
+        assign_orders(&mut merged, root, &mut 0);
+    }
+
+    mappings
+        .into_iter()
+        .map(|mapping| mapping.into_iter().map(|node| merged[node].order).collect())
+        .collect()
+}
+
+/// Something in a stylesheet that was skipped because we don't understand it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWarning {
+    pub kind: ParseWarningKind,
+    /// 1-based, like the positions in pest errors
+    pub line: usize,
+    pub column: usize,
+    /// the text that was dropped
+    pub text: String,
+}
+
+impl Display for ParseWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.line, self.column, self.kind, self.text
+        )
+    }
+}
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum ParseWarningKind {
+    /// an at-rule we don't support, skipped along with its block
+    #[strum(serialize = "unknown at-rule")]
+    UnknownAtRule,
+    /// a rule whose selector can't be parsed, skipped along with its block
+    #[strum(serialize = "invalid rule")]
+    InvalidRule,
+    /// a declaration that isn't `name: value`, or whose value can't be parsed
+    #[strum(serialize = "invalid declaration")]
+    InvalidDeclaration,
+    /// an `@import` that couldn't be loaded, or that imports itself
+    #[strum(serialize = "unresolved import")]
+    UnresolvedImport,
+    /// a `}` without a matching `{`
+    #[strum(serialize = "unmatched closing brace")]
+    StrayBrace,
 }
 
 #[derive(Debug, Display, Clone, Copy, PartialEq, PartialOrd, Eq, Ord)]
@@ -43,7 +211,7 @@ impl Origin {
     }
 }
 
-pub type PropMap = HashMap<Rc<String>, Rc<PropertyValue>>;
+pub type PropMap = HashMap<Arc<String>, Arc<PropertyValue>>;
 
 #[derive(Debug)]
 pub struct PropertyValue {
@@ -55,20 +223,39 @@ pub struct PropertyValue {
 pub struct Rule {
     pub selector: ComplexSelector,
     pub props: PropMap,
+    /// the conditions of the `@media` blocks the rule is nested in.
+    /// the rule only applies if all of them match
+    pub media: Vec<Arc<MediaQueryList>>,
+    /// the cascade layer the rule is in, as an index into `Stylesheet::layers`.
+    /// see `layer_orders` for where the layer ends up in the cascade
+    pub layer: usize,
+}
+
+impl Rule {
+    pub fn media_matches(&self, device: &Device, viewport: (u16, u16)) -> bool {
+        self.media.iter().all(|m| m.matches(device, viewport))
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 /// `(a, b, c, d)`
 /// `a` = 1 if the styles are defined inline
-/// `b` = 1 if id is present
+/// `b` = amount of ids
 /// `c` = amount of classes
-/// `d` = 1 if tag is present
+/// `d` = amount of tags
 pub struct Specificity(pub usize, pub usize, pub usize, pub usize);
 
+impl Specificity {
+    /// the specificity of declarations in a `style` attribute
+    pub const INLINE: Specificity = Specificity(1, 0, 0, 0);
+}
+
 #[derive(Debug)]
 pub struct ComplexSelector {
     pub inner: Vec<Selector>,
     pub combinators: Vec<Combinator>,
+    /// `::before` in `p::before`. the rest of the selector matches the originating element
+    pub pseudo_element: Option<PseudoElement>,
 }
 
 impl FromStr for ComplexSelector {
@@ -76,7 +263,7 @@ impl FromStr for ComplexSelector {
 
     fn from_str(input: &str) -> Result<Self> {
         let mut pairs =
-            css::CssParser::parse(css::Rule::complex_selector, input).map_err(|e| Box::new(e))?;
+            css::CssParser::parse(css::Rule::complex_selector, input).map_err(Box::new)?;
         let pair = match pairs.next() {
             Some(v) => v,
             None => return Err(Error::InvalidSelector),
@@ -87,15 +274,27 @@ impl FromStr for ComplexSelector {
 
 impl ComplexSelector {
     pub fn specificity(&self) -> Specificity {
-        self.inner
+        let compound = self
+            .inner
             .iter()
             .map(|s| s.specificity())
             .fold(Specificity(0, 0, 0, 0), |r, v| {
-                Specificity(0, r.0 + v.0, r.1 + v.1, r.2 + v.2)
-            })
+                Specificity(r.0 + v.0, r.1 + v.1, r.2 + v.2, r.3 + v.3)
+            });
+        // pseudo-elements count like type selectors
+        let pseudo = self.pseudo_element.iter().count();
+        Specificity(compound.0, compound.1, compound.2, compound.3 + pseudo)
     }
 }
 
+#[derive(Debug, Display, EnumString, Clone, Copy, PartialEq, Eq, Hash)]
+#[strum(serialize_all = "kebab-case", ascii_case_insensitive)]
+pub enum PseudoElement {
+    Marker,
+    Before,
+    After,
+}
+
 #[derive(Debug, Clone)]
 pub struct Selector {
     pub id: Option<String>,
@@ -104,7 +303,7 @@ pub struct Selector {
 }
 
 impl Selector {
-    /// TODO: add the inline (a) somehow
+    /// `a` is always 0, as only declarations in a `style` attribute are inline (see `Specificity::INLINE`)
     pub fn specificity(&self) -> Specificity {
         let b = self.id.iter().count();
         let c = self.classes.len();
@@ -131,10 +330,52 @@ pub enum Value {
     Keyword(String),
     Dimension(Dimension),
     Color(Color),
+    /// unparsed text: the value of a custom property (`--x`),
+    /// or a value containing `var()`, which can only be parsed after substitution
+    Raw(String),
+    /// `calc()`, `min()`, `max()` or `clamp()`
+    Math(MathExpr),
+    /// a quoted string, already unescaped
+    String(String),
+    /// any other function, eg. `attr(title)`
+    Function(String, Vec<Value>),
+    /// the values of the properties that take several of them, like `content` or `quotes`
+    List(Vec<Value>),
     None,
 }
 
-#[derive(Debug, Clone, Copy)]
+/// the keywords that every property takes, and that can only be the whole value of a declaration
+pub const CSS_WIDE_KEYWORDS: [&str; 5] = ["inherit", "initial", "unset", "revert", "revert-layer"];
+
+impl Value {
+    pub fn is_css_wide_keyword(&self) -> bool {
+        matches!(self, Value::Keyword(k) if CSS_WIDE_KEYWORDS.iter().any(|wide| k.eq_ignore_ascii_case(wide)))
+    }
+
+    /// the values of a `Value::List`, or the value itself
+    pub fn as_list(&self) -> &[Value] {
+        match self {
+            Value::List(values) => values,
+            value => std::slice::from_ref(value),
+        }
+    }
+
+    /// whether the value can compute differently when the viewport is resized:
+    /// it has viewport units, or is only known after `var()` substitution
+    pub fn is_viewport_relative(&self) -> bool {
+        match self {
+            Value::Dimension(dimension) => matches!(dimension.unit, Unit::Vw | Unit::Vh),
+            Value::Math(expr) => expr.is_viewport_relative(),
+            Value::Raw(_) => true,
+            Value::Function(_, values) | Value::List(values) => {
+                values.iter().any(Value::is_viewport_relative)
+            }
+            Value::Keyword(_) | Value::Color(_) | Value::String(_) | Value::None => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Dimension {
     pub value: f64,
     pub unit: Unit,
@@ -142,29 +383,82 @@ pub struct Dimension {
 
 #[derive(Error, Debug)]
 pub enum DimensionError {
-    #[error("cannot calculate percentage dimension on property \"{prop_name}\" that is not applied to parent")]
-    MissingPropOnParent { prop_name: String },
-    #[error("cannot calculate percentage dimension on property \"{prop_name}\" that is a color on parent")]
-    PropParentIsColor { prop_name: String },
-    #[error("cannot calculate percentage dimension on property \"{prop_name}\" that is a keyword on parent")]
-    PropParentIsKeyword { prop_name: String },
-    #[error("as_tb_trivial cannot convert a relative unit")]
+    #[error("cannot convert a percentage without a containing block")]
     Relative,
     #[error("cannot convert a unitless or invalid unit")]
     Invalid,
-    #[error("shared node error: {0}")]
-    SharedNodeError(#[from] shared_node::Error),
+    #[error("math expression mixes numbers and lengths in an invalid way, or divides by zero")]
+    InvalidMath,
+}
+
+pub const PX_TO_TB: f64 = 8.0;
+
+/// The font sizes that the font-relative units are relative to, in px.
+///
+/// a cell is half an em wide at the default font size (16px),
+/// so `1ch` (a character) and `1lh` (a line) are both one cell there
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FontMetrics {
+    /// the font size of the element, or of its parent for `font-size` itself
+    pub em: f64,
+    /// the font size of the root element
+    pub rem: f64,
+}
+
+impl FontMetrics {
+    pub const DEFAULT_FONT_SIZE: f64 = 16.0;
+
+    pub fn new(em: f32, rem: f32) -> Self {
+        Self {
+            em: em.into(),
+            rem: rem.into(),
+        }
+    }
+}
+
+impl Default for FontMetrics {
+    /// what media queries use, since they don't belong to any element
+    fn default() -> Self {
+        Self {
+            em: Self::DEFAULT_FONT_SIZE,
+            rem: Self::DEFAULT_FONT_SIZE,
+        }
+    }
 }
 
-const PX_TO_TB: f64 = 8.0;
 impl Dimension {
+    /// `basis` is the size that percentages are relative to, in tb.
+    /// without one, they fail with `DimensionError::Relative`
     pub fn as_tb(
         &self,
-        parent: &SharedNode,
-        prop_name: &str,
+        basis: Option<f64>,
         viewport: (u16, u16),
+        font: FontMetrics,
     ) -> core::result::Result<Dimension, DimensionError> {
-        let value = match self.unit {
+        Ok(Dimension {
+            value: self.tb_value(basis, viewport, font)?.round(),
+            unit: Unit::Tb,
+        })
+    }
+
+    /// converts every unit that doesn't depend on the containing block.
+    /// fails with `DimensionError::Relative` on percentages
+    pub fn as_tb_trivial(
+        &self,
+        viewport: (u16, u16),
+        font: FontMetrics,
+    ) -> core::result::Result<Dimension, DimensionError> {
+        self.as_tb(None, viewport, font)
+    }
+
+    /// the value in tb units, before rounding
+    pub(super) fn tb_value(
+        &self,
+        basis: Option<f64>,
+        viewport: (u16, u16),
+        font: FontMetrics,
+    ) -> core::result::Result<f64, DimensionError> {
+        Ok(match self.unit {
             Unit::Px => self.value / PX_TO_TB,
             Unit::Pt => self.value * (1.0 / 72.0) * 96.0 / PX_TO_TB,
             Unit::Q => self.value * 0.945 / PX_TO_TB,
@@ -173,59 +467,30 @@ impl Dimension {
             Unit::Pc => self.value * 16.0 / PX_TO_TB,
             Unit::In => self.value * 96.0 / PX_TO_TB,
             Unit::Tb => self.value,
-            Unit::Em | Unit::Rem => self.value, // font size is always 1tb on everything no matter what
+            Unit::Em => self.value * font.em / PX_TO_TB,
+            Unit::Rem => self.value * font.rem / PX_TO_TB,
+            Unit::Ch | Unit::Ex | Unit::Lh => self.value * font.em / 2.0 / PX_TO_TB,
+            Unit::Rlh => self.value * font.rem / 2.0 / PX_TO_TB,
             Unit::Vw => (Into::<f64>::into(viewport.0) / 100.0) * self.value,
             Unit::Vh => (Into::<f64>::into(viewport.1) / 100.0) * self.value,
-            Unit::Percent => {
-                let style =
-                    parent
-                        .ask_style(prop_name)?
-                        .ok_or(DimensionError::MissingPropOnParent {
-                            prop_name: prop_name.into(),
-                        })?;
-
-                match &style.value.value {
-                    Value::Keyword(_) => {
-                        return Err(DimensionError::PropParentIsKeyword {
-                            prop_name: prop_name.into(),
-                        })
-                    } // TODO: In this case we propbably need to ask a VM or something to compute the value
-                    Value::Color(_) => {
-                        return Err(DimensionError::PropParentIsColor {
-                            prop_name: prop_name.into(),
-                        })
-                    }
-                    Value::Dimension(dimension) => {
-                        let grandparent = parent.parent()?;
-                        let dimension_tb = dimension.as_tb(&grandparent, prop_name, viewport)?;
-
-                        (dimension_tb.value / 100.0) * self.value
-                    }
-                    Value::None => unreachable!(),
-                }
-            }
+            Unit::Percent => (basis.ok_or(DimensionError::Relative)? / 100.0) * self.value,
             Unit::Unitless | Unit::Invalid => return Err(DimensionError::Invalid),
-        };
-
-        Ok(Dimension {
-            value: value.round(),
-            unit: Unit::Tb,
         })
     }
 
     pub fn as_tb_u16(
         &self,
-        parent: &SharedNode,
-        prop_name: &str,
+        basis: Option<f64>,
         viewport: (u16, u16),
+        font: FontMetrics,
     ) -> core::result::Result<u16, DimensionError> {
-        Ok(self.as_tb(parent, prop_name, viewport)?.value as u16)
+        Ok(self.as_tb(basis, viewport, font)?.value as u16)
     }
 }
 
 /// Describes a CSS Unit.
 /// These will eventually be transformed into "tb units"
-#[derive(Debug, Display, Clone, Copy)]
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
 pub enum Unit {
     Px,  // pixel (1/96 in)
     Pt,  // point (1/72 in)
@@ -234,8 +499,12 @@ pub enum Unit {
     Mm,  // millimeter
     Cm,  // centimeter
     In,  // inch
-    Em,  // relative to font size of element (always equal to 1tb)
-    Rem, // relative to font size of root (always equal to 1tb)
+    Em,  // relative to font size of element
+    Rem, // relative to font size of root
+    Ch,  // width of a character, half an em
+    Ex,  // height of an x, about half an em
+    Lh,  // height of a line, one cell at the default font size
+    Rlh, // height of a line of the root
     Vh,  // relative to viewport height
     Vw,  // relative to viewport width
     Tb,  // tb unit == 1 cell (not standard CSS)
@@ -260,6 +529,10 @@ impl FromStr for Unit {
             "in" => Unit::In,
             "em" => Unit::Em,
             "rem" => Unit::Rem,
+            "ch" => Unit::Ch,
+            "ex" => Unit::Ex,
+            "lh" => Unit::Lh,
+            "rlh" => Unit::Rlh,
             "vh" => Unit::Vh,
             "vw" => Unit::Vw,
             "tb" => Unit::Tb,
@@ -270,7 +543,7 @@ impl FromStr for Unit {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -278,6 +551,96 @@ pub struct Color {
     pub a: u8,
 }
 
+impl Color {
+    pub const TRANSPARENT: Color = Color::rgba(0, 0, 0, 0);
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Color { r, g, b, a }
+    }
+
+    /// parses `#rgb`, `#rgba`, `#rrggbb` and `#rrggbbaa` (the `#` is optional)
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        let digit = |i: usize| u8::from_str_radix(hex.get(i..i + 1)?, 16).ok();
+        let pair = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+
+        match hex.len() {
+            3 | 4 => Some(Color {
+                r: digit(0)? * 0x11,
+                g: digit(1)? * 0x11,
+                b: digit(2)? * 0x11,
+                a: if hex.len() == 4 {
+                    digit(3)? * 0x11
+                } else {
+                    0xff
+                },
+            }),
+            6 | 8 => Some(Color {
+                r: pair(0)?,
+                g: pair(2)?,
+                b: pair(4)?,
+                a: if hex.len() == 8 { pair(6)? } else { 0xff },
+            }),
+            _ => None,
+        }
+    }
+
+    /// looks up a named color (eg. `red`, `rebeccapurple`, `transparent`).
+    /// `currentcolor` and system colors are not handled here.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        let hex = match name.as_str() {
+            "transparent" => return Some(Color::TRANSPARENT),
+            "black" => "000000",
+            "silver" => "c0c0c0",
+            "gray" | "grey" => "808080",
+            "white" => "ffffff",
+            "maroon" => "800000",
+            "red" => "ff0000",
+            "purple" => "800080",
+            "fuchsia" | "magenta" => "ff00ff",
+            "green" => "008000",
+            "lime" => "00ff00",
+            "olive" => "808000",
+            "yellow" => "ffff00",
+            "navy" => "000080",
+            "blue" => "0000ff",
+            "teal" => "008080",
+            "aqua" | "cyan" => "00ffff",
+            "orange" => "ffa500",
+            "pink" => "ffc0cb",
+            "brown" => "a52a2a",
+            "gold" => "ffd700",
+            "indigo" => "4b0082",
+            "violet" => "ee82ee",
+            "crimson" => "dc143c",
+            "coral" => "ff7f50",
+            "salmon" => "fa8072",
+            "tomato" => "ff6347",
+            "orchid" => "da70d6",
+            "khaki" => "f0e68c",
+            "beige" => "f5f5dc",
+            "ivory" => "fffff0",
+            "tan" => "d2b48c",
+            "chocolate" => "d2691e",
+            "turquoise" => "40e0d0",
+            "skyblue" => "87ceeb",
+            "steelblue" => "4682b4",
+            "royalblue" => "4169e1",
+            "darkblue" => "00008b",
+            "darkred" => "8b0000",
+            "darkgreen" => "006400",
+            "darkgray" | "darkgrey" => "a9a9a9",
+            "lightgray" | "lightgrey" => "d3d3d3",
+            "dimgray" | "dimgrey" => "696969",
+            "whitesmoke" => "f5f5f5",
+            "rebeccapurple" => "663399",
+            _ => return None,
+        };
+        Color::from_hex(hex)
+    }
+}
+
 impl Display for Color {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -287,3 +650,31 @@ impl Display for Color {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn complex_selector_specificity() {
+        let cases = [
+            ("*", Specificity(0, 0, 0, 0)),
+            ("p", Specificity(0, 0, 0, 1)),
+            ("div p", Specificity(0, 0, 0, 2)),
+            (".a.b", Specificity(0, 0, 2, 0)),
+            ("#x", Specificity(0, 1, 0, 0)),
+            ("#x .a > p + span", Specificity(0, 1, 1, 2)),
+            ("div#x.a ~ #y.b.c", Specificity(0, 2, 3, 1)),
+        ];
+
+        for (selector, specificity) in cases {
+            assert_eq!(
+                ComplexSelector::from_str(selector).unwrap().specificity(),
+                specificity,
+                "{selector}"
+            );
+        }
+
+        assert!(Specificity::INLINE > Specificity(0, 100, 0, 0));
+    }
+}
@@ -0,0 +1,177 @@
+use super::dom::ElementData;
+
+/// How many rows and columns a cell takes up, from `colspan` and `rowspan`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub columns: usize,
+    /// `None` for `rowspan="0"`, which spans every row left in the row group
+    pub rows: Option<usize>,
+}
+
+impl Span {
+    /// with the html limits: at most 1000 columns and 65534 rows, and invalid values count as 1
+    pub fn new(cell: Option<&ElementData>) -> Self {
+        let attr = |name| {
+            let value = cell?.attrs.get(name)?;
+            value.trim().parse::<usize>().ok()
+        };
+        let columns = attr("colspan").filter(|&columns| columns > 0);
+        let columns = columns.unwrap_or(1).min(1000);
+        let rows = match attr("rowspan") {
+            Some(0) => None,
+            rows => Some(rows.unwrap_or(1).min(65534)),
+        };
+        Self { columns, rows }
+    }
+}
+
+/// Where a cell ends up in the grid of a table, 0-based
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    pub row: usize,
+    pub column: usize,
+    pub rows: usize,
+    pub columns: usize,
+}
+
+/// The slots of a table, filled in row by row like the html table model:
+/// each cell takes the first column that isn't taken by a cell spanning down from a row above
+#[derive(Debug, Default)]
+pub struct Slots {
+    /// for each column, the first row that isn't taken yet
+    free_from: Vec<usize>,
+    rows: usize,
+}
+
+impl Slots {
+    /// places the cells of a row group, given the spans of the cells in each of its rows
+    pub fn place_group(&mut self, group: &[Vec<Span>]) -> Vec<Vec<Placement>> {
+        let first = self.rows;
+        let end = first + group.len();
+        let mut placed = vec![];
+        for (i, cells) in group.iter().enumerate() {
+            let row = first + i;
+            let mut column = 0;
+            let mut placements = vec![];
+            for span in cells {
+                while self.free_from.get(column).is_some_and(|&free| free > row) {
+                    column += 1;
+                }
+                // a cell can't span past the end of its row group
+                let rows = span.rows.map_or(end - row, |rows| rows.min(end - row));
+                if self.free_from.len() < column + span.columns {
+                    self.free_from.resize(column + span.columns, 0);
+                }
+                for free in &mut self.free_from[column..column + span.columns] {
+                    *free = row + rows;
+                }
+                placements.push(Placement {
+                    row,
+                    column,
+                    rows,
+                    columns: span.columns,
+                });
+                column += span.columns;
+            }
+            placed.push(placements);
+        }
+        self.rows = end;
+        placed
+    }
+
+    pub fn columns(&self) -> usize {
+        self.free_from.len()
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::render::{
+        testing::{rendered, rendered_with},
+        Glyphs,
+    };
+
+    #[test]
+    fn spans() {
+        let span = |columns, rows| Span { columns, rows };
+        let mut slots = Slots::default();
+        // | a (2 rows) | b (2 columns)  |
+        // |            | c | d (rest)   |
+        // | e          | f |            |
+        let placed = slots.place_group(&[
+            vec![span(1, Some(2)), span(2, Some(1))],
+            vec![span(1, Some(1)), span(1, None)],
+            vec![span(1, Some(1)), span(1, Some(1))],
+        ]);
+        let at = |row: usize, cell: usize| {
+            let p = placed[row][cell];
+            (p.row, p.column, p.rows, p.columns)
+        };
+        assert_eq!(at(0, 0), (0, 0, 2, 1));
+        assert_eq!(at(0, 1), (0, 1, 1, 2));
+        assert_eq!(at(1, 0), (1, 1, 1, 1));
+        assert_eq!(at(1, 1), (1, 2, 2, 1));
+        assert_eq!(at(2, 0), (2, 0, 1, 1));
+        assert_eq!(at(2, 1), (2, 1, 1, 1));
+        assert_eq!((slots.rows(), slots.columns()), (3, 3));
+
+        // a new row group starts below every span of the previous one
+        let placed = slots.place_group(&[vec![span(1, Some(5))]]);
+        assert_eq!(placed[0][0].row, 3);
+        assert_eq!(placed[0][0].rows, 1);
+    }
+
+    #[test]
+    fn tables() {
+        let table = r#"<table>
+            <caption>crates</caption>
+            <tr><th>name</th><th>version</th></tr>
+            <tr><td colspan="2">taffy</td></tr>
+            <td>loose</td>
+        </table>"#;
+        let cells = "td { border: 1tb solid; } th { border: 1tb solid; }";
+        assert_eq!(
+            rendered(table, cells, (20, 11)),
+            [
+                "crates",
+                "┌─────┐┌───────┐",
+                "│name ││version│",
+                "└─────┘└───────┘",
+                "┌──────────────┐",
+                "│taffy         │",
+                "└──────────────┘",
+                "┌─────┐",
+                "│loose│",
+                "└─────┘",
+                "",
+            ]
+        );
+
+        let table = "<table><tr><td>a</td><td>b</td></tr></table>";
+        let cells = "table { border-spacing: 1tb 0; } td { border: 1tb solid; }";
+        assert_eq!(
+            rendered(table, cells, (8, 3)),
+            ["┌─┐ ┌─┐", "│a│ │b│", "└─┘ └─┘"]
+        );
+
+        // collapsed borders are shared by neighbouring cells
+        let table = r#"<table>
+            <tr><td rowspan="2">a</td><td>b</td></tr>
+            <tr><td>c</td></tr>
+        </table>"#;
+        let cells = "table { border-collapse: collapse; } td { border: 1tb solid; }";
+        assert_eq!(
+            rendered(table, cells, (8, 5)),
+            ["┌─┬─┐", "│a│b│", "│ ├─┤", "│ │c│", "└─┴─┘"]
+        );
+        assert_eq!(
+            rendered_with(table, cells, (8, 5), Glyphs::Ascii),
+            ["+-+-+", "|a|b|", "| +-+", "| |c|", "+-+-+"]
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use std::borrow::Cow;
+
+use textwrap::core::display_width;
+
+use super::computed_style::WhiteSpace;
+
+/// applies white-space processing to the text of a box:
+/// runs of whitespace become a single space (newlines too, unless `pre-line`),
+/// and whitespace at the start and end is removed, since a text run starts and ends a line for now
+pub fn collapse(text: &str, white_space: WhiteSpace) -> Cow<'_, str> {
+    let keep_newlines = match white_space {
+        WhiteSpace::Normal | WhiteSpace::Nowrap => false,
+        WhiteSpace::PreLine => true,
+        WhiteSpace::Pre | WhiteSpace::PreWrap | WhiteSpace::BreakSpaces => {
+            return Cow::Borrowed(text)
+        }
+    };
+
+    let collapse_line = |line: &str| line.split_whitespace().collect::<Vec<_>>().join(" ");
+    match keep_newlines {
+        true => Cow::Owned(
+            text.trim()
+                .lines()
+                .map(collapse_line)
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+        false => Cow::Owned(collapse_line(text)),
+    }
+}
+
+/// the lines that `text` is broken into in a box `width` cells wide, or only at newlines without a width
+pub fn lines(text: &str, width: Option<usize>, white_space: WhiteSpace) -> Vec<Cow<'_, str>> {
+    let wraps = !matches!(white_space, WhiteSpace::Nowrap | WhiteSpace::Pre);
+    let mut lines = vec![];
+    for line in text.split('\n') {
+        match width {
+            Some(width) if wraps && display_width(line) > width => {
+                lines.extend(textwrap::wrap(line, width.max(1)));
+            }
+            _ => lines.push(Cow::Borrowed(line)),
+        }
+    }
+    lines
+}
+
+/// the width of the longest line, which is all the room `text` takes up
+pub fn max_content_width(text: &str) -> usize {
+    text.split('\n').map(display_width).max().unwrap_or(0)
+}
+
+/// the width of the longest word, which is the narrowest `text` can get without overflowing
+pub fn min_content_width(text: &str, white_space: WhiteSpace) -> usize {
+    match white_space {
+        WhiteSpace::Nowrap | WhiteSpace::Pre => max_content_width(text),
+        _ => text
+            .split(char::is_whitespace)
+            .map(display_width)
+            .max()
+            .unwrap_or(0),
+    }
+}
+
+/// the size of a box that only holds `text`, for taffy's measure function
+pub fn measure(
+    text: &str,
+    white_space: WhiteSpace,
+    known_dimensions: taffy::Size<Option<f32>>,
+    available_space: taffy::Size<taffy::AvailableSpace>,
+) -> taffy::Size<f32> {
+    use taffy::AvailableSpace;
+
+    let max_content = max_content_width(text) as f32;
+    let width = known_dimensions
+        .width
+        .unwrap_or(match available_space.width {
+            AvailableSpace::MinContent => min_content_width(text, white_space) as f32,
+            AvailableSpace::MaxContent => max_content,
+            AvailableSpace::Definite(width) => max_content.min(width),
+        });
+
+    let lines = lines(text, Some(width.max(0.0) as usize), white_space);
+    let width = known_dimensions.width.unwrap_or_else(|| {
+        let widest = lines.iter().map(|line| display_width(line)).max();
+        widest.unwrap_or(0) as f32
+    });
+    let height = known_dimensions.height.unwrap_or(lines.len() as f32);
+    taffy::Size { width, height }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrapping() {
+        let text = collapse("  a  table\n of\tcontents ", WhiteSpace::Normal);
+        assert_eq!(text, "a table of contents");
+        assert_eq!(min_content_width(&text, WhiteSpace::Normal), 8);
+        assert_eq!(max_content_width(&text), 19);
+        assert_eq!(
+            lines(&text, Some(10), WhiteSpace::Normal),
+            ["a table of", "contents"]
+        );
+        assert_eq!(lines(&text, Some(10), WhiteSpace::Nowrap).len(), 1);
+
+        let pre = collapse(" a\n  b", WhiteSpace::Pre);
+        assert_eq!(lines(&pre, Some(1), WhiteSpace::Pre), [" a", "  b"]);
+        assert_eq!(collapse(" a \n  b c", WhiteSpace::PreLine), "a\nb c");
+    }
+}